@@ -0,0 +1,200 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+use async_trait::async_trait;
+use linera_sdk::{
+    ApplicationCallResult,
+    base::{SessionId, WithContractAbi}, CalleeContext, Contract, ExecutionResult, MessageContext,
+    OperationContext, SessionCallResult, ViewStateStorage,
+};
+use linera_sdk::base::{Amount, ChainId};
+use linera_sdk::contract::system_api;
+use thiserror::Error;
+
+use airdrop::{Hash, MessageOpt, OperationOpt};
+use fungible::{Account, AccountOwner, ResponseOpt};
+
+use self::state::Airdrop;
+
+mod state;
+/*
+空投合约入口文件
+*/
+
+linera_sdk::contract!(Airdrop);
+
+impl WithContractAbi for Airdrop {
+    type Abi = airdrop::AirdropAbi;
+}
+
+#[async_trait]
+impl Contract for Airdrop {
+    type Error = ContractError;
+    type Storage = ViewStateStorage<Self>;
+
+    // 合约部署时调用，部署者就是管理员
+    async fn initialize(
+        &mut self,
+        context: &OperationContext,
+        _argument: Self::InitializationArgument,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        self.admin.set(context.authenticated_signer);
+        Ok(ExecutionResult::default())
+    }
+
+    // 前端调用拦截转发
+    async fn execute_operation(
+        &mut self,
+        context: &OperationContext,
+        operation: Self::Operation,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        let signer = context.authenticated_signer.ok_or(ContractError::MissingSigner)?;
+        let home_chain_id = Self::home_chain_id();
+        let on_home_chain = system_api::current_chain_id() == home_chain_id;
+        match operation {
+            OperationOpt::SetMerkleRoot {root} => {
+                if !on_home_chain {
+                    return Err(ContractError::NotHomeChain);
+                }
+                if *self.admin.get() != Some(signer) {
+                    return Err(ContractError::NotAdmin);
+                }
+                self.set_merkle_root(root);
+                Ok(ExecutionResult::default())
+            }
+            OperationOpt::ClaimAirdrop {index,owner,amount,proof,target_account} => {
+                if owner != AccountOwner::User(signer) {
+                    return Err(ContractError::NotLeafOwner);
+                }
+                if !on_home_chain {
+                    let message = MessageOpt::ClaimAirdrop {index,owner,amount,proof,target_account};
+                    return Ok(ExecutionResult::default().with_authenticated_message(home_chain_id,message));
+                }
+                self.claim(index,owner,amount,&proof,target_account).await?;
+                Ok(ExecutionResult::default())
+            }
+        }
+    }
+
+    // 跨链相互调用
+    async fn execute_message(
+        &mut self,
+        context: &MessageContext,
+        message: Self::Message,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        let signer = context.authenticated_signer.ok_or(ContractError::MissingSigner)?;
+        match message {
+            MessageOpt::ClaimAirdrop {index,owner,amount,proof,target_account} => {
+                // 消息执行失败会阻塞整个收件箱，所以无效的领取只记录日志然后忽略
+                let result = if owner == AccountOwner::User(signer) {
+                    self.claim(index,owner,amount,&proof,target_account).await
+                } else {
+                    Err(ContractError::NotLeafOwner)
+                };
+                if let Err(error) = result {
+                    log::warn!("忽略 {} 对空投 {} 的领取: {}",signer,index,error);
+                }
+            }
+        }
+        Ok(ExecutionResult::default())
+    }
+
+    async fn handle_application_call(
+        &mut self,
+        _context: &CalleeContext,
+        _call: Self::ApplicationCall,
+        _forwarded_sessions: Vec<SessionId>,
+    ) -> Result<ApplicationCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        Err(ContractError::ApplicationCallNotSupported)
+    }
+
+    async fn handle_session_call(
+        &mut self,
+        _context: &CalleeContext,
+        _session: Self::SessionState,
+        _call: Self::SessionCall,
+        _forwarded_sessions: Vec<SessionId>,
+    ) -> Result<SessionCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        Err(ContractError::SessionNotSupported)
+    }
+}
+
+impl Airdrop {
+    // 部署合约的链，空投的代币和领取记录都在这条链上
+    fn home_chain_id() -> ChainId {
+        system_api::current_application_id().creation.chain_id
+    }
+
+    // 验证证明，记录领取并从空投程序账户转给target_account
+    async fn claim(&mut self, index: u64, owner: AccountOwner, amount: Amount, proof: &[Hash], target_account: Account) -> Result<(), ContractError> {
+        let root = (*self.merkle_root.get()).ok_or(ContractError::MerkleRootNotSet)?;
+        if !airdrop::verify_proof(&root,airdrop::leaf_hash(index,&owner,amount),proof) {
+            return Err(ContractError::InvalidProof);
+        }
+        if self.is_claimed(index).await {
+            return Err(ContractError::AlreadyClaimed);
+        }
+        // 转账失败会让整个消息失败并堵住收件箱，所以记录领取之前先检查空投程序账户的余额，不够时和其它无效领取一样返回错误
+        let airdrop_owner = AccountOwner::Application(system_api::current_application_id());
+        let token_id = Self::parameters()?;
+        let (response, _) = self.call_application(true,token_id,&fungible::ApplicationCallOpt::Balance {owner: airdrop_owner},vec![]).await?;
+        let available = match response {
+            ResponseOpt::Amount(balance) => balance,
+            _ => Amount::default(),
+        };
+        if available < amount {
+            return Err(ContractError::InsufficientAirdropBalance {available, requested: amount});
+        }
+        self.set_claimed(index,amount).await;
+        // 目标账户在其它链上时fungible程序会发消息入账
+        let call = fungible::ApplicationCallOpt::Transfer {
+            owner: airdrop_owner,
+            amount,
+            target_account,
+            request_id: None,
+        };
+        self.call_application(true,token_id,&call,vec![]).await?;
+        Ok(())
+    }
+}
+
+// 自定义一些合约错误
+#[derive(Debug, Error)]
+pub enum ContractError {
+    #[error("Failed to deserialize BCS bytes")]
+    BcsError(#[from] bcs::Error),
+
+    #[error("Failed to deserialize JSON string")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Operation requires an authenticated signer")]
+    MissingSigner,
+
+    #[error("Operation must be executed on the airdrop home chain")]
+    NotHomeChain,
+
+    #[error("Only the admin can set the Merkle root")]
+    NotAdmin,
+
+    // 只能领取自己的空投
+    #[error("Signer is not the owner of the airdrop leaf")]
+    NotLeafOwner,
+
+    #[error("Merkle root has not been set")]
+    MerkleRootNotSet,
+
+    #[error("Invalid Merkle proof")]
+    InvalidProof,
+
+    #[error("Airdrop has already been claimed")]
+    AlreadyClaimed,
+
+    // 空投程序账户里的代币不够这次领取
+    #[error("Insufficient airdrop balance: requested {requested}, available {available}")]
+    InsufficientAirdropBalance { available: Amount, requested: Amount },
+
+    #[error("Application calls not supported")]
+    ApplicationCallNotSupported,
+
+    #[error("Session not supported")]
+    SessionNotSupported,
+}
@@ -0,0 +1,139 @@
+use std::fmt;
+use std::str::FromStr;
+
+use async_graphql::{Request, Response, scalar};
+use linera_sdk::base::{Amount, ApplicationId, ContractAbi, ServiceAbi};
+use linera_sdk::graphql::GraphQLMutationRoot;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha3::{Digest, Sha3_256};
+use fungible::{Account, AccountOwner, FungibleTokenAbi};
+
+/*
+默克尔空投程序上下文相关定义文件
+*/
+
+pub struct AirdropAbi;
+
+// 合约写入相关Abi
+impl ContractAbi for AirdropAbi {
+    // 参数是空投的fungible程序ID
+    type Parameters = ApplicationId<FungibleTokenAbi>;
+    type InitializationArgument = ();
+    type Operation = OperationOpt;
+    type Message = MessageOpt;
+    type ApplicationCall = ();
+    type SessionCall = ();
+    type SessionState = ();
+    type Response = ();
+}
+
+// 合约查询相关Abi
+impl ServiceAbi for AirdropAbi {
+    type Parameters = ApplicationId<FungibleTokenAbi>;
+    type Query = Request;
+    type QueryResponse = Response;
+}
+
+/*
+操作枚举(注意：空投的代币是空投程序在部署链上的程序账户中的余额，在其它链上领取会把签名的消息发到部署链)
+*/
+#[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
+pub enum OperationOpt {
+    // 设置默克尔根(仅管理员，重新设置会清空领取记录)
+    SetMerkleRoot {
+        root: Hash
+    },
+    // 领取空投：index、owner、amount是叶子的内容，proof是从叶子到根的兄弟节点
+    ClaimAirdrop {
+        index: u64,
+        owner: AccountOwner,
+        amount: Amount,
+        proof: Vec<Hash>,
+        target_account: Account
+    }
+}
+
+// 消息枚举(注意：这个消息是签名发送的，签名者必须是叶子中的owner)
+#[derive(Debug, Deserialize, Serialize)]
+pub enum MessageOpt {
+    ClaimAirdrop {
+        index: u64,
+        owner: AccountOwner,
+        amount: Amount,
+        proof: Vec<Hash>,
+        target_account: Account
+    }
+}
+
+// 这个是graphql解析使用(注意：JSON中是64位十六进制字符串)
+scalar!(Hash);
+
+// sha3-256哈希
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Hash(pub [u8; 32]);
+
+impl fmt::Display for Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Hash {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 64 || !s.is_ascii() {
+            return Err(format!("Invalid hash: {}", s));
+        }
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).map_err(|_| format!("Invalid hash: {}", s))?;
+        }
+        Ok(Hash(bytes))
+    }
+}
+
+impl Serialize for Hash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_string())
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Hash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            s.parse().map_err(serde::de::Error::custom)
+        } else {
+            Ok(Hash(<[u8; 32]>::deserialize(deserializer)?))
+        }
+    }
+}
+
+// 叶子哈希：sha3(bcs(index, owner, amount))
+pub fn leaf_hash(index: u64, owner: &AccountOwner, amount: Amount) -> Hash {
+    let bytes = bcs::to_bytes(&(index, owner, amount)).expect("序列化叶子失败!");
+    Hash(Sha3_256::digest(bytes).into())
+}
+
+// 父节点哈希：两个子节点按大小排序后拼接再哈希，所以证明中不需要左右方向
+pub fn hash_pair(a: &Hash, b: &Hash) -> Hash {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut hasher = Sha3_256::new();
+    hasher.update(first.0);
+    hasher.update(second.0);
+    Hash(hasher.finalize().into())
+}
+
+// 验证叶子和证明能不能算出默克尔根
+pub fn verify_proof(root: &Hash, leaf: Hash, proof: &[Hash]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| hash_pair(&node, sibling));
+    computed == *root
+}
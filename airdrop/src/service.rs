@@ -0,0 +1,76 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+mod state;
+
+use self::state::Airdrop;
+use async_trait::async_trait;
+use linera_sdk::{base::WithServiceAbi, QueryContext, Service, ViewStateStorage};
+use std::sync::Arc;
+use async_graphql::{EmptySubscription, MergedObject, Object, Response, Schema};
+use linera_sdk::base::Amount;
+use linera_sdk::graphql::GraphQLMutationRoot;
+use thiserror::Error;
+use airdrop::{Hash, OperationOpt};
+use fungible::AccountOwner;
+
+/*
+查询服务相关实现(就是定义一些只读的数据给前端调用)
+*/
+
+linera_sdk::service!(Airdrop);
+
+impl WithServiceAbi for Airdrop {
+    type Abi = airdrop::AirdropAbi;
+}
+
+#[async_trait]
+impl Service for Airdrop {
+    type Error = ServiceError;
+    type Storage = ViewStateStorage<Self>;
+
+    async fn handle_query(
+        self: Arc<Self>,
+        _context: &QueryContext,
+        request: Self::Query,
+    ) -> Result<Response, Self::Error> {
+        let query_root = QueryRoot(self.clone(), ClaimQuery { state: self.clone() });
+        let schema = Schema::build(query_root,OperationOpt::mutation_root(),EmptySubscription).finish();
+        let response = schema.execute(request).await;
+        Ok(response)
+    }
+}
+
+#[derive(MergedObject)]
+struct QueryRoot(Arc<Airdrop>, ClaimQuery);
+
+struct ClaimQuery {
+    state: Arc<Airdrop>,
+}
+
+#[Object]
+impl ClaimQuery {
+    // 某个叶子是否已经领取
+    async fn is_claimed(&self, index: u64) -> bool {
+        self.state.is_claimed(index).await
+    }
+
+    // 检查证明是否有效并且还没有领取(注意：领取前可以先用这个查询确认)
+    async fn check_claim(&self, index: u64, owner: AccountOwner, amount: Amount, proof: Vec<Hash>) -> bool {
+        match *self.state.merkle_root.get() {
+            Some(root) => {
+                airdrop::verify_proof(&root, airdrop::leaf_hash(index, &owner, amount), &proof)
+                    && !self.state.is_claimed(index).await
+            }
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ServiceError {
+    #[error("Queries not supported by application")]
+    QueriesNotSupported,
+
+    #[error("Invalid query argument; could not deserialize request")]
+    InvalidQuery(#[from] serde_json::Error),
+}
@@ -0,0 +1,54 @@
+use linera_sdk::base::{Amount, Owner};
+use linera_sdk::views::{MapView, RegisterView, ViewStorageContext};
+use linera_views::views::{GraphQLView, RootView, View};
+use airdrop::Hash;
+
+/*
+状态文件定义数据结构以及应用核心逻辑实现(注意：状态只在部署合约的链上有意义)
+*/
+
+#[derive(RootView, GraphQLView)]
+#[view(context = "ViewStorageContext")]
+pub struct Airdrop {
+    /* 管理员，可以设置默克尔根 */
+    pub admin: RegisterView<Option<Owner>>,
+    /* 默克尔根 */
+    pub merkle_root: RegisterView<Option<Hash>>,
+    /* 领取记录位图：键是index / 64，值的第index % 64位表示是否已经领取 */
+    pub claimed: MapView<u64, u64>,
+    /* 已经领取的总量 */
+    pub total_claimed: RegisterView<Amount>,
+}
+
+#[allow(dead_code)]//该注解用于去除未使用警告
+impl Airdrop {
+    // 设置默克尔根并清空领取记录
+    pub fn set_merkle_root(&mut self, root: Hash) {
+        self.merkle_root.set(Some(root));
+        self.claimed.clear();
+        self.total_claimed.set(Amount::default());
+    }
+
+    // 是否已经领取
+    pub async fn is_claimed(&self, index: u64) -> bool {
+        let word = self.claimed
+            .get(&(index / 64))
+            .await
+            .expect("获取领取记录失败!")
+            .unwrap_or_default();
+        word & (1 << (index % 64)) != 0
+    }
+
+    // 记录领取
+    pub async fn set_claimed(&mut self, index: u64, amount: Amount) {
+        let word = self.claimed
+            .get(&(index / 64))
+            .await
+            .expect("获取领取记录失败!")
+            .unwrap_or_default();
+        self.claimed
+            .insert(&(index / 64), word | (1 << (index % 64)))
+            .expect("记录领取失败!");
+        self.total_claimed.get_mut().saturating_add_assign(amount);
+    }
+}
@@ -0,0 +1,128 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::panic::AssertUnwindSafe;
+
+use futures::FutureExt;
+use linera_sdk::base::{Amount, Owner};
+use linera_sdk::test::TestValidator;
+use airdrop::{AirdropAbi, OperationOpt};
+use fungible::{Account, AccountOwner, FungibleTokenAbi};
+use fungible_client::{ActiveChainTransport, FungibleClient};
+
+#[tokio::test]
+async fn test_merkle_claims_local_and_remote() {
+    let initial_amount = Amount::from(1_000_000u128);
+    let funded_amount = Amount::from(1_000u128);
+    // 模拟一个验证节点，当前项目就是空投程序
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    let mut home_chain = validator.new_chain().await;
+    let home_owner = AccountOwner::User(Owner::from(home_chain.public_key()));
+    let remote_chain = validator.new_chain().await;
+    let remote_owner = AccountOwner::User(Owner::from(remote_chain.public_key()));
+
+    let fungible_bytecode_id = home_chain.publish_bytecodes_in("../fungible").await;
+    let token_id = home_chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let airdrop_id = home_chain
+        .create_application::<AirdropAbi>(bytecode_id, token_id, (), vec![token_id.forget_abi()])
+        .await;
+    let vault = AccountOwner::Application(airdrop_id.forget_abi());
+
+    // 三个叶子的默克尔树：root = hash(hash(leaf0, leaf1), leaf2)
+    let leaf0 = airdrop::leaf_hash(0, &home_owner, Amount::from(100u128));
+    let leaf1 = airdrop::leaf_hash(1, &remote_owner, Amount::from(200u128));
+    let leaf2 = airdrop::leaf_hash(2, &vault, Amount::from(300u128));
+    let root = airdrop::hash_pair(&airdrop::hash_pair(&leaf0, &leaf1), &leaf2);
+
+    // 给空投程序账户充值，设置默克尔根，部署者领取自己的空投
+    home_chain.add_block(|block| {
+        block
+            .with_operation(
+                token_id,
+                fungible::OperationOpt::Transfer {
+                    owner: home_owner,
+                    amount: funded_amount,
+                    target_account: Account { chain_id: home_chain.id(), owner: vault },
+                    request_id: None,
+                },
+            )
+            .with_operation(airdrop_id, OperationOpt::SetMerkleRoot { root })
+            .with_operation(
+                airdrop_id,
+                OperationOpt::ClaimAirdrop {
+                    index: 0,
+                    owner: home_owner,
+                    amount: Amount::from(100u128),
+                    proof: vec![leaf1, leaf2],
+                    target_account: Account { chain_id: home_chain.id(), owner: home_owner },
+                },
+            );
+    }).await;
+
+    // 重复领取时整个区块被拒绝
+    let result = AssertUnwindSafe(home_chain.add_block(|block| {
+        block.with_operation(
+            airdrop_id,
+            OperationOpt::ClaimAirdrop {
+                index: 0,
+                owner: home_owner,
+                amount: Amount::from(100u128),
+                proof: vec![leaf1, leaf2],
+                target_account: Account { chain_id: home_chain.id(), owner: home_owner },
+            },
+        );
+    })).catch_unwind().await;
+    assert!(result.is_err());
+
+    // 另一条链上的用户领取到自己的链上
+    remote_chain.register_application(airdrop_id).await;
+    remote_chain.add_block(|block| {
+        block.with_operation(
+            airdrop_id,
+            OperationOpt::ClaimAirdrop {
+                index: 1,
+                owner: remote_owner,
+                amount: Amount::from(200u128),
+                proof: vec![leaf0, leaf2],
+                target_account: Account { chain_id: remote_chain.id(), owner: remote_owner },
+            },
+        );
+    }).await;
+    home_chain.handle_received_messages().await;
+    remote_chain.handle_received_messages().await;
+
+    let home_token = FungibleClient::new(ActiveChainTransport::new(&home_chain, token_id));
+    assert_eq!(
+        home_token.execute(fungible_client::balance(home_owner)).await.unwrap(),
+        initial_amount.saturating_sub(funded_amount).saturating_add(Amount::from(100u128))
+    );
+    let remote_token = FungibleClient::new(ActiveChainTransport::new(&remote_chain, token_id));
+    assert_eq!(remote_token.execute(fungible_client::balance(remote_owner)).await.unwrap(), Amount::from(200u128));
+    assert_eq!(home_token.execute(fungible_client::balance(vault)).await.unwrap(), Amount::from(700u128));
+    let value = home_chain.graphql_query(airdrop_id, String::from("query { isClaimed(index: 1) }")).await;
+    assert_eq!(value["isClaimed"].as_bool(), Some(true));
+
+    // 空投账户余额不够时其它链上的领取被忽略，不记录领取，也不会堵住部署链的收件箱
+    let large_leaf = airdrop::leaf_hash(0, &remote_owner, Amount::from(10_000u128));
+    let large_root = airdrop::hash_pair(&large_leaf, &leaf2);
+    home_chain.add_block(|block| {
+        block.with_operation(airdrop_id, OperationOpt::SetMerkleRoot { root: large_root });
+    }).await;
+    remote_chain.add_block(|block| {
+        block.with_operation(
+            airdrop_id,
+            OperationOpt::ClaimAirdrop {
+                index: 0,
+                owner: remote_owner,
+                amount: Amount::from(10_000u128),
+                proof: vec![leaf2],
+                target_account: Account { chain_id: remote_chain.id(), owner: remote_owner },
+            },
+        );
+    }).await;
+    home_chain.handle_received_messages().await;
+    let value = home_chain.graphql_query(airdrop_id, String::from("query { isClaimed(index: 0) }")).await;
+    assert_eq!(value["isClaimed"].as_bool(), Some(false));
+    assert_eq!(home_token.execute(fungible_client::balance(vault)).await.unwrap(), Amount::from(700u128));
+}
@@ -0,0 +1,219 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+use async_trait::async_trait;
+use linera_sdk::{
+    ApplicationCallResult,
+    base::{SessionId, WithContractAbi}, CalleeContext, Contract, ExecutionResult, MessageContext,
+    OperationContext, SessionCallResult, ViewStateStorage,
+};
+use linera_sdk::base::{Amount, ApplicationId, ChainId, Owner};
+use linera_sdk::contract::system_api;
+use thiserror::Error;
+
+use amm::{OperationOpt, PoolToken};
+use fungible::{Account, AccountOwner, FungibleTokenAbi};
+
+use self::state::{Pool, PoolError};
+
+mod state;
+/*
+AMM合约入口文件
+*/
+
+linera_sdk::contract!(Pool);
+
+impl WithContractAbi for Pool {
+    type Abi = amm::AmmAbi;
+}
+
+#[async_trait]
+impl Contract for Pool {
+    type Error = ContractError;
+    type Storage = ViewStateStorage<Self>;
+
+    // 合约部署时调用，检查交易对参数
+    async fn initialize(
+        &mut self,
+        _context: &OperationContext,
+        _argument: Self::InitializationArgument,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        let parameters = Self::parameters()?;
+        if parameters.token0 == parameters.token1 {
+            return Err(ContractError::IdenticalTokens);
+        }
+        if u128::from(parameters.fee_basis_points) >= amm::FEE_DENOMINATOR {
+            return Err(ContractError::InvalidFee);
+        }
+        Ok(ExecutionResult::default())
+    }
+
+    // 前端调用拦截转发
+    async fn execute_operation(
+        &mut self,
+        context: &OperationContext,
+        operation: Self::Operation,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        let signer = context.authenticated_signer.ok_or(ContractError::MissingSigner)?;
+        // 交易池的代币账户在部署合约的链上，在其它链上储备和余额对不上
+        if system_api::current_chain_id() != Self::home_chain_id() {
+            return Err(ContractError::NotHomeChain);
+        }
+        match operation {
+            OperationOpt::AddLiquidity {amount0,amount1,min_shares} => {
+                let quote = self.quote_add_liquidity(amount0,amount1)?;
+                if quote.shares < min_shares {
+                    return Err(ContractError::SlippageExceeded);
+                }
+                // 把签名者的代币转到交易池的程序账户
+                self.pull(PoolToken::Token0,signer,quote.amount0).await?;
+                self.pull(PoolToken::Token1,signer,quote.amount1).await?;
+                self.add_liquidity(signer,quote).await;
+                log::debug!("{} 添加流动性 {} {} 获得份额 {}",signer,quote.amount0,quote.amount1,quote.shares);
+                Ok(ExecutionResult::default())
+            }
+            OperationOpt::RemoveLiquidity {shares,min_amount0,min_amount1} => {
+                let quote = self.quote_remove_liquidity(shares)?;
+                if quote.amount0 < min_amount0 || quote.amount1 < min_amount1 {
+                    return Err(ContractError::SlippageExceeded);
+                }
+                self.remove_liquidity(signer,quote).await?;
+                self.pay(PoolToken::Token0,signer,quote.amount0).await?;
+                self.pay(PoolToken::Token1,signer,quote.amount1).await?;
+                Ok(ExecutionResult::default())
+            }
+            OperationOpt::Swap {token_in,amount_in,min_amount_out} => {
+                let fee_basis_points = Self::parameters()?.fee_basis_points;
+                let amount_out = self.quote_swap(token_in,amount_in,fee_basis_points)?;
+                if amount_out < min_amount_out {
+                    return Err(ContractError::SlippageExceeded);
+                }
+                self.pull(token_in,signer,amount_in).await?;
+                self.swap(token_in,amount_in,amount_out)?;
+                self.pay(Self::other(token_in),signer,amount_out).await?;
+                log::debug!("{} 用 {} 换出 {}",signer,amount_in,amount_out);
+                Ok(ExecutionResult::default())
+            }
+        }
+    }
+
+    async fn execute_message(
+        &mut self,
+        _context: &MessageContext,
+        _message: Self::Message,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        Err(ContractError::MessagesNotSupported)
+    }
+
+    async fn handle_application_call(
+        &mut self,
+        _context: &CalleeContext,
+        _call: Self::ApplicationCall,
+        _forwarded_sessions: Vec<SessionId>,
+    ) -> Result<ApplicationCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        Err(ContractError::ApplicationCallNotSupported)
+    }
+
+    async fn handle_session_call(
+        &mut self,
+        _context: &CalleeContext,
+        _session: Self::SessionState,
+        _call: Self::SessionCall,
+        _forwarded_sessions: Vec<SessionId>,
+    ) -> Result<SessionCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        Err(ContractError::SessionNotSupported)
+    }
+}
+
+impl Pool {
+    // 部署合约的链，交易池的代币都在这条链上
+    fn home_chain_id() -> ChainId {
+        system_api::current_application_id().creation.chain_id
+    }
+
+    // 交易池在fungible程序中的账户
+    fn pool_account() -> Account {
+        Account {
+            chain_id: system_api::current_chain_id(),
+            owner: AccountOwner::Application(system_api::current_application_id()),
+        }
+    }
+
+    fn token_id(token: PoolToken) -> Result<ApplicationId<FungibleTokenAbi>, ContractError> {
+        let parameters = Self::parameters()?;
+        Ok(match token {
+            PoolToken::Token0 => parameters.token0,
+            PoolToken::Token1 => parameters.token1,
+        })
+    }
+
+    fn other(token: PoolToken) -> PoolToken {
+        match token {
+            PoolToken::Token0 => PoolToken::Token1,
+            PoolToken::Token1 => PoolToken::Token0,
+        }
+    }
+
+    // 从签名者的账户转入交易池(注意：签名会转发给fungible程序，由它验证签名者就是账户所有者)
+    async fn pull(&mut self, token: PoolToken, owner: Owner, amount: Amount) -> Result<(), ContractError> {
+        let call = fungible::ApplicationCallOpt::Transfer {
+            owner: AccountOwner::User(owner),
+            amount,
+            target_account: Self::pool_account(),
+            request_id: None,
+        };
+        self.call_application(true,Self::token_id(token)?,&call,vec![]).await?;
+        Ok(())
+    }
+
+    // 从交易池的程序账户转给签名者
+    async fn pay(&mut self, token: PoolToken, owner: Owner, amount: Amount) -> Result<(), ContractError> {
+        let call = fungible::ApplicationCallOpt::Transfer {
+            owner: Self::pool_account().owner,
+            amount,
+            target_account: Account { chain_id: system_api::current_chain_id(), owner: AccountOwner::User(owner) },
+            request_id: None,
+        };
+        self.call_application(true,Self::token_id(token)?,&call,vec![]).await?;
+        Ok(())
+    }
+}
+
+// 自定义一些合约错误
+#[derive(Debug, Error)]
+pub enum ContractError {
+    #[error("Failed to deserialize BCS bytes")]
+    BcsError(#[from] bcs::Error),
+
+    #[error("Failed to deserialize JSON string")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Pool tokens must be different applications")]
+    IdenticalTokens,
+
+    #[error("Fee must be less than 10000 basis points")]
+    InvalidFee,
+
+    #[error("Operation requires an authenticated signer")]
+    MissingSigner,
+
+    // 所有操作只能在部署合约的链上进行
+    #[error("Operation must be executed on the pool home chain")]
+    NotHomeChain,
+
+    // 报价低于调用方给的最小值
+    #[error("Slippage limit exceeded")]
+    SlippageExceeded,
+
+    // 如果碰到PoolError错误会自动转成Pool错误
+    #[error(transparent)]
+    Pool(#[from] PoolError),
+
+    #[error("Messages not supported")]
+    MessagesNotSupported,
+
+    #[error("Application calls not supported")]
+    ApplicationCallNotSupported,
+
+    #[error("Session not supported")]
+    SessionNotSupported,
+}
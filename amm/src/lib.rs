@@ -0,0 +1,92 @@
+use async_graphql::{Enum, Request, Response, SimpleObject};
+use linera_sdk::base::{Amount, ApplicationId, ContractAbi, ServiceAbi};
+use linera_sdk::graphql::GraphQLMutationRoot;
+use serde::{Deserialize, Serialize};
+use fungible::FungibleTokenAbi;
+
+/*
+恒定乘积做市商(AMM)上下文相关定义文件
+*/
+
+pub struct AmmAbi;
+
+// 合约写入相关Abi
+impl ContractAbi for AmmAbi {
+    // 参数是交易对的两个fungible程序ID和手续费
+    type Parameters = PoolParameters;
+    type InitializationArgument = ();
+    type Operation = OperationOpt;
+    type Message = ();
+    type ApplicationCall = ();
+    type SessionCall = ();
+    type SessionState = ();
+    type Response = ();
+}
+
+// 合约查询相关Abi
+impl ServiceAbi for AmmAbi {
+    type Parameters = PoolParameters;
+    type Query = Request;
+    type QueryResponse = Response;
+}
+
+// 手续费的分母，fee_basis_points是万分之几
+pub const FEE_DENOMINATOR: u128 = 10_000;
+
+// 价格查询的放大倍数
+pub const PRICE_SCALE: u128 = 1_000_000_000_000_000_000;
+
+// 交易池参数
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct PoolParameters {
+    pub token0: ApplicationId<FungibleTokenAbi>,
+    pub token1: ApplicationId<FungibleTokenAbi>,
+    // 兑换手续费(万分之几，例如30就是0.3%)，手续费留在池子里归流动性提供者所有
+    pub fee_basis_points: u16,
+}
+
+// 交易对中的哪一个代币
+#[derive(Clone, Copy, Debug, Deserialize, Enum, Eq, PartialEq, Serialize)]
+pub enum PoolToken {
+    Token0,
+    Token1,
+}
+
+// 操作枚举(注意：交易池的代币都在部署合约的链上，所有操作只能在这条链上进行)
+#[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
+pub enum OperationOpt {
+    // 添加流动性：amount0和amount1是最多愿意存入的数量，按当前价格比例存入，获得的份额不能少于min_shares
+    AddLiquidity {
+        amount0: Amount,
+        amount1: Amount,
+        min_shares: Amount
+    },
+    // 移除流动性：按份额比例取回两种代币，取回的数量不能少于min_amount0和min_amount1
+    RemoveLiquidity {
+        shares: Amount,
+        min_amount0: Amount,
+        min_amount1: Amount
+    },
+    // 兑换：存入token_in换出另一种代币，换出的数量不能少于min_amount_out(滑点保护)
+    Swap {
+        token_in: PoolToken,
+        amount_in: Amount,
+        min_amount_out: Amount
+    }
+}
+
+// 交易池储备
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, SimpleObject)]
+pub struct Reserves {
+    pub reserve0: Amount,
+    pub reserve1: Amount,
+    pub total_shares: Amount,
+}
+
+// 添加或移除流动性的报价
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize, SimpleObject)]
+pub struct LiquidityQuote {
+    pub amount0: Amount,
+    pub amount1: Amount,
+    pub shares: Amount,
+}
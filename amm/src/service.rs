@@ -0,0 +1,87 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+mod state;
+
+use self::state::Pool;
+use async_trait::async_trait;
+use linera_sdk::{base::WithServiceAbi, QueryContext, Service, ViewStateStorage};
+use std::sync::Arc;
+use async_graphql::{EmptySubscription, MergedObject, Object, Response, Schema};
+use linera_sdk::base::Amount;
+use linera_sdk::graphql::GraphQLMutationRoot;
+use thiserror::Error;
+use amm::{LiquidityQuote, OperationOpt, PoolToken, Reserves};
+
+/*
+查询服务相关实现(就是定义一些只读的数据给前端调用)
+*/
+
+linera_sdk::service!(Pool);
+
+impl WithServiceAbi for Pool {
+    type Abi = amm::AmmAbi;
+}
+
+#[async_trait]
+impl Service for Pool {
+    type Error = ServiceError;
+    type Storage = ViewStateStorage<Self>;
+
+    async fn handle_query(
+        self: Arc<Self>,
+        _context: &QueryContext,
+        request: Self::Query,
+    ) -> Result<Response, Self::Error> {
+        let fee_basis_points = Self::parameters()?.fee_basis_points;
+        let query_root = QueryRoot(self.clone(), QuoteQuery { state: self.clone(), fee_basis_points });
+        let schema = Schema::build(query_root,OperationOpt::mutation_root(),EmptySubscription).finish();
+        let response = schema.execute(request).await;
+        Ok(response)
+    }
+}
+
+#[derive(MergedObject)]
+struct QueryRoot(Arc<Pool>, QuoteQuery);
+
+// 报价相关查询
+struct QuoteQuery {
+    state: Arc<Pool>,
+    fee_basis_points: u16,
+}
+
+#[Object]
+impl QuoteQuery {
+    // 查询储备和份额总量
+    async fn reserves(&self) -> Reserves {
+        self.state.reserves()
+    }
+
+    // 当前价格：1个tokenIn能换多少另一种代币(放大了10^18倍)
+    async fn spot_price(&self, token_in: PoolToken) -> async_graphql::Result<Amount> {
+        Ok(self.state.spot_price(token_in)?)
+    }
+
+    // 兑换报价：扣除手续费后能换出多少另一种代币
+    async fn quote_swap(&self, token_in: PoolToken, amount_in: Amount) -> async_graphql::Result<Amount> {
+        Ok(self.state.quote_swap(token_in, amount_in, self.fee_basis_points)?)
+    }
+
+    // 添加流动性报价：实际存入的数量和获得的份额
+    async fn quote_add_liquidity(&self, amount0: Amount, amount1: Amount) -> async_graphql::Result<LiquidityQuote> {
+        Ok(self.state.quote_add_liquidity(amount0, amount1)?)
+    }
+
+    // 移除流动性报价：能取回的数量
+    async fn quote_remove_liquidity(&self, shares: Amount) -> async_graphql::Result<LiquidityQuote> {
+        Ok(self.state.quote_remove_liquidity(shares)?)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ServiceError {
+    #[error("Queries not supported by application")]
+    QueriesNotSupported,
+
+    #[error("Invalid query argument; could not deserialize request")]
+    InvalidQuery(#[from] serde_json::Error),
+}
@@ -0,0 +1,184 @@
+use linera_sdk::base::{Amount, Owner};
+use linera_sdk::views::{MapView, RegisterView, ViewStorageContext};
+use linera_views::views::{GraphQLView, RootView};
+use thiserror::Error;
+use amm::{FEE_DENOMINATOR, LiquidityQuote, PoolToken, PRICE_SCALE, Reserves};
+use fixed_math::{mul_div, sqrt_mul};
+
+/*
+状态文件定义数据结构以及应用核心逻辑实现(注意：储备是合约自己记的账，不是去fungible程序查询的余额)
+*/
+
+#[derive(RootView, GraphQLView)]
+#[view(context = "ViewStorageContext")]
+pub struct Pool {
+    /* token0的储备 */
+    pub reserve0: RegisterView<Amount>,
+    /* token1的储备 */
+    pub reserve1: RegisterView<Amount>,
+    /* 流动性份额总量 */
+    pub total_shares: RegisterView<Amount>,
+    /* 每个流动性提供者的份额 */
+    pub shares: MapView<Owner, Amount>,
+}
+
+// 定义交易池异常
+#[derive(Clone, Copy, Debug, Error)]
+pub enum PoolError {
+    #[error("Amount must be greater than zero")]
+    ZeroAmount,
+
+    #[error("Insufficient liquidity in the pool")]
+    InsufficientLiquidity,
+
+    #[error("Insufficient liquidity shares")]
+    InsufficientShares,
+
+    #[error("Arithmetic overflow")]
+    Overflow,
+}
+
+#[allow(dead_code)]//该注解用于去除未使用警告
+impl Pool {
+    // 当前储备
+    pub fn reserves(&self) -> Reserves {
+        Reserves {
+            reserve0: *self.reserve0.get(),
+            reserve1: *self.reserve1.get(),
+            total_shares: *self.total_shares.get(),
+        }
+    }
+
+    // 某个流动性提供者的份额
+    pub async fn shares_of(&self, owner: &Owner) -> Amount {
+        self.shares
+            .get(owner)
+            .await
+            .expect("获取份额失败!")
+            .unwrap_or_default()
+    }
+
+    // 添加流动性报价：第一次添加份额是sqrt(amount0 * amount1)，之后按当前价格比例存入，多余的部分不存入
+    pub fn quote_add_liquidity(&self, amount0: Amount, amount1: Amount) -> Result<LiquidityQuote, PoolError> {
+        let (amount0, amount1) = (u128::from(amount0), u128::from(amount1));
+        if amount0 == 0 || amount1 == 0 {
+            return Err(PoolError::ZeroAmount);
+        }
+        let reserves = self.reserves();
+        let (reserve0, reserve1) = (u128::from(reserves.reserve0), u128::from(reserves.reserve1));
+        let total_shares = u128::from(reserves.total_shares);
+        let (used0, used1, shares) = if total_shares == 0 {
+            (amount0, amount1, sqrt_mul(amount0, amount1))
+        } else {
+            let optimal1 = mul_div(amount0, reserve1, reserve0).ok_or(PoolError::Overflow)?;
+            let (used0, used1) = if optimal1 <= amount1 {
+                (amount0, optimal1)
+            } else {
+                (mul_div(amount1, reserve0, reserve1).ok_or(PoolError::Overflow)?, amount1)
+            };
+            let shares0 = mul_div(used0, total_shares, reserve0).ok_or(PoolError::Overflow)?;
+            let shares1 = mul_div(used1, total_shares, reserve1).ok_or(PoolError::Overflow)?;
+            (used0, used1, shares0.min(shares1))
+        };
+        if shares == 0 {
+            return Err(PoolError::ZeroAmount);
+        }
+        Ok(LiquidityQuote {
+            amount0: Amount::from(used0),
+            amount1: Amount::from(used1),
+            shares: Amount::from(shares),
+        })
+    }
+
+    // 移除流动性报价：按份额比例取回两种代币
+    pub fn quote_remove_liquidity(&self, shares: Amount) -> Result<LiquidityQuote, PoolError> {
+        let reserves = self.reserves();
+        if shares == Amount::default() {
+            return Err(PoolError::ZeroAmount);
+        }
+        if shares > reserves.total_shares {
+            return Err(PoolError::InsufficientShares);
+        }
+        let total_shares = u128::from(reserves.total_shares);
+        let amount0 = mul_div(u128::from(shares), u128::from(reserves.reserve0), total_shares).ok_or(PoolError::Overflow)?;
+        let amount1 = mul_div(u128::from(shares), u128::from(reserves.reserve1), total_shares).ok_or(PoolError::Overflow)?;
+        Ok(LiquidityQuote {
+            amount0: Amount::from(amount0),
+            amount1: Amount::from(amount1),
+            shares,
+        })
+    }
+
+    // 兑换报价：扣除手续费后按 x * y = k 计算换出的数量
+    pub fn quote_swap(&self, token_in: PoolToken, amount_in: Amount, fee_basis_points: u16) -> Result<Amount, PoolError> {
+        if amount_in == Amount::default() {
+            return Err(PoolError::ZeroAmount);
+        }
+        let (reserve_in, reserve_out) = self.directed_reserves(token_in);
+        let (reserve_in, reserve_out) = (u128::from(reserve_in), u128::from(reserve_out));
+        if reserve_in == 0 || reserve_out == 0 {
+            return Err(PoolError::InsufficientLiquidity);
+        }
+        let fee_factor = FEE_DENOMINATOR.saturating_sub(u128::from(fee_basis_points));
+        let amount_in_with_fee = mul_div(u128::from(amount_in), fee_factor, FEE_DENOMINATOR).ok_or(PoolError::Overflow)?;
+        let denominator = reserve_in.checked_add(amount_in_with_fee).ok_or(PoolError::Overflow)?;
+        let amount_out = mul_div(reserve_out, amount_in_with_fee, denominator).ok_or(PoolError::Overflow)?;
+        if amount_out == 0 {
+            return Err(PoolError::InsufficientLiquidity);
+        }
+        Ok(Amount::from(amount_out))
+    }
+
+    // 当前价格：1个token_in能换多少另一种代币(不含手续费和滑点，放大了10^18倍)
+    pub fn spot_price(&self, token_in: PoolToken) -> Result<Amount, PoolError> {
+        let (reserve_in, reserve_out) = self.directed_reserves(token_in);
+        if reserve_in == Amount::default() {
+            return Err(PoolError::InsufficientLiquidity);
+        }
+        let price = mul_div(u128::from(reserve_out), PRICE_SCALE, u128::from(reserve_in)).ok_or(PoolError::Overflow)?;
+        Ok(Amount::from(price))
+    }
+
+    // 添加流动性，更新储备和份额
+    pub async fn add_liquidity(&mut self, owner: Owner, quote: LiquidityQuote) {
+        self.reserve0.get_mut().saturating_add_assign(quote.amount0);
+        self.reserve1.get_mut().saturating_add_assign(quote.amount1);
+        self.total_shares.get_mut().saturating_add_assign(quote.shares);
+        let mut shares = self.shares_of(&owner).await;
+        shares.saturating_add_assign(quote.shares);
+        self.shares.insert(&owner, shares).expect("记录份额失败!");
+    }
+
+    // 移除流动性，更新储备和份额
+    pub async fn remove_liquidity(&mut self, owner: Owner, quote: LiquidityQuote) -> Result<(), PoolError> {
+        let mut shares = self.shares_of(&owner).await;
+        shares.try_sub_assign(quote.shares).map_err(|_| PoolError::InsufficientShares)?;
+        if shares == Amount::default() {
+            self.shares.remove(&owner).expect("删除份额失败!");
+        } else {
+            self.shares.insert(&owner, shares).expect("记录份额失败!");
+        }
+        self.total_shares.get_mut().try_sub_assign(quote.shares).map_err(|_| PoolError::InsufficientShares)?;
+        self.reserve0.get_mut().try_sub_assign(quote.amount0).map_err(|_| PoolError::InsufficientLiquidity)?;
+        self.reserve1.get_mut().try_sub_assign(quote.amount1).map_err(|_| PoolError::InsufficientLiquidity)?;
+        Ok(())
+    }
+
+    // 兑换，更新储备
+    pub fn swap(&mut self, token_in: PoolToken, amount_in: Amount, amount_out: Amount) -> Result<(), PoolError> {
+        let (reserve_in, reserve_out) = match token_in {
+            PoolToken::Token0 => (&mut self.reserve0, &mut self.reserve1),
+            PoolToken::Token1 => (&mut self.reserve1, &mut self.reserve0),
+        };
+        reserve_in.get_mut().saturating_add_assign(amount_in);
+        reserve_out.get_mut().try_sub_assign(amount_out).map_err(|_| PoolError::InsufficientLiquidity)
+    }
+
+    // 按兑换方向返回(存入代币的储备，换出代币的储备)
+    fn directed_reserves(&self, token_in: PoolToken) -> (Amount, Amount) {
+        match token_in {
+            PoolToken::Token0 => (*self.reserve0.get(), *self.reserve1.get()),
+            PoolToken::Token1 => (*self.reserve1.get(), *self.reserve0.get()),
+        }
+    }
+}
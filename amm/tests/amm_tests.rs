@@ -0,0 +1,108 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::panic::AssertUnwindSafe;
+
+use futures::FutureExt;
+use linera_sdk::base::{Amount, Owner};
+use linera_sdk::test::TestValidator;
+use amm::{AmmAbi, OperationOpt, PoolParameters, PoolToken};
+use fungible::{AccountOwner, FungibleTokenAbi};
+use fungible_client::{ActiveChainTransport, FungibleClient};
+
+#[tokio::test]
+async fn test_liquidity_and_swap() {
+    let initial_amount = Amount::from(1_000_000u128);
+    let liquidity_amount = Amount::from(100_000u128);
+    let swap_amount = Amount::from(10_000u128);
+    // 模拟一个验证节点，当前项目就是AMM
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    let mut chain = validator.new_chain().await;
+    let user = AccountOwner::User(Owner::from(chain.public_key()));
+    // 用同一份fungible字节码部署两个代币
+    let fungible_bytecode_id = chain.publish_bytecodes_in("../fungible").await;
+    let token0 = chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let token1 = chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let pool_id = chain
+        .create_application::<AmmAbi>(
+            bytecode_id,
+            PoolParameters { token0, token1, fee_basis_points: 30 },
+            (),
+            vec![token0.forget_abi(), token1.forget_abi()],
+        ).await;
+    let pool = AccountOwner::Application(pool_id.forget_abi());
+
+    // 第一次添加流动性，份额是sqrt(amount0 * amount1)
+    chain.add_block(|block| {
+        block.with_operation(
+            pool_id,
+            OperationOpt::AddLiquidity {
+                amount0: liquidity_amount,
+                amount1: liquidity_amount,
+                min_shares: liquidity_amount,
+            },
+        );
+    }).await;
+    let token0_client = FungibleClient::new(ActiveChainTransport::new(&chain, token0));
+    assert_eq!(token0_client.execute(fungible_client::balance(pool)).await.unwrap(), liquidity_amount);
+    let token1_client = FungibleClient::new(ActiveChainTransport::new(&chain, token1));
+    assert_eq!(token1_client.execute(fungible_client::balance(pool)).await.unwrap(), liquidity_amount);
+
+    // 报价和实际兑换结果一致：10000 * 0.997 = 9970，100000 * 9970 / (100000 + 9970)
+    let quote = chain
+        .graphql_query(pool_id, String::from("query { quoteSwap(tokenIn: Token0, amountIn: \"10000\") }"))
+        .await;
+    let expected_out = Amount::from(9_066u128);
+    assert_eq!(quote["quoteSwap"].as_str().unwrap().parse::<Amount>().unwrap(), expected_out);
+
+    // 最小换出数量高于报价时整个区块被拒绝
+    let result = AssertUnwindSafe(chain.add_block(|block| {
+        block.with_operation(
+            pool_id,
+            OperationOpt::Swap {
+                token_in: PoolToken::Token0,
+                amount_in: swap_amount,
+                min_amount_out: expected_out.saturating_add(Amount::from(1u128)),
+            },
+        );
+    })).catch_unwind().await;
+    assert!(result.is_err());
+
+    chain.add_block(|block| {
+        block.with_operation(
+            pool_id,
+            OperationOpt::Swap {
+                token_in: PoolToken::Token0,
+                amount_in: swap_amount,
+                min_amount_out: expected_out,
+            },
+        );
+    }).await;
+    assert_eq!(
+        token0_client.execute(fungible_client::balance(user)).await.unwrap(),
+        initial_amount.saturating_sub(liquidity_amount).saturating_sub(swap_amount)
+    );
+    assert_eq!(
+        token1_client.execute(fungible_client::balance(user)).await.unwrap(),
+        initial_amount.saturating_sub(liquidity_amount).saturating_add(expected_out)
+    );
+
+    // 移除全部流动性，取回池子里所有的代币
+    chain.add_block(|block| {
+        block.with_operation(
+            pool_id,
+            OperationOpt::RemoveLiquidity {
+                shares: liquidity_amount,
+                min_amount0: liquidity_amount.saturating_add(swap_amount),
+                min_amount1: liquidity_amount.saturating_sub(expected_out),
+            },
+        );
+    }).await;
+    assert_eq!(token0_client.execute(fungible_client::balance(pool)).await.unwrap(), Amount::default());
+    assert_eq!(token1_client.execute(fungible_client::balance(pool)).await.unwrap(), Amount::default());
+    assert_eq!(token0_client.execute(fungible_client::balance(user)).await.unwrap(), initial_amount);
+    assert_eq!(token1_client.execute(fungible_client::balance(user)).await.unwrap(), initial_amount);
+}
@@ -0,0 +1,332 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+use async_trait::async_trait;
+use linera_sdk::{
+    ApplicationCallResult,
+    base::{SessionId, WithContractAbi}, CalleeContext, Contract, ExecutionResult, MessageContext,
+    OperationContext, SessionCallResult, ViewStateStorage,
+};
+use linera_sdk::base::{Amount, ChainId};
+use linera_sdk::contract::system_api;
+use thiserror::Error;
+
+use bridge::{BridgeTransfer, Direction, MessageOpt, OperationOpt, Route, Ticket};
+use fungible::{Account, AccountOwner, ErrorCode, ResponseOpt};
+
+use self::state::{Bridge, NonceStatus};
+
+mod state;
+/*
+桥合约入口文件
+*/
+
+linera_sdk::contract!(Bridge);
+
+impl WithContractAbi for Bridge {
+    type Abi = bridge::BridgeAbi;
+}
+
+#[async_trait]
+impl Contract for Bridge {
+    type Error = ContractError;
+    type Storage = ViewStateStorage<Self>;
+
+    // 合约部署时调用，检查桥参数
+    async fn initialize(
+        &mut self,
+        _context: &OperationContext,
+        _argument: Self::InitializationArgument,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        let parameters = Self::parameters()?;
+        if parameters.source_token == parameters.wrapped_token {
+            return Err(ContractError::IdenticalTokens);
+        }
+        // 包装代币只能在它的部署链上铸造
+        if parameters.wrapped_token.forget_abi().creation.chain_id != system_api::current_chain_id() {
+            return Err(ContractError::WrongHomeChain);
+        }
+        Ok(ExecutionResult::default())
+    }
+
+    // 前端调用拦截转发
+    async fn execute_operation(
+        &mut self,
+        context: &OperationContext,
+        operation: Self::Operation,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        let signer = context.authenticated_signer.ok_or(ContractError::MissingSigner)?;
+        let parameters = Self::parameters()?;
+        let (direction, amount, target_account) = match operation {
+            OperationOpt::Lock {amount,target_account} => {
+                Self::check_amount(amount)?;
+                // 把签名者的源代币转到桥在部署合约的链上的程序账户
+                let call = fungible::ApplicationCallOpt::Transfer {
+                    owner: AccountOwner::User(signer),
+                    amount,
+                    target_account: Self::bridge_account(),
+                    request_id: None,
+                };
+                self.call_application(true,parameters.source_token,&call,vec![]).await?;
+                (Direction::Lock, amount, target_account)
+            }
+            OperationOpt::Burn {amount,target_account} => {
+                Self::check_amount(amount)?;
+                let call = fungible::ApplicationCallOpt::Burn {
+                    owner: AccountOwner::User(signer),
+                    amount,
+                    request_id: None,
+                };
+                self.call_application(true,parameters.wrapped_token,&call,vec![]).await?;
+                (Direction::Burn, amount, target_account)
+            }
+            OperationOpt::RetryRelease {origin,nonce} => {
+                self.retry(Route {origin,direction: Direction::Burn},nonce).await?;
+                return Ok(ExecutionResult::default());
+            }
+            OperationOpt::RetryMint {origin,nonce} => {
+                self.retry(Route {origin,direction: Direction::Lock},nonce).await?;
+                return Ok(ExecutionResult::default());
+            }
+        };
+        let nonce = self.next_nonce(direction).await;
+        let chain_id = system_api::current_chain_id();
+        let home_chain_id = Self::home_chain_id();
+        if chain_id == home_chain_id {
+            self.process(BridgeTransfer {origin: chain_id,direction,nonce,amount,target_account}).await?;
+            return Ok(ExecutionResult::default());
+        }
+        let message = match direction {
+            Direction::Lock => MessageOpt::MintRequest {nonce,amount,target_account},
+            Direction::Burn => MessageOpt::ReleaseRequest {nonce,amount,target_account},
+        };
+        Ok(ExecutionResult::default().with_message(home_chain_id,message))
+    }
+
+    // 跨链相互调用
+    async fn execute_message(
+        &mut self,
+        context: &MessageContext,
+        message: Self::Message,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        let origin = context.message_id.chain_id;
+        let transfer = match message {
+            MessageOpt::MintRequest {nonce,amount,target_account} => {
+                BridgeTransfer {origin,direction: Direction::Lock,nonce,amount,target_account}
+            }
+            MessageOpt::ReleaseRequest {nonce,amount,target_account} => {
+                BridgeTransfer {origin,direction: Direction::Burn,nonce,amount,target_account}
+            }
+        };
+        self.process(transfer).await?;
+        Ok(ExecutionResult::default())
+    }
+
+    async fn handle_application_call(
+        &mut self,
+        _context: &CalleeContext,
+        _call: Self::ApplicationCall,
+        _forwarded_sessions: Vec<SessionId>,
+    ) -> Result<ApplicationCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        Err(ContractError::ApplicationCallNotSupported)
+    }
+
+    async fn handle_session_call(
+        &mut self,
+        _context: &CalleeContext,
+        _session: Self::SessionState,
+        _call: Self::SessionCall,
+        _forwarded_sessions: Vec<SessionId>,
+    ) -> Result<SessionCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        Err(ContractError::SessionNotSupported)
+    }
+}
+
+impl Bridge {
+    // 部署合约的链，锁定的源代币和包装代币的铸造都在这条链上
+    fn home_chain_id() -> ChainId {
+        system_api::current_application_id().creation.chain_id
+    }
+
+    // 桥在fungible程序中的账户
+    fn bridge_account() -> Account {
+        Account {
+            chain_id: Self::home_chain_id(),
+            owner: AccountOwner::Application(system_api::current_application_id()),
+        }
+    }
+
+    fn check_amount(amount: Amount) -> Result<(), ContractError> {
+        if amount == Amount::default() {
+            return Err(ContractError::ZeroAmount);
+        }
+        Ok(())
+    }
+
+    // 在部署合约的链上按序号顺序处理请求
+    async fn process(&mut self, transfer: BridgeTransfer) -> Result<(), ContractError> {
+        // 消息执行失败会阻塞整个收件箱，所以重复的请求只记录日志然后忽略，提前到达的请求先保存起来
+        match self.nonce_status(&transfer).await {
+            NonceStatus::Duplicate => {
+                log::warn!("忽略 {} 的重复请求 {:?} {}",transfer.origin,transfer.direction,transfer.nonce);
+                return Ok(());
+            }
+            NonceStatus::Ahead => {
+                log::warn!("{} 的请求 {:?} {} 提前到达，等待前面的请求",transfer.origin,transfer.direction,transfer.nonce);
+                self.add_pending(transfer);
+                return Ok(());
+            }
+            NonceStatus::Expected => {}
+        }
+        let route = Route {origin: transfer.origin,direction: transfer.direction};
+        self.settle(transfer).await?;
+        // 处理完之后，继续处理已经提前到达的后续请求
+        let mut nonce = self.advance_nonce(&route).await;
+        while let Some(next) = self.take_pending(Ticket {route,nonce}).await {
+            self.settle(next).await?;
+            nonce = self.advance_nonce(&route).await;
+        }
+        Ok(())
+    }
+
+    /*
+    结算请求(注意：消息里的铸币或转账失败会阻塞整个收件箱，所以先预检查，
+    不能结算的请求记下来，等可以结算时用RetryMint或RetryRelease重新结算)
+    */
+    async fn settle(&mut self, transfer: BridgeTransfer) -> Result<(), ContractError> {
+        if let Some(reason) = self.settle_failure(&transfer).await? {
+            log::warn!("暂时无法结算 {} 的请求 {:?} {}: {}",transfer.origin,transfer.direction,transfer.nonce,reason);
+            self.add_failed(transfer);
+            return Ok(());
+        }
+        self.complete(transfer).await
+    }
+
+    /*
+    预检查请求现在能不能结算，不能时返回原因(注意：不修改状态)。
+    锁定：源代币要已经转到桥的账户(其它链上锁定时源代币是fungible程序发消息入账的，可能比铸币请求晚到)，桥要是包装代币的铸币者；
+    销毁：锁定的源代币要足够(包装代币被其它铸币者多铸造时可能不够)，桥的账户要能转出
+    */
+    async fn settle_failure(&mut self, transfer: &BridgeTransfer) -> Result<Option<ContractError>, ContractError> {
+        let parameters = Self::parameters()?;
+        let locked = *self.locked.get();
+        match transfer.direction {
+            Direction::Lock => {
+                let call = fungible::ApplicationCallOpt::Balance {owner: Self::bridge_account().owner};
+                let (response, _) = self.call_application(true,parameters.source_token,&call,vec![]).await?;
+                let balance = match response {
+                    ResponseOpt::Amount(balance) => balance,
+                    _ => Amount::default(),
+                };
+                if balance < locked.saturating_add(transfer.amount) {
+                    return Ok(Some(ContractError::SourceNotCredited));
+                }
+                let (response, _) = self.call_application(true,parameters.wrapped_token,&fungible::ApplicationCallOpt::CheckMint,vec![]).await?;
+                if let ResponseOpt::Rejected(code) = response {
+                    return Ok(Some(ContractError::Rejected(code)));
+                }
+            }
+            Direction::Burn => {
+                if locked < transfer.amount {
+                    return Ok(Some(ContractError::InsufficientLocked));
+                }
+                let call = fungible::ApplicationCallOpt::CheckTransfer {
+                    owner: Self::bridge_account().owner,
+                    amount: transfer.amount,
+                };
+                let (response, _) = self.call_application(true,parameters.source_token,&call,vec![]).await?;
+                if let ResponseOpt::Rejected(code) = response {
+                    return Ok(Some(ContractError::Rejected(code)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // 完成结算：锁定的源代币到账后铸造包装代币，销毁后释放源代币
+    async fn complete(&mut self, transfer: BridgeTransfer) -> Result<(), ContractError> {
+        let parameters = Self::parameters()?;
+        match transfer.direction {
+            Direction::Lock => {
+                self.locked.get_mut().saturating_add_assign(transfer.amount);
+                let call = fungible::ApplicationCallOpt::Mint {
+                    target_account: transfer.target_account,
+                    amount: transfer.amount,
+                    request_id: None,
+                };
+                self.call_application(true,parameters.wrapped_token,&call,vec![]).await?;
+            }
+            Direction::Burn => {
+                let locked = self.locked.get_mut();
+                *locked = locked.saturating_sub(transfer.amount);
+                let call = fungible::ApplicationCallOpt::Transfer {
+                    owner: Self::bridge_account().owner,
+                    amount: transfer.amount,
+                    target_account: transfer.target_account,
+                    request_id: None,
+                };
+                self.call_application(true,parameters.source_token,&call,vec![]).await?;
+            }
+        }
+        self.transfers.push(transfer);
+        Ok(())
+    }
+
+    // 重新结算之前没有结算的请求(注意：预检查还是不通过时操作失败，请求继续留在没有结算的请求里)
+    async fn retry(&mut self, route: Route, nonce: u64) -> Result<(), ContractError> {
+        if system_api::current_chain_id() != Self::home_chain_id() {
+            return Err(ContractError::NotHomeChain);
+        }
+        let transfer = self.take_failed(Ticket {route,nonce}).await.ok_or(ContractError::FailedTransferNotFound)?;
+        if let Some(reason) = self.settle_failure(&transfer).await? {
+            return Err(reason);
+        }
+        self.complete(transfer).await
+    }
+}
+
+// 自定义一些合约错误
+#[derive(Debug, Error)]
+pub enum ContractError {
+    #[error("Failed to deserialize BCS bytes")]
+    BcsError(#[from] bcs::Error),
+
+    #[error("Failed to deserialize JSON string")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Source and wrapped tokens must be different applications")]
+    IdenticalTokens,
+
+    // 桥要部署在包装代币的部署链上
+    #[error("Bridge must be created on the wrapped token's creation chain")]
+    WrongHomeChain,
+
+    #[error("Operation requires an authenticated signer")]
+    MissingSigner,
+
+    #[error("Amount must be greater than zero")]
+    ZeroAmount,
+
+    #[error("Failed transfers can only be retried on the bridge's home chain")]
+    NotHomeChain,
+
+    #[error("No failed transfer with this origin, direction and nonce")]
+    FailedTransferNotFound,
+
+    // 锁定的源代币不够释放
+    #[error("Not enough locked source tokens to release")]
+    InsufficientLocked,
+
+    // 其它链上锁定的源代币还没有入账到桥的账户
+    #[error("Locked source tokens have not been credited to the bridge yet")]
+    SourceNotCredited,
+
+    // fungible程序预检查没有通过(比如桥不是铸币者、桥的账户不能转出)
+    #[error("Token rejected the call with error code {0:?}")]
+    Rejected(ErrorCode),
+
+    #[error("Application calls not supported")]
+    ApplicationCallNotSupported,
+
+    #[error("Session not supported")]
+    SessionNotSupported,
+}
@@ -0,0 +1,122 @@
+use async_graphql::{Enum, Request, Response, scalar, SimpleObject};
+use linera_sdk::base::{Amount, ApplicationId, ChainId, ContractAbi, ServiceAbi};
+use linera_sdk::graphql::GraphQLMutationRoot;
+use serde::{Deserialize, Serialize};
+use fungible::{Account, FungibleTokenAbi};
+
+/*
+包装代币跨程序桥上下文相关定义文件
+*/
+
+pub struct BridgeAbi;
+
+// 合约写入相关Abi
+impl ContractAbi for BridgeAbi {
+    // 参数是被锁定的源代币和被铸造的包装代币
+    type Parameters = BridgeParameters;
+    type InitializationArgument = ();
+    type Operation = OperationOpt;
+    type Message = MessageOpt;
+    type ApplicationCall = ();
+    type SessionCall = ();
+    type SessionState = ();
+    type Response = ();
+}
+
+// 合约查询相关Abi
+impl ServiceAbi for BridgeAbi {
+    type Parameters = BridgeParameters;
+    type Query = Request;
+    type QueryResponse = Response;
+}
+
+/*
+桥参数(注意：桥要部署在包装代币的部署链上，并且要被包装代币的管理员设置为铸币者，
+因为fungible程序只在部署合约的链上铸币)
+*/
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct BridgeParameters {
+    pub source_token: ApplicationId<FungibleTokenAbi>,
+    pub wrapped_token: ApplicationId<FungibleTokenAbi>,
+}
+
+// 操作枚举(注意：可以在任何链上操作，请求会按顺序编号发到部署合约的链处理)
+#[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
+pub enum OperationOpt {
+    // 锁定签名者的源代币，给target_account铸造相同数量的包装代币
+    Lock {
+        amount: Amount,
+        target_account: Account
+    },
+    // 销毁签名者的包装代币，把相同数量的源代币释放给target_account
+    Burn {
+        amount: Amount,
+        target_account: Account
+    },
+    // 重新释放之前没有释放的请求(只能在部署合约的链上执行，任何人都可以调用，源代币还是释放给原来的target_account)
+    RetryRelease {
+        origin: ChainId,
+        nonce: u64
+    },
+    // 重新铸造之前没有铸造的请求(只能在部署合约的链上执行，任何人都可以调用，包装代币还是铸造给原来的target_account)
+    RetryMint {
+        origin: ChainId,
+        nonce: u64
+    }
+}
+
+// 消息枚举(注意：nonce是发起链上这个方向的请求序号，部署合约的链按序号顺序处理，提前到达的请求先保存起来)
+#[derive(Debug, Deserialize, Serialize)]
+pub enum MessageOpt {
+    // 源代币已经锁定，请求铸造包装代币
+    MintRequest {
+        nonce: u64,
+        amount: Amount,
+        target_account: Account
+    },
+    // 包装代币已经销毁，请求释放源代币
+    ReleaseRequest {
+        nonce: u64,
+        amount: Amount,
+        target_account: Account
+    }
+}
+
+// 桥的方向
+#[derive(Clone, Copy, Debug, Deserialize, Enum, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Direction {
+    // 锁定源代币，铸造包装代币
+    Lock,
+    // 销毁包装代币，释放源代币
+    Burn,
+}
+
+// 这个是graphql解析使用
+scalar!(Route);
+
+// 某条链上某个方向的请求，用作期望序号的键
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Route {
+    pub origin: ChainId,
+    pub direction: Direction,
+}
+
+// 这个是graphql解析使用
+scalar!(Ticket);
+
+// 某条链上某个方向的某个请求，用作提前到达和失败请求的键
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Ticket {
+    pub route: Route,
+    pub nonce: u64,
+}
+
+// 部署合约的链上处理过的请求
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct BridgeTransfer {
+    pub origin: ChainId,
+    pub direction: Direction,
+    pub nonce: u64,
+    pub amount: Amount,
+    pub target_account: Account,
+}
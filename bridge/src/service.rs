@@ -0,0 +1,47 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+mod state;
+
+use self::state::Bridge;
+use async_trait::async_trait;
+use linera_sdk::{base::WithServiceAbi, QueryContext, Service, ViewStateStorage};
+use std::sync::Arc;
+use async_graphql::{EmptySubscription, Response, Schema};
+use linera_sdk::graphql::GraphQLMutationRoot;
+use thiserror::Error;
+use bridge::OperationOpt;
+
+/*
+查询服务相关实现(就是定义一些只读的数据给前端调用)
+*/
+
+linera_sdk::service!(Bridge);
+
+impl WithServiceAbi for Bridge {
+    type Abi = bridge::BridgeAbi;
+}
+
+#[async_trait]
+impl Service for Bridge {
+    type Error = ServiceError;
+    type Storage = ViewStateStorage<Self>;
+
+    async fn handle_query(
+        self: Arc<Self>,
+        _context: &QueryContext,
+        request: Self::Query,
+    ) -> Result<Response, Self::Error> {
+        let schema = Schema::build(self.clone(),OperationOpt::mutation_root(),EmptySubscription).finish();
+        let response = schema.execute(request).await;
+        Ok(response)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ServiceError {
+    #[error("Queries not supported by application")]
+    QueriesNotSupported,
+
+    #[error("Invalid query argument; could not deserialize request")]
+    InvalidQuery(#[from] serde_json::Error),
+}
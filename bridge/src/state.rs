@@ -0,0 +1,124 @@
+use linera_sdk::base::Amount;
+use linera_sdk::views::{LogView, MapView, RegisterView, ViewStorageContext};
+use linera_views::views::{GraphQLView, RootView};
+use bridge::{BridgeTransfer, Direction, Route, Ticket};
+
+/*
+状态文件定义数据结构以及应用核心逻辑实现
+*/
+
+#[derive(RootView, GraphQLView)]
+#[view(context = "ViewStorageContext")]
+pub struct Bridge {
+    /* 当前链上每个方向下一个请求的序号 */
+    pub next_nonces: MapView<Direction, u64>,
+    /* 部署合约的链上每条链每个方向期望的下一个序号，小于它的请求已经处理过了 */
+    pub expected_nonces: MapView<Route, u64>,
+    /* 锁定的源代币总量，等于铸造出去还没销毁的包装代币(注意：只在部署合约的链上有意义) */
+    pub locked: RegisterView<Amount>,
+    /* 部署合约的链上处理过的请求 */
+    pub transfers: LogView<BridgeTransfer>,
+    /* 比期望序号提前到达的请求，等前面的请求处理完再按顺序处理 */
+    pub pending_transfers: MapView<Ticket, BridgeTransfer>,
+    /* 预检查没有通过而没有结算的请求(源代币还没到账、桥不是铸币者、锁定的源代币不足等)，可以用RetryMint或RetryRelease重新结算 */
+    pub failed_transfers: MapView<Ticket, BridgeTransfer>,
+}
+
+// 请求序号和期望序号比较的结果
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NonceStatus {
+    // 等于期望序号，可以处理
+    Expected,
+    // 小于期望序号，已经处理过了
+    Duplicate,
+    // 大于期望序号，前面还有请求没有到达
+    Ahead,
+}
+
+#[allow(dead_code)]//该注解用于去除未使用警告
+impl Bridge {
+    // 分配当前链上这个方向的下一个请求序号
+    pub async fn next_nonce(&mut self, direction: Direction) -> u64 {
+        let nonce = self.next_nonces
+            .get(&direction)
+            .await
+            .expect("获取请求序号失败!")
+            .unwrap_or_default();
+        self.next_nonces
+            .insert(&direction, nonce + 1)
+            .expect("记录请求序号失败!");
+        nonce
+    }
+
+    // 期望的下一个序号
+    pub async fn expected_nonce(&self, route: &Route) -> u64 {
+        self.expected_nonces
+            .get(route)
+            .await
+            .expect("获取期望序号失败!")
+            .unwrap_or_default()
+    }
+
+    // 比较请求序号和期望序号(注意：不会修改期望序号)
+    pub async fn nonce_status(&self, transfer: &BridgeTransfer) -> NonceStatus {
+        let expected = self.expected_nonce(&Self::route(transfer)).await;
+        if transfer.nonce < expected {
+            NonceStatus::Duplicate
+        } else if transfer.nonce > expected {
+            NonceStatus::Ahead
+        } else {
+            NonceStatus::Expected
+        }
+    }
+
+    // 期望序号加一，返回新的期望序号
+    pub async fn advance_nonce(&mut self, route: &Route) -> u64 {
+        let expected = self.expected_nonce(route).await + 1;
+        self.expected_nonces
+            .insert(route, expected)
+            .expect("记录期望序号失败!");
+        expected
+    }
+
+    // 保存提前到达的请求
+    pub fn add_pending(&mut self, transfer: BridgeTransfer) {
+        self.pending_transfers
+            .insert(&Self::ticket(&transfer), transfer)
+            .expect("记录提前到达的请求失败!");
+    }
+
+    // 取出某个序号提前到达的请求
+    pub async fn take_pending(&mut self, ticket: Ticket) -> Option<BridgeTransfer> {
+        let transfer = self.pending_transfers
+            .get(&ticket)
+            .await
+            .expect("获取提前到达的请求失败!")?;
+        self.pending_transfers.remove(&ticket).expect("删除提前到达的请求失败!");
+        Some(transfer)
+    }
+
+    // 记录没有结算的请求
+    pub fn add_failed(&mut self, transfer: BridgeTransfer) {
+        self.failed_transfers
+            .insert(&Self::ticket(&transfer), transfer)
+            .expect("记录失败的请求失败!");
+    }
+
+    // 取出没有结算的请求
+    pub async fn take_failed(&mut self, ticket: Ticket) -> Option<BridgeTransfer> {
+        let transfer = self.failed_transfers
+            .get(&ticket)
+            .await
+            .expect("获取失败的请求失败!")?;
+        self.failed_transfers.remove(&ticket).expect("删除失败的请求失败!");
+        Some(transfer)
+    }
+
+    pub fn route(transfer: &BridgeTransfer) -> Route {
+        Route { origin: transfer.origin, direction: transfer.direction }
+    }
+
+    pub fn ticket(transfer: &BridgeTransfer) -> Ticket {
+        Ticket { route: Self::route(transfer), nonce: transfer.nonce }
+    }
+}
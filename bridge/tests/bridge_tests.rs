@@ -0,0 +1,233 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use linera_sdk::base::{Amount, Owner};
+use linera_sdk::test::TestValidator;
+use bridge::{BridgeAbi, BridgeParameters, OperationOpt};
+use fungible::{Account, AccountOwner, FungibleTokenAbi};
+use fungible_client::{ActiveChainTransport, FungibleClient};
+
+#[tokio::test]
+async fn test_lock_mint_and_burn_release() {
+    let initial_amount = Amount::from(1_000_000u128);
+    let lock_amount = Amount::from(300u128);
+    let burn_amount = Amount::from(100u128);
+    // 模拟一个验证节点，当前项目就是桥
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    // 部署合约的链，源代币、包装代币和桥都部署在这条链上
+    let mut home_chain = validator.new_chain().await;
+    let home_owner = AccountOwner::User(Owner::from(home_chain.public_key()));
+    // 持有包装代币的另一条链
+    let remote_chain = validator.new_chain().await;
+    let remote_owner = AccountOwner::User(Owner::from(remote_chain.public_key()));
+
+    let fungible_bytecode_id = home_chain.publish_bytecodes_in("../fungible").await;
+    let source_token = home_chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let wrapped_token = home_chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), Amount::default().into(), vec![])
+        .await;
+    let bridge_id = home_chain
+        .create_application::<BridgeAbi>(
+            bytecode_id,
+            BridgeParameters { source_token, wrapped_token },
+            (),
+            vec![source_token.forget_abi(), wrapped_token.forget_abi()],
+        ).await;
+
+    // 包装代币的管理员把桥设置为铸币者，然后锁定源代币给另一条链上的用户铸造包装代币
+    home_chain.add_block(|block| {
+        block
+            .with_operation(
+                wrapped_token,
+                fungible::OperationOpt::SetMinter {
+                    minter: AccountOwner::Application(bridge_id.forget_abi()),
+                    enabled: true,
+                },
+            )
+            .with_operation(
+                bridge_id,
+                OperationOpt::Lock {
+                    amount: lock_amount,
+                    target_account: Account { chain_id: remote_chain.id(), owner: remote_owner },
+                },
+            );
+    }).await;
+    remote_chain.handle_received_messages().await;
+    let remote_wrapped = FungibleClient::new(ActiveChainTransport::new(&remote_chain, wrapped_token));
+    assert_eq!(remote_wrapped.execute(fungible_client::balance(remote_owner)).await.unwrap(), lock_amount);
+    let home_source = FungibleClient::new(ActiveChainTransport::new(&home_chain, source_token));
+    assert_eq!(
+        home_source.execute(fungible_client::balance(AccountOwner::Application(bridge_id.forget_abi()))).await.unwrap(),
+        lock_amount
+    );
+
+    // 在另一条链上销毁包装代币，把源代币释放回部署合约的链
+    remote_chain.register_application(bridge_id).await;
+    remote_chain.add_block(|block| {
+        block.with_operation(
+            bridge_id,
+            OperationOpt::Burn {
+                amount: burn_amount,
+                target_account: Account { chain_id: home_chain.id(), owner: home_owner },
+            },
+        );
+    }).await;
+    home_chain.handle_received_messages().await;
+
+    assert_eq!(remote_wrapped.execute(fungible_client::balance(remote_owner)).await.unwrap(), lock_amount.saturating_sub(burn_amount));
+    assert_eq!(
+        home_source.execute(fungible_client::balance(home_owner)).await.unwrap(),
+        initial_amount.saturating_sub(lock_amount).saturating_add(burn_amount)
+    );
+    let value = home_chain.graphql_query(bridge_id, String::from("query { locked }")).await;
+    assert_eq!(value["locked"].as_str().unwrap().parse::<Amount>().unwrap(), lock_amount.saturating_sub(burn_amount));
+}
+
+#[tokio::test]
+async fn test_failed_release_can_be_retried() {
+    let initial_amount = Amount::from(1_000_000u128);
+    let lock_amount = Amount::from(300u128);
+    let extra_amount = Amount::from(200u128);
+    let burn_amount = Amount::from(500u128);
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    let mut home_chain = validator.new_chain().await;
+    let home_owner = AccountOwner::User(Owner::from(home_chain.public_key()));
+    let remote_chain = validator.new_chain().await;
+    let remote_owner = AccountOwner::User(Owner::from(remote_chain.public_key()));
+
+    let fungible_bytecode_id = home_chain.publish_bytecodes_in("../fungible").await;
+    let source_token = home_chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let wrapped_token = home_chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), Amount::default().into(), vec![])
+        .await;
+    let bridge_id = home_chain
+        .create_application::<BridgeAbi>(
+            bytecode_id,
+            BridgeParameters { source_token, wrapped_token },
+            (),
+            vec![source_token.forget_abi(), wrapped_token.forget_abi()],
+        ).await;
+    let remote_account = Account { chain_id: remote_chain.id(), owner: remote_owner };
+
+    // 桥铸造300，管理员自己作为铸币者又多铸造了200，包装代币比锁定的源代币多
+    home_chain.add_block(|block| {
+        block
+            .with_operation(
+                wrapped_token,
+                fungible::OperationOpt::SetMinter {
+                    minter: AccountOwner::Application(bridge_id.forget_abi()),
+                    enabled: true,
+                },
+            )
+            .with_operation(wrapped_token, fungible::OperationOpt::SetMinter { minter: home_owner, enabled: true })
+            .with_operation(bridge_id, OperationOpt::Lock { amount: lock_amount, target_account: remote_account })
+            .with_operation(
+                wrapped_token,
+                fungible::OperationOpt::Mint { target_account: remote_account, amount: extra_amount, request_id: None },
+            );
+    }).await;
+    remote_chain.handle_received_messages().await;
+
+    // 销毁的数量超过锁定的源代币，请求被记录为失败，源代币没有释放
+    remote_chain.register_application(bridge_id).await;
+    remote_chain.add_block(|block| {
+        block.with_operation(
+            bridge_id,
+            OperationOpt::Burn {
+                amount: burn_amount,
+                target_account: Account { chain_id: home_chain.id(), owner: home_owner },
+            },
+        );
+    }).await;
+    home_chain.handle_received_messages().await;
+    let home_balance = initial_amount.saturating_sub(lock_amount);
+    let home_source = FungibleClient::new(ActiveChainTransport::new(&home_chain, source_token));
+    assert_eq!(home_source.execute(fungible_client::balance(home_owner)).await.unwrap(), home_balance);
+    let ticket = format!(
+        "{{ route: {{ origin: \"{}\", direction: \"Burn\" }}, nonce: 0 }}",
+        remote_chain.id()
+    );
+    let query = format!("query {{ failedTransfers(ticket: {}) {{ amount }} }}", ticket);
+    let value = home_chain.graphql_query(bridge_id, query.clone()).await;
+    assert_eq!(value["failedTransfers"]["amount"].as_str().unwrap().parse::<Amount>().unwrap(), burn_amount);
+
+    // 再锁定一些源代币之后重新释放
+    home_chain.add_block(|block| {
+        block
+            .with_operation(
+                bridge_id,
+                OperationOpt::Lock {
+                    amount: extra_amount,
+                    target_account: Account { chain_id: home_chain.id(), owner: home_owner },
+                },
+            )
+            .with_operation(bridge_id, OperationOpt::RetryRelease { origin: remote_chain.id(), nonce: 0 });
+    }).await;
+    assert_eq!(
+        home_source.execute(fungible_client::balance(home_owner)).await.unwrap(),
+        home_balance.saturating_sub(extra_amount).saturating_add(burn_amount)
+    );
+    let value = home_chain.graphql_query(bridge_id, query).await;
+    assert!(value["failedTransfers"].is_null());
+    let value = home_chain.graphql_query(bridge_id, String::from("query { locked }")).await;
+    assert_eq!(value["locked"].as_str().unwrap().parse::<Amount>().unwrap(), Amount::default());
+}
+
+#[tokio::test]
+async fn test_failed_mint_can_be_retried() {
+    let initial_amount = Amount::from(1_000_000u128);
+    let lock_amount = Amount::from(300u128);
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    let mut home_chain = validator.new_chain().await;
+    let home_owner = AccountOwner::User(Owner::from(home_chain.public_key()));
+
+    let fungible_bytecode_id = home_chain.publish_bytecodes_in("../fungible").await;
+    let source_token = home_chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let wrapped_token = home_chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), Amount::default().into(), vec![])
+        .await;
+    let bridge_id = home_chain
+        .create_application::<BridgeAbi>(
+            bytecode_id,
+            BridgeParameters { source_token, wrapped_token },
+            (),
+            vec![source_token.forget_abi(), wrapped_token.forget_abi()],
+        ).await;
+    let home_account = Account { chain_id: home_chain.id(), owner: home_owner };
+
+    // 桥还不是铸币者，铸币的预检查没有通过，请求被记录为失败，源代币留在桥的账户但不算锁定
+    home_chain.add_block(|block| {
+        block.with_operation(bridge_id, OperationOpt::Lock { amount: lock_amount, target_account: home_account });
+    }).await;
+    let ticket = format!(
+        "{{ route: {{ origin: \"{}\", direction: \"Lock\" }}, nonce: 0 }}",
+        home_chain.id()
+    );
+    let query = format!("query {{ locked failedTransfers(ticket: {}) {{ amount }} }}", ticket);
+    let value = home_chain.graphql_query(bridge_id, query.clone()).await;
+    assert_eq!(value["failedTransfers"]["amount"].as_str().unwrap().parse::<Amount>().unwrap(), lock_amount);
+    assert_eq!(value["locked"].as_str().unwrap().parse::<Amount>().unwrap(), Amount::default());
+
+    // 把桥设置为铸币者之后重新铸造
+    home_chain.add_block(|block| {
+        block
+            .with_operation(
+                wrapped_token,
+                fungible::OperationOpt::SetMinter {
+                    minter: AccountOwner::Application(bridge_id.forget_abi()),
+                    enabled: true,
+                },
+            )
+            .with_operation(bridge_id, OperationOpt::RetryMint { origin: home_chain.id(), nonce: 0 });
+    }).await;
+    let home_wrapped = FungibleClient::new(ActiveChainTransport::new(&home_chain, wrapped_token));
+    assert_eq!(home_wrapped.execute(fungible_client::balance(home_owner)).await.unwrap(), lock_amount);
+    let value = home_chain.graphql_query(bridge_id, query).await;
+    assert!(value["failedTransfers"].is_null());
+    assert_eq!(value["locked"].as_str().unwrap().parse::<Amount>().unwrap(), lock_amount);
+}
@@ -0,0 +1,172 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+use async_trait::async_trait;
+use linera_sdk::{
+    ApplicationCallResult,
+    base::{SessionId, WithContractAbi}, CalleeContext, Contract, ExecutionResult, MessageContext,
+    OperationContext, SessionCallResult, ViewStateStorage,
+};
+use linera_sdk::base::{Amount, ChainId};
+use linera_sdk::contract::system_api;
+use thiserror::Error;
+
+use faucet::{MessageOpt, OperationOpt};
+use fungible::{Account, AccountOwner, ResponseOpt};
+
+use self::state::{Faucet, RateLimitedError};
+
+mod state;
+/*
+水龙头合约入口文件
+*/
+
+linera_sdk::contract!(Faucet);
+
+impl WithContractAbi for Faucet {
+    type Abi = faucet::FaucetAbi;
+}
+
+#[async_trait]
+impl Contract for Faucet {
+    type Error = ContractError;
+    type Storage = ViewStateStorage<Self>;
+
+    // 合约部署时调用，部署者就是管理员
+    async fn initialize(
+        &mut self,
+        context: &OperationContext,
+        argument: Self::InitializationArgument,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        self.admin.set(context.authenticated_signer);
+        self.config.set(argument);
+        Ok(ExecutionResult::default())
+    }
+
+    // 前端调用拦截转发
+    async fn execute_operation(
+        &mut self,
+        context: &OperationContext,
+        operation: Self::Operation,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        let home_chain_id = Self::home_chain_id();
+        let on_home_chain = system_api::current_chain_id() == home_chain_id;
+        match operation {
+            OperationOpt::Dispense {target_account} => {
+                if !on_home_chain {
+                    // 不需要签名：谁都可以给任何账户领取，代币还是发给target_account(测试网水龙头可以接受)
+                    let message = MessageOpt::Dispense {target_account};
+                    return Ok(ExecutionResult::default().with_message(home_chain_id,message));
+                }
+                self.dispense(target_account).await?;
+            }
+            OperationOpt::SetConfig {config} => {
+                if !on_home_chain {
+                    return Err(ContractError::NotHomeChain);
+                }
+                if context.authenticated_signer.is_none() || *self.admin.get() != context.authenticated_signer {
+                    return Err(ContractError::NotAdmin);
+                }
+                self.config.set(config);
+            }
+        }
+        Ok(ExecutionResult::default())
+    }
+
+    // 跨链相互调用
+    async fn execute_message(
+        &mut self,
+        _context: &MessageContext,
+        message: Self::Message,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        match message {
+            MessageOpt::Dispense {target_account} => {
+                // 消息执行失败会阻塞整个收件箱，所以超过限制或者水龙头余额不够的领取只记录日志然后忽略
+                if let Err(error) = self.dispense(target_account).await {
+                    log::warn!("忽略 {:?} 的领取: {}",target_account.owner,error);
+                }
+            }
+        }
+        Ok(ExecutionResult::default())
+    }
+
+    async fn handle_application_call(
+        &mut self,
+        _context: &CalleeContext,
+        _call: Self::ApplicationCall,
+        _forwarded_sessions: Vec<SessionId>,
+    ) -> Result<ApplicationCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        Err(ContractError::ApplicationCallNotSupported)
+    }
+
+    async fn handle_session_call(
+        &mut self,
+        _context: &CalleeContext,
+        _session: Self::SessionState,
+        _call: Self::SessionCall,
+        _forwarded_sessions: Vec<SessionId>,
+    ) -> Result<SessionCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        Err(ContractError::SessionNotSupported)
+    }
+}
+
+impl Faucet {
+    // 部署合约的链，水龙头的代币和领取记录都在这条链上
+    fn home_chain_id() -> ChainId {
+        system_api::current_application_id().creation.chain_id
+    }
+
+    // 检查限流后从水龙头程序账户转给target_account(注意：目标账户在其它链上时fungible程序会发消息入账)
+    async fn dispense(&mut self, target_account: Account) -> Result<(), ContractError> {
+        // 转账失败会让整个消息失败并堵住收件箱，所以记录领取之前先检查水龙头程序账户的余额
+        let faucet_owner = AccountOwner::Application(system_api::current_application_id());
+        let token_id = Self::parameters()?;
+        let requested = self.config.get().amount;
+        let (response, _) = self.call_application(true,token_id,&fungible::ApplicationCallOpt::Balance {owner: faucet_owner},vec![]).await?;
+        let available = match response {
+            ResponseOpt::Amount(balance) => balance,
+            _ => Amount::default(),
+        };
+        if available < requested {
+            return Err(ContractError::InsufficientFaucetBalance {available, requested});
+        }
+        let amount = self.record_claim(target_account.owner,system_api::current_system_time()).await?;
+        let call = fungible::ApplicationCallOpt::Transfer {
+            owner: faucet_owner,
+            amount,
+            target_account,
+            request_id: None,
+        };
+        self.call_application(true,token_id,&call,vec![]).await?;
+        Ok(())
+    }
+}
+
+// 自定义一些合约错误
+#[derive(Debug, Error)]
+pub enum ContractError {
+    #[error("Failed to deserialize BCS bytes")]
+    BcsError(#[from] bcs::Error),
+
+    #[error("Failed to deserialize JSON string")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Operation must be executed on the faucet home chain")]
+    NotHomeChain,
+
+    #[error("Only the admin can change the faucet configuration")]
+    NotAdmin,
+
+    // 同一个账户在时间窗口内重复领取
+    #[error(transparent)]
+    RateLimited(#[from] RateLimitedError),
+
+    // 水龙头程序账户里的代币不够这次发放
+    #[error("Insufficient faucet balance: requested {requested}, available {available}")]
+    InsufficientFaucetBalance { available: Amount, requested: Amount },
+
+    #[error("Application calls not supported")]
+    ApplicationCallNotSupported,
+
+    #[error("Session not supported")]
+    SessionNotSupported,
+}
@@ -0,0 +1,65 @@
+use async_graphql::{InputObject, Request, Response, SimpleObject};
+use linera_sdk::base::{Amount, ApplicationId, ContractAbi, ServiceAbi};
+use linera_sdk::graphql::GraphQLMutationRoot;
+use serde::{Deserialize, Serialize};
+use fungible::{Account, FungibleTokenAbi};
+
+/*
+测试网水龙头上下文相关定义文件
+*/
+
+pub struct FaucetAbi;
+
+// 合约写入相关Abi
+impl ContractAbi for FaucetAbi {
+    // 参数是发放的fungible程序ID
+    type Parameters = ApplicationId<FungibleTokenAbi>;
+    // 合约部署时的参数
+    type InitializationArgument = FaucetConfig;
+    type Operation = OperationOpt;
+    type Message = MessageOpt;
+    type ApplicationCall = ();
+    type SessionCall = ();
+    type SessionState = ();
+    type Response = ();
+}
+
+// 合约查询相关Abi
+impl ServiceAbi for FaucetAbi {
+    type Parameters = ApplicationId<FungibleTokenAbi>;
+    type Query = Request;
+    type QueryResponse = Response;
+}
+
+// 水龙头规则：每个账户在每个时间窗口内可以领取一次amount(注意：同时作为GraphQL输入和输出类型)
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, InputObject, SimpleObject)]
+#[graphql(input_name = "FaucetConfigInput")]
+pub struct FaucetConfig {
+    pub amount: Amount,
+    // 时间窗口(微秒)
+    pub window_micros: u64,
+}
+
+/*
+操作枚举(注意：水龙头的代币是水龙头程序在部署链上的程序账户中的余额，在其它链上领取会发消息到部署链)
+*/
+#[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
+pub enum OperationOpt {
+    // 给target_account发放代币，按target_account的owner限流
+    Dispense {
+        target_account: Account
+    },
+    // 修改水龙头规则(仅管理员)
+    SetConfig {
+        config: FaucetConfig
+    }
+}
+
+// 消息枚举
+#[derive(Debug, Deserialize, Serialize)]
+pub enum MessageOpt {
+    // 其它链上的领取请求(注意：不带签名，任何人都可以让某个账户的时间窗口开始计时，代币还是发给这个账户，测试网可以接受)
+    Dispense {
+        target_account: Account
+    }
+}
@@ -0,0 +1,65 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+mod state;
+
+use self::state::Faucet;
+use async_trait::async_trait;
+use linera_sdk::{base::WithServiceAbi, QueryContext, Service, ViewStateStorage};
+use std::sync::Arc;
+use async_graphql::{EmptySubscription, MergedObject, Object, Response, Schema};
+use linera_sdk::base::Timestamp;
+use linera_sdk::graphql::GraphQLMutationRoot;
+use thiserror::Error;
+use faucet::OperationOpt;
+use fungible::AccountOwner;
+
+/*
+查询服务相关实现(就是定义一些只读的数据给前端调用)
+*/
+
+linera_sdk::service!(Faucet);
+
+impl WithServiceAbi for Faucet {
+    type Abi = faucet::FaucetAbi;
+}
+
+#[async_trait]
+impl Service for Faucet {
+    type Error = ServiceError;
+    type Storage = ViewStateStorage<Self>;
+
+    async fn handle_query(
+        self: Arc<Self>,
+        _context: &QueryContext,
+        request: Self::Query,
+    ) -> Result<Response, Self::Error> {
+        let query_root = QueryRoot(self.clone(), ClaimQuery { state: self.clone() });
+        let schema = Schema::build(query_root,OperationOpt::mutation_root(),EmptySubscription).finish();
+        let response = schema.execute(request).await;
+        Ok(response)
+    }
+}
+
+#[derive(MergedObject)]
+struct QueryRoot(Arc<Faucet>, ClaimQuery);
+
+struct ClaimQuery {
+    state: Arc<Faucet>,
+}
+
+#[Object]
+impl ClaimQuery {
+    // 某个账户下次可以领取的时间，从没领取过返回null
+    async fn next_claim_time(&self, owner: AccountOwner) -> Option<Timestamp> {
+        self.state.next_claim_time(&owner).await
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ServiceError {
+    #[error("Queries not supported by application")]
+    QueriesNotSupported,
+
+    #[error("Invalid query argument; could not deserialize request")]
+    InvalidQuery(#[from] serde_json::Error),
+}
@@ -0,0 +1,56 @@
+use linera_sdk::base::{Amount, Owner, Timestamp};
+use linera_sdk::views::{MapView, RegisterView, ViewStorageContext};
+use linera_views::views::{GraphQLView, RootView};
+use thiserror::Error;
+use faucet::FaucetConfig;
+use fungible::AccountOwner;
+
+/*
+状态文件定义数据结构以及应用核心逻辑实现(注意：状态只在部署合约的链上有意义)
+*/
+
+#[derive(RootView, GraphQLView)]
+#[view(context = "ViewStorageContext")]
+pub struct Faucet {
+    /* 管理员，可以修改水龙头规则 */
+    pub admin: RegisterView<Option<Owner>>,
+    /* 水龙头规则 */
+    pub config: RegisterView<FaucetConfig>,
+    /* 每个账户上次领取的时间 */
+    pub last_claims: MapView<AccountOwner, Timestamp>,
+    /* 已经发放的总量 */
+    pub total_dispensed: RegisterView<Amount>,
+}
+
+// 定义领取太频繁异常，带上下次可以领取的时间
+#[derive(Clone, Copy, Debug, Error)]
+#[error("Rate limited until {} micros", .0.micros())]
+pub struct RateLimitedError(pub Timestamp);
+
+#[allow(dead_code)]//该注解用于去除未使用警告
+impl Faucet {
+    // 下次可以领取的时间，从没领取过返回None
+    pub async fn next_claim_time(&self, owner: &AccountOwner) -> Option<Timestamp> {
+        let last_claim = self.last_claims
+            .get(owner)
+            .await
+            .expect("获取领取时间失败!")?;
+        let window_micros = self.config.get().window_micros;
+        Some(Timestamp::from(last_claim.micros().saturating_add(window_micros)))
+    }
+
+    // 记录领取，还在时间窗口内时返回RateLimitedError
+    pub async fn record_claim(&mut self, owner: AccountOwner, now: Timestamp) -> Result<Amount, RateLimitedError> {
+        if let Some(next_claim_time) = self.next_claim_time(&owner).await {
+            if now < next_claim_time {
+                return Err(RateLimitedError(next_claim_time));
+            }
+        }
+        self.last_claims
+            .insert(&owner, now)
+            .expect("记录领取时间失败!");
+        let amount = self.config.get().amount;
+        self.total_dispensed.get_mut().saturating_add_assign(amount);
+        Ok(amount)
+    }
+}
@@ -0,0 +1,94 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::panic::AssertUnwindSafe;
+
+use futures::FutureExt;
+use linera_sdk::base::{Amount, Owner, Timestamp};
+use linera_sdk::test::TestValidator;
+use faucet::{FaucetAbi, FaucetConfig, OperationOpt};
+use fungible::{Account, AccountOwner, FungibleTokenAbi};
+use fungible_client::{ActiveChainTransport, FungibleClient};
+
+#[tokio::test]
+async fn test_dispense_is_rate_limited_per_owner() {
+    let initial_amount = Amount::from(1_000_000u128);
+    let faucet_amount = Amount::from(10u128);
+    let window_micros = 3_600_000_000;
+    // 模拟一个验证节点，当前项目就是水龙头
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    let mut home_chain = validator.new_chain().await;
+    let admin = AccountOwner::User(Owner::from(home_chain.public_key()));
+    let remote_chain = validator.new_chain().await;
+    let remote_owner = AccountOwner::User(Owner::from(remote_chain.public_key()));
+
+    let fungible_bytecode_id = home_chain.publish_bytecodes_in("../fungible").await;
+    let token_id = home_chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let faucet_id = home_chain
+        .create_application::<FaucetAbi>(
+            bytecode_id,
+            token_id,
+            FaucetConfig { amount: faucet_amount, window_micros },
+            vec![token_id.forget_abi()],
+        ).await;
+    let faucet = AccountOwner::Application(faucet_id.forget_abi());
+    let target_account = Account { chain_id: remote_chain.id(), owner: remote_owner };
+
+    // 给水龙头充值，然后给另一条链上的用户发放
+    let start = Timestamp::now();
+    home_chain.add_block(|block| {
+        block
+            .with_timestamp(start)
+            .with_operation(
+                token_id,
+                fungible::OperationOpt::Transfer {
+                    owner: admin,
+                    amount: Amount::from(1_000u128),
+                    target_account: Account { chain_id: home_chain.id(), owner: faucet },
+                    request_id: None,
+                },
+            )
+            .with_operation(faucet_id, OperationOpt::Dispense { target_account });
+    }).await;
+
+    // 时间窗口内再次领取被拒绝
+    let result = AssertUnwindSafe(home_chain.add_block(|block| {
+        block
+            .with_timestamp(start)
+            .with_operation(faucet_id, OperationOpt::Dispense { target_account });
+    })).catch_unwind().await;
+    assert!(result.is_err());
+
+    // 时间窗口过了之后可以再次领取
+    home_chain.add_block(|block| {
+        block
+            .with_timestamp(Timestamp::from(start.micros() + window_micros))
+            .with_operation(faucet_id, OperationOpt::Dispense { target_account });
+    }).await;
+    remote_chain.handle_received_messages().await;
+
+    let remote_token = FungibleClient::new(ActiveChainTransport::new(&remote_chain, token_id));
+    assert_eq!(remote_token.execute(fungible_client::balance(remote_owner)).await.unwrap(), Amount::from(20u128));
+    let home_token = FungibleClient::new(ActiveChainTransport::new(&home_chain, token_id));
+    assert_eq!(home_token.execute(fungible_client::balance(faucet)).await.unwrap(), Amount::from(980u128));
+
+    // 水龙头余额不够时其它链上的领取被忽略，不记录领取，也不会堵住部署链的收件箱
+    home_chain.add_block(|block| {
+        block.with_operation(
+            faucet_id,
+            OperationOpt::SetConfig { config: FaucetConfig { amount: Amount::from(10_000u128), window_micros } },
+        );
+    }).await;
+    remote_chain.register_application(faucet_id).await;
+    remote_chain.add_block(|block| {
+        block.with_operation(
+            faucet_id,
+            OperationOpt::Dispense { target_account: Account { chain_id: home_chain.id(), owner: admin } },
+        );
+    }).await;
+    home_chain.handle_received_messages().await;
+    assert_eq!(home_token.execute(fungible_client::balance(faucet)).await.unwrap(), Amount::from(980u128));
+    let value = home_chain.graphql_query(faucet_id, String::from("query { totalDispensed }")).await;
+    assert_eq!(value["totalDispensed"].as_str().unwrap().parse::<Amount>().unwrap(), Amount::from(20u128));
+}
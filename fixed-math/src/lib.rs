@@ -0,0 +1,83 @@
+/*
+定点数计算，供AMM、质押、撮合引擎等程序使用(注意：Amount内部是u128，两个Amount相乘会超出u128，所以乘法先算成256位再除)
+*/
+
+const LOW_MASK: u128 = u64::MAX as u128;
+
+// 两个u128相乘，返回256位结果(高128位，低128位)
+pub fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    let (a_high, a_low) = (a >> 64, a & LOW_MASK);
+    let (b_high, b_low) = (b >> 64, b & LOW_MASK);
+    let low_low = a_low * b_low;
+    let low_high = a_low * b_high;
+    let high_low = a_high * b_low;
+    let high_high = a_high * b_high;
+    let middle = (low_low >> 64) + (low_high & LOW_MASK) + (high_low & LOW_MASK);
+    let low = (low_low & LOW_MASK) | (middle << 64);
+    let high = high_high + (low_high >> 64) + (high_low >> 64) + (middle >> 64);
+    (high, low)
+}
+
+// 计算 a * b / c(向下取整)，除数为0或者结果超出u128时返回None
+pub fn mul_div(a: u128, b: u128, c: u128) -> Option<u128> {
+    if c == 0 {
+        return None;
+    }
+    let (high, low) = mul_wide(a, b);
+    if high == 0 {
+        return Some(low / c);
+    }
+    if high >= c {
+        return None;
+    }
+    // 256位除以128位的长除法，high < c 保证商不超过u128
+    let mut remainder = high;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= c {
+            remainder = remainder.wrapping_sub(c);
+            quotient |= 1;
+        }
+    }
+    Some(quotient)
+}
+
+// 计算 sqrt(a * b)(向下取整)，用于第一次添加流动性时的份额
+pub fn sqrt_mul(a: u128, b: u128) -> u128 {
+    let target = mul_wide(a, b);
+    let (mut low, mut high) = (0u128, u128::MAX);
+    while low < high {
+        let middle = low + (high - low) / 2 + 1;
+        if mul_wide(middle, middle) <= target {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+    low
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{mul_div, mul_wide, sqrt_mul};
+
+    #[test]
+    fn mul_div_handles_overflowing_products() {
+        assert_eq!(mul_wide(u128::MAX, 2), (1, u128::MAX - 1));
+        assert_eq!(mul_div(u128::MAX, 6, 3), None);
+        assert_eq!(mul_div(u128::MAX, 3, 6), Some(u128::MAX / 2));
+        assert_eq!(mul_div(10u128.pow(30), 10u128.pow(30), 10u128.pow(25)), Some(10u128.pow(35)));
+        assert_eq!(mul_div(7, 3, 0), None);
+    }
+
+    #[test]
+    fn sqrt_mul_rounds_down() {
+        assert_eq!(sqrt_mul(4, 9), 6);
+        assert_eq!(sqrt_mul(2, 4), 2);
+        assert_eq!(sqrt_mul(10u128.pow(30), 10u128.pow(30)), 10u128.pow(30));
+        assert_eq!(sqrt_mul(u128::MAX, u128::MAX), u128::MAX);
+    }
+}
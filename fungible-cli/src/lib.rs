@@ -0,0 +1,235 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use linera_sdk::base::{Amount, ApplicationId, ChainId};
+use thiserror::Error;
+use fungible::{Account, AccountOwner, InitialBalance, InitializationArgument, TokenEvent, TokenMetadata};
+use fungible_client::{ClientError, FungibleClient, HttpTransport, MutationResult};
+
+/*
+fungible应用的命令行工具：通过节点服务(linera service)的GraphQL接口部署、转账和查询，不用手写GraphQL
+账户格式：用户地址直接写地址，程序账户写 app:程序ID
+*/
+
+#[derive(Debug, Parser)]
+#[command(name = "fungible-cli", about = "Operate fungible token deployments through the node service")]
+pub struct Cli {
+    /// 节点服务地址
+    #[arg(long, global = true, default_value = "http://localhost:8080")]
+    pub node_url: String,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+// 应用所在的链和程序ID
+#[derive(Debug, Args)]
+pub struct AppArgs {
+    #[arg(long)]
+    pub chain_id: ChainId,
+
+    #[arg(long)]
+    pub application_id: ApplicationId,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 用已经发布的字节码部署代币，部署者是管理员
+    Deploy {
+        #[arg(long)]
+        chain_id: ChainId,
+        #[arg(long)]
+        bytecode_id: String,
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        symbol: String,
+        #[arg(long, default_value_t = 18)]
+        decimals: u8,
+        /// 初始余额，格式：账户=金额，可以传多次
+        #[arg(long = "distribute", value_parser = parse_initial_balance)]
+        accounts: Vec<InitialBalance>,
+    },
+    /// 转账
+    Transfer {
+        #[command(flatten)]
+        app: AppArgs,
+        #[arg(long, value_parser = parse_owner)]
+        owner: AccountOwner,
+        #[arg(long)]
+        amount: Amount,
+        #[arg(long)]
+        target_chain_id: ChainId,
+        #[arg(long, value_parser = parse_owner)]
+        target_owner: AccountOwner,
+        /// 请求ID，超时后用同一个ID重试不会重复转账
+        #[arg(long)]
+        request_id: Option<String>,
+    },
+    /// 从其它链(或者当前链)上的账户获取代币
+    Claim {
+        #[command(flatten)]
+        app: AppArgs,
+        #[arg(long)]
+        source_chain_id: ChainId,
+        #[arg(long, value_parser = parse_owner)]
+        source_owner: AccountOwner,
+        #[arg(long)]
+        amount: Amount,
+        #[arg(long)]
+        target_chain_id: ChainId,
+        #[arg(long, value_parser = parse_owner)]
+        target_owner: AccountOwner,
+        /// 请求ID，超时后用同一个ID重试不会重复获取
+        #[arg(long)]
+        request_id: Option<String>,
+    },
+    /// 查询可用余额(不包括锁定的代币)
+    Balance {
+        #[command(flatten)]
+        app: AppArgs,
+        #[arg(long, value_parser = parse_owner)]
+        owner: AccountOwner,
+    },
+    /// 查询有余额记录的所有账户
+    Holders {
+        #[command(flatten)]
+        app: AppArgs,
+    },
+    /// 导出账户的余额变化记录
+    History {
+        #[command(flatten)]
+        app: AppArgs,
+        #[arg(long, value_parser = parse_owner)]
+        owner: AccountOwner,
+        #[arg(long, value_enum, default_value = "csv")]
+        format: Format,
+        /// 从这个事件ID开始导出
+        #[arg(long, default_value_t = 0)]
+        since: u64,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error(transparent)]
+    Client(#[from] ClientError),
+
+    #[error("Failed to encode JSON output")]
+    Json(#[from] serde_json::Error),
+}
+
+// 执行命令，返回要打印的内容
+pub async fn run(cli: Cli) -> Result<String, CliError> {
+    match cli.command {
+        Command::Deploy { chain_id, bytecode_id, name, symbol, decimals, accounts } => {
+            let argument = InitializationArgument::Distribution {
+                metadata: TokenMetadata { name, symbol, decimals },
+                accounts,
+            };
+            let client = FungibleClient::new(HttpTransport::new(cli.node_url));
+            Ok(client.execute(fungible_client::create_application(chain_id, &bytecode_id, &argument)?).await?)
+        }
+        Command::Transfer { app, owner, amount, target_chain_id, target_owner, request_id } => {
+            let target_account = Account { chain_id: target_chain_id, owner: target_owner };
+            let result = app_client(&cli.node_url, &app)
+                .execute(fungible_client::transfer(owner, amount, target_account, request_id.as_deref()))
+                .await?;
+            Ok(format_mutation(result))
+        }
+        Command::Claim { app, source_chain_id, source_owner, amount, target_chain_id, target_owner, request_id } => {
+            let source_account = Account { chain_id: source_chain_id, owner: source_owner };
+            let target_account = Account { chain_id: target_chain_id, owner: target_owner };
+            let result = app_client(&cli.node_url, &app)
+                .execute(fungible_client::claim(source_account, amount, target_account, request_id.as_deref()))
+                .await?;
+            Ok(format_mutation(result))
+        }
+        Command::Balance { app, owner } => {
+            let balance = app_client(&cli.node_url, &app).execute(fungible_client::balance(owner)).await?;
+            Ok(balance.to_string())
+        }
+        Command::Holders { app } => {
+            let holders = app_client(&cli.node_url, &app).execute(fungible_client::holders()).await?;
+            Ok(holders.iter().map(format_owner).collect::<Vec<_>>().join("\n"))
+        }
+        Command::History { app, owner, format, since } => {
+            let events = app_client(&cli.node_url, &app)
+                .execute(fungible_client::balance_changed(owner, since))
+                .await?;
+            match format {
+                Format::Csv => Ok(to_csv(&events)),
+                Format::Json => Ok(serde_json::to_string_pretty(&events)?),
+            }
+        }
+    }
+}
+
+fn app_client(node_url: &str, app: &AppArgs) -> FungibleClient<HttpTransport> {
+    FungibleClient::new(HttpTransport::for_application(node_url, app.chain_id, app.application_id))
+}
+
+// 节点服务直接提交操作，返回区块哈希
+fn format_mutation(result: MutationResult) -> String {
+    match result {
+        MutationResult::Certificate(hash) => hash,
+        MutationResult::Operation(operation) => format!("{:?}", operation),
+    }
+}
+
+pub fn parse_owner(value: &str) -> Result<AccountOwner, String> {
+    match value.strip_prefix("app:") {
+        Some(application_id) => application_id
+            .parse()
+            .map(AccountOwner::Application)
+            .map_err(|_| format!("Invalid application ID: {}", application_id)),
+        None => value
+            .parse()
+            .map(AccountOwner::User)
+            .map_err(|_| format!("Invalid owner: {}", value)),
+    }
+}
+
+pub fn format_owner(owner: &AccountOwner) -> String {
+    match owner {
+        AccountOwner::User(owner) => owner.to_string(),
+        AccountOwner::Application(application_id) => format!("app:{}", application_id),
+    }
+}
+
+fn parse_initial_balance(value: &str) -> Result<InitialBalance, String> {
+    let (owner, amount) = value
+        .split_once('=')
+        .ok_or_else(|| format!("Expected OWNER=AMOUNT, got {}", value))?;
+    Ok(InitialBalance {
+        owner: parse_owner(owner)?,
+        amount: amount.parse().map_err(|_| format!("Invalid amount: {}", amount))?,
+    })
+}
+
+// 导出CSV(注意：字段里面没有逗号，不需要转义)
+fn to_csv(events: &[TokenEvent]) -> String {
+    let mut lines = vec!["id,kind,owner,amount,balance,target_chain_id,target_owner,timestamp".to_string()];
+    for event in events {
+        let (target_chain_id, target_owner) = match &event.target_account {
+            Some(account) => (account.chain_id.to_string(), format_owner(&account.owner)),
+            None => (String::new(), String::new()),
+        };
+        lines.push(format!(
+            "{},{:?},{},{},{},{},{},{}",
+            event.id,
+            event.kind,
+            format_owner(&event.owner),
+            event.amount,
+            event.balance,
+            target_chain_id,
+            target_owner,
+            event.timestamp.micros()
+        ));
+    }
+    lines.join("\n")
+}
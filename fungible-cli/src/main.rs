@@ -0,0 +1,14 @@
+use clap::Parser;
+use fungible_cli::Cli;
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    match fungible_cli::run(cli).await {
+        Ok(output) => println!("{}", output),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
+}
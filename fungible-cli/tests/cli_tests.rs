@@ -0,0 +1,152 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread::{self, JoinHandle};
+
+use clap::Parser;
+use linera_sdk::base::{Amount, ApplicationId, BlockHeight, BytecodeId, MessageId};
+use serde_json::json;
+use fungible_cli::Cli;
+
+const CHAIN_ID: &str = "e476187f6ddfeb9d588c7b45d3df334d5501d6499b3f9ad5595cae86cce16a65";
+const OWNER: &str = "1c02a28d03e846b113de238d8880df3c9c802143b73aea5d173466701bee1786";
+
+// 测试用的程序ID
+fn application_id() -> String {
+    let message_id = MessageId {
+        chain_id: CHAIN_ID.parse().unwrap(),
+        height: BlockHeight(0),
+        index: 0,
+    };
+    ApplicationId { bytecode_id: BytecodeId::new(message_id), creation: message_id }.to_string()
+}
+
+/*
+模拟节点服务：只响应一次请求，返回收到的完整请求(请求行、请求头和请求体)
+*/
+fn mock_node(response: serde_json::Value) -> (String, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 4096];
+        // 读到请求头结束并且请求体读完为止
+        loop {
+            let read = stream.read(&mut buffer).unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some(header_end) = text.find("\r\n\r\n") {
+                let content_length = text[..header_end]
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length").then(|| value.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or_default();
+                if request.len() >= header_end + 4 + content_length {
+                    break;
+                }
+            }
+            if read == 0 {
+                break;
+            }
+        }
+        let body = response.to_string();
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        ).unwrap();
+        String::from_utf8(request).unwrap()
+    });
+    (url, handle)
+}
+
+async fn run(url: &str, args: &[&str]) -> Result<String, fungible_cli::CliError> {
+    let cli = Cli::parse_from(["fungible-cli", "--node-url", url].iter().chain(args));
+    fungible_cli::run(cli).await
+}
+
+#[tokio::test]
+async fn test_balance() {
+    let (url, request) = mock_node(json!({ "data": { "balanceOf": { "available": "700", "locked": "300" } } }));
+    let output = run(&url, &["balance", "--chain-id", CHAIN_ID, "--application-id", &application_id(), "--owner", OWNER])
+        .await
+        .unwrap();
+    assert_eq!(output.parse::<Amount>().unwrap(), "700".parse::<Amount>().unwrap());
+
+    let request = request.join().unwrap();
+    assert!(request.starts_with(&format!("POST /chains/{}/applications/{} ", CHAIN_ID, application_id())));
+    assert!(request.contains("balanceOf(owner: {User: "));
+    assert!(request.contains("available"));
+    assert!(request.contains(OWNER));
+}
+
+#[tokio::test]
+async fn test_history_csv() {
+    let events = json!([{
+        "id": 3,
+        "kind": "TRANSFER_OUT",
+        "owner": { "User": OWNER },
+        "amount": "10",
+        "balance": "90",
+        "targetAccount": { "chainId": CHAIN_ID, "owner": { "User": OWNER } },
+        "timestamp": 42
+    }]);
+    let (url, request) = mock_node(json!({ "data": { "balanceChanged": events } }));
+    let output = run(&url, &["history", "--chain-id", CHAIN_ID, "--application-id", &application_id(), "--owner", OWNER, "--since", "3"])
+        .await
+        .unwrap();
+    assert!(request.join().unwrap().contains("since: 3"));
+
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[0], "id,kind,owner,amount,balance,target_chain_id,target_owner,timestamp");
+    assert!(lines[1].starts_with(&format!("3,TransferOut,{},", OWNER)));
+    assert!(lines[1].ends_with(&format!(",{},{},42", CHAIN_ID, OWNER)));
+}
+
+#[tokio::test]
+async fn test_deploy_with_distribution() {
+    let (url, request) = mock_node(json!({ "data": { "createApplication": "new-application-id" } }));
+    let output = run(&url, &[
+        "deploy",
+        "--chain-id", CHAIN_ID,
+        "--bytecode-id", "bytecode",
+        "--name", "Demo",
+        "--symbol", "DMO",
+        "--distribute", &format!("{}=1000", OWNER),
+    ]).await.unwrap();
+    assert_eq!(output, "new-application-id");
+
+    // 部署请求发到节点服务的根地址
+    let request = request.join().unwrap();
+    assert!(request.starts_with("POST / "));
+    assert!(request.contains("createApplication"));
+    assert!(request.contains("DMO"));
+    assert!(request.contains(OWNER));
+}
+
+#[tokio::test]
+async fn test_graphql_errors_are_reported() {
+    let (url, _request) = mock_node(json!({
+        "data": null,
+        "errors": [{ "message": "Snapshot 7 not found", "extensions": { "code": 301 } }]
+    }));
+    let error = run(&url, &["holders", "--chain-id", CHAIN_ID, "--application-id", &application_id()])
+        .await
+        .unwrap_err();
+    match error {
+        fungible_cli::CliError::Client(fungible_client::ClientError::GraphQL(errors)) => {
+            assert_eq!(errors[0].code(), Some(301));
+        }
+        error => panic!("unexpected error {:?}", error),
+    }
+}
+
+#[test]
+fn test_invalid_application_id_is_rejected() {
+    // 程序ID在解析参数时检查，不会发出请求
+    let result = Cli::try_parse_from(["fungible-cli", "balance", "--chain-id", CHAIN_ID, "--application-id", "app", "--owner", OWNER]);
+    assert!(result.is_err());
+}
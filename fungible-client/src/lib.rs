@@ -0,0 +1,399 @@
+use async_graphql::InputType;
+use linera_sdk::base::{Amount, ApplicationId, ChainId, Owner};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use thiserror::Error;
+use fungible::{
+    Account, AccountBalance, AccountOwner, ErrorCodeInfo, ExportedState, InitializationArgument, Lock, OperationOpt, ProcessedRequest, Quota, TokenEvent,
+    TokenMetadata, TransferLimits, TransferSimulation,
+};
+
+pub use self::transport::Transport;
+#[cfg(feature = "http")]
+pub use self::transport::HttpTransport;
+#[cfg(feature = "test")]
+pub use self::transport::ActiveChainTransport;
+
+mod transport;
+
+/*
+fungible应用的Rust客户端：每个查询和修改都有一个构造函数，返回带类型的Query，
+交给FungibleClient通过某种Transport(本地linera service的HTTP接口或者测试里的ActiveChain)执行并解析结果
+*/
+
+// 客户端
+pub struct FungibleClient<T> {
+    transport: T,
+}
+
+impl<T: Transport> FungibleClient<T> {
+    pub fn new(transport: T) -> Self {
+        FungibleClient { transport }
+    }
+
+    // 执行查询并解析结果
+    pub async fn execute<R>(&self, query: Query<R>) -> Result<R, ClientError> {
+        let data = self.transport.execute(query.query()).await?;
+        query.decode(&data)
+    }
+}
+
+/*
+带类型的GraphQL请求：field是响应中要读取的字段，decode把字段的值转成结果类型
+*/
+pub struct Query<R> {
+    query: String,
+    field: &'static str,
+    decode: fn(Value) -> Result<R, ClientError>,
+}
+
+impl<R> Query<R> {
+    fn new(query: String, field: &'static str, decode: fn(Value) -> Result<R, ClientError>) -> Self {
+        Query { query, field, decode }
+    }
+
+    // GraphQL请求字符串
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    // 从响应的data中解析结果
+    pub fn decode(&self, data: &Value) -> Result<R, ClientError> {
+        let value = data.get(self.field).cloned().ok_or(ClientError::MissingField(self.field))?;
+        (self.decode)(value)
+    }
+}
+
+/*
+修改的结果：ActiveChain等直接执行查询服务的方式返回序列化后的操作，需要自己放到区块里面；
+linera service节点服务会直接把操作提交到链上，返回区块哈希
+*/
+#[derive(Debug)]
+pub enum MutationResult {
+    Operation(OperationOpt),
+    Certificate(String),
+}
+
+// 查询服务返回的错误(注意：extensions里面有code和name，参考fungible::ErrorCode)
+#[derive(Clone, Debug, Deserialize)]
+pub struct GraphQLError {
+    pub message: String,
+    #[serde(default)]
+    pub extensions: Option<Value>,
+}
+
+impl GraphQLError {
+    // 错误码
+    pub fn code(&self) -> Option<u16> {
+        self.extensions.as_ref()?.get("code")?.as_u64().map(|code| code as u16)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("GraphQL errors: {0:?}")]
+    GraphQL(Vec<GraphQLError>),
+
+    #[error("Field {0} is missing in the response")]
+    MissingField(&'static str),
+
+    #[error("Failed to decode JSON response")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Failed to decode BCS operation")]
+    Bcs(#[from] bcs::Error),
+
+    #[cfg(feature = "http")]
+    #[error("HTTP request failed")]
+    Http(#[from] reqwest::Error),
+}
+
+// 把参数转成GraphQL字面量(注意：AccountOwner这种枚举也会正确转成 { User: "..." })
+fn arg<T: InputType>(value: &T) -> String {
+    value.to_value().to_string()
+}
+
+fn decode<R: DeserializeOwned>(value: Value) -> Result<R, ClientError> {
+    Ok(serde_json::from_value(value)?)
+}
+
+// balanceOf中的可用余额
+fn decode_available(mut value: Value) -> Result<Amount, ClientError> {
+    decode(value["available"].take())
+}
+
+// 账户没有锁定记录时为空列表
+fn decode_locks(value: Value) -> Result<Vec<Lock>, ClientError> {
+    Ok(decode::<Option<Vec<Lock>>>(value)?.unwrap_or_default())
+}
+
+fn decode_mutation(value: Value) -> Result<MutationResult, ClientError> {
+    match value {
+        Value::String(hash) => Ok(MutationResult::Certificate(hash)),
+        value => {
+            let bytes: Vec<u8> = decode(value)?;
+            Ok(MutationResult::Operation(bcs::from_bytes(&bytes)?))
+        }
+    }
+}
+
+const QUOTA_FIELDS: &str = "maxTransfer ownerRemaining chainRemaining";
+const LIMITS_FIELDS: &str = "maxTransfer dailyOwnerCap dailyChainCap";
+const BALANCE_FIELDS: &str = "available locked";
+const LOCK_FIELDS: &str = "id locker amount expiresAt";
+const REQUEST_FIELDS: &str = "requestId kind amount height timestamp source { chainId owner } target { chainId owner }";
+const EVENT_FIELDS: &str = "id kind owner amount balance targetAccount { chainId owner } timestamp";
+
+/* 查询 */
+
+// 账户的可用余额(和程序调用Balance一样，不包括锁定的代币，已经过期的锁定算作可用)
+pub fn balance(owner: AccountOwner) -> Query<Amount> {
+    Query::new(
+        format!("query {{ balanceOf(owner: {}) {{ available }} }}", arg(&owner)),
+        "balanceOf",
+        decode_available,
+    )
+}
+
+// 账户的可用和锁定余额(已经过期的锁定算作可用)
+pub fn account_balance(owner: AccountOwner) -> Query<AccountBalance> {
+    Query::new(
+        format!("query {{ balanceOf(owner: {}) {{ {} }} }}", arg(&owner), BALANCE_FIELDS),
+        "balanceOf",
+        decode,
+    )
+}
+
+// 账户还没有结算的锁定记录
+pub fn locks(owner: AccountOwner) -> Query<Vec<Lock>> {
+    Query::new(
+        format!("query {{ locks(accountOwner: {}) {{ {} }} }}", arg(&owner), LOCK_FIELDS),
+        "locks",
+        decode_locks,
+    )
+}
+
+// 有余额记录的所有账户
+pub fn holders() -> Query<Vec<AccountOwner>> {
+    Query::new("query { holders }".to_string(), "holders", decode)
+}
+
+// 总供应量(注意：只在部署合约的链上有意义)
+pub fn total_supply() -> Query<Amount> {
+    Query::new("query { totalSupply }".to_string(), "totalSupply", decode)
+}
+
+// 管理员
+pub fn admin() -> Query<Option<AccountOwner>> {
+    Query::new("query { admin }".to_string(), "admin", decode)
+}
+
+// 某个快照时账户的余额
+pub fn balance_at(owner: AccountOwner, snapshot_id: u64) -> Query<Amount> {
+    Query::new(
+        format!("query {{ balanceAt(owner: {}, snapshotId: {}) }}", arg(&owner), snapshot_id),
+        "balanceAt",
+        decode,
+    )
+}
+
+// 某个快照时的总供应量
+pub fn total_supply_at(snapshot_id: u64) -> Query<Amount> {
+    Query::new(format!("query {{ totalSupplyAt(snapshotId: {}) }}", snapshot_id), "totalSupplyAt", decode)
+}
+
+// 当前链的转账限额
+pub fn limits() -> Query<TransferLimits> {
+    Query::new(format!("query {{ limits {{ {} }} }}", LIMITS_FIELDS), "limits", decode)
+}
+
+// 账户在当前链上今天剩余的转账额度
+pub fn remaining_quota(owner: AccountOwner) -> Query<Quota> {
+    Query::new(
+        format!("query {{ remainingQuota(owner: {}) {{ {} }} }}", arg(&owner), QUOTA_FIELDS),
+        "remainingQuota",
+        decode,
+    )
+}
+
+// 模拟一笔转账
+pub fn simulate_transfer(owner: AccountOwner, amount: Amount, target: Account, signer: Option<Owner>) -> Query<TransferSimulation> {
+    Query::new(
+        format!(
+            "query {{ simulateTransfer(owner: {}, amount: {}, target: {}, signer: {}) {{ \
+             success errorCode errorMessage authenticated \
+             sourceBalance sourceBalanceAfter targetBalanceAfter crossChainMessage }} }}",
+            arg(&owner),
+            arg(&amount),
+            arg(&target),
+            arg(&signer)
+        ),
+        "simulateTransfer",
+        decode,
+    )
+}
+
+// 代币信息
+pub fn metadata() -> Query<Option<TokenMetadata>> {
+    Query::new("query { metadata { name symbol decimals } }".to_string(), "metadata", decode)
+}
+
+// 最近一次ExportSnapshot导出的状态
+pub fn last_export() -> Query<Option<ExportedState>> {
+    Query::new("query { lastExport { height state hash } }".to_string(), "lastExport", decode)
+}
+
+// 账户的某个请求有没有执行过，None表示没有执行过或者记录已经过期
+pub fn request_status(owner: AccountOwner, request_id: &str) -> Query<Option<ProcessedRequest>> {
+    Query::new(
+        format!(
+            "query {{ requestStatus(owner: {}, requestId: {}) {{ {} }} }}",
+            arg(&owner),
+            arg(&request_id.to_string()),
+            REQUEST_FIELDS
+        ),
+        "requestStatus",
+        decode,
+    )
+}
+
+// 所有错误码
+pub fn error_codes() -> Query<Vec<ErrorCodeInfo>> {
+    Query::new("query { errorCodes { code name } }".to_string(), "errorCodes", decode)
+}
+
+/* 订阅(注意：每次请求返回since之后的所有事件，客户端收到节点服务的新区块通知后带上since重新订阅) */
+
+// 账户余额变化
+pub fn balance_changed(owner: AccountOwner, since: u64) -> Query<Vec<TokenEvent>> {
+    Query::new(
+        format!("subscription {{ balanceChanged(owner: {}, since: {}) {{ {} }} }}", arg(&owner), since, EVENT_FIELDS),
+        "balanceChanged",
+        decode,
+    )
+}
+
+// 账户的转账
+pub fn transfers(owner: AccountOwner, since: u64) -> Query<Vec<TokenEvent>> {
+    Query::new(
+        format!("subscription {{ transfers(owner: {}, since: {}) {{ {} }} }}", arg(&owner), since, EVENT_FIELDS),
+        "transfers",
+        decode,
+    )
+}
+
+/* 节点服务(注意：下面的请求要发到节点服务的根地址，比如 http://localhost:8080，而不是应用地址) */
+
+// 用已经发布的字节码创建fungible应用，返回程序ID
+pub fn create_application(chain_id: ChainId, bytecode_id: &str, argument: &InitializationArgument) -> Result<Query<String>, ClientError> {
+    // 参数和部署参数都是JSON字符串
+    let argument = serde_json::to_string(argument)?;
+    Ok(Query::new(
+        format!(
+            "mutation {{ createApplication(chainId: {}, bytecodeId: {}, parameters: \"null\", \
+             initializationArgument: {}, requiredApplicationIds: []) }}",
+            arg(&chain_id),
+            arg(&bytecode_id.to_string()),
+            arg(&argument)
+        ),
+        "createApplication",
+        decode,
+    ))
+}
+
+/* 修改(注意：request_id由调用方生成，超时后用同一个request_id重试不会重复执行) */
+
+pub fn transfer(owner: AccountOwner, amount: Amount, target_account: Account, request_id: Option<&str>) -> Query<MutationResult> {
+    Query::new(
+        format!(
+            "mutation {{ transfer(owner: {}, amount: {}, targetAccount: {}, requestId: {}) }}",
+            arg(&owner),
+            arg(&amount),
+            arg(&target_account),
+            arg(&request_id.map(str::to_string))
+        ),
+        "transfer",
+        decode_mutation,
+    )
+}
+
+pub fn claim(source_account: Account, amount: Amount, target_account: Account, request_id: Option<&str>) -> Query<MutationResult> {
+    Query::new(
+        format!(
+            "mutation {{ claim(sourceAccount: {}, amount: {}, targetAccount: {}, requestId: {}) }}",
+            arg(&source_account),
+            arg(&amount),
+            arg(&target_account),
+            arg(&request_id.map(str::to_string))
+        ),
+        "claim",
+        decode_mutation,
+    )
+}
+
+pub fn take_snapshot() -> Query<MutationResult> {
+    Query::new("mutation { takeSnapshot }".to_string(), "takeSnapshot", decode_mutation)
+}
+
+pub fn mint(target_account: Account, amount: Amount, request_id: Option<&str>) -> Query<MutationResult> {
+    Query::new(
+        format!(
+            "mutation {{ mint(targetAccount: {}, amount: {}, requestId: {}) }}",
+            arg(&target_account),
+            arg(&amount),
+            arg(&request_id.map(str::to_string))
+        ),
+        "mint",
+        decode_mutation,
+    )
+}
+
+pub fn burn(owner: AccountOwner, amount: Amount, request_id: Option<&str>) -> Query<MutationResult> {
+    Query::new(
+        format!(
+            "mutation {{ burn(owner: {}, amount: {}, requestId: {}) }}",
+            arg(&owner),
+            arg(&amount),
+            arg(&request_id.map(str::to_string))
+        ),
+        "burn",
+        decode_mutation,
+    )
+}
+
+pub fn set_minter(minter: AccountOwner, enabled: bool) -> Query<MutationResult> {
+    Query::new(
+        format!("mutation {{ setMinter(minter: {}, enabled: {}) }}", arg(&minter), enabled),
+        "setMinter",
+        decode_mutation,
+    )
+}
+
+pub fn transfer_admin(new_admin: AccountOwner) -> Query<MutationResult> {
+    Query::new(
+        format!("mutation {{ transferAdmin(newAdmin: {}) }}", arg(&new_admin)),
+        "transferAdmin",
+        decode_mutation,
+    )
+}
+
+pub fn set_limits(chain_id: ChainId, limits: TransferLimits) -> Query<MutationResult> {
+    Query::new(
+        format!("mutation {{ setLimits(chainId: {}, limits: {}) }}", arg(&chain_id), arg(&limits)),
+        "setLimits",
+        decode_mutation,
+    )
+}
+
+pub fn export_snapshot() -> Query<MutationResult> {
+    Query::new("mutation { exportSnapshot }".to_string(), "exportSnapshot", decode_mutation)
+}
+
+pub fn set_locker(locker: ApplicationId, enabled: bool) -> Query<MutationResult> {
+    Query::new(
+        format!("mutation {{ setLocker(locker: {}, enabled: {}) }}", arg(&locker), enabled),
+        "setLocker",
+        decode_mutation,
+    )
+}
@@ -0,0 +1,95 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::ClientError;
+#[cfg(feature = "http")]
+use crate::GraphQLError;
+
+/*
+执行GraphQL请求的方式，返回响应的data部分
+*/
+#[async_trait(?Send)]
+pub trait Transport {
+    async fn execute(&self, query: &str) -> Result<Value, ClientError>;
+}
+
+/*
+本地linera service的HTTP接口(注意：linera service默认端口是8080，每个应用的地址是/chains/链ID/applications/程序ID)
+*/
+#[cfg(feature = "http")]
+pub struct HttpTransport {
+    client: reqwest::Client,
+    url: String,
+}
+
+#[cfg(feature = "http")]
+impl HttpTransport {
+    // 直接传应用的完整地址
+    pub fn new(url: impl Into<String>) -> Self {
+        HttpTransport { client: reqwest::Client::new(), url: url.into() }
+    }
+
+    // 用节点服务地址、链ID和程序ID拼出应用地址
+    pub fn for_application(
+        node_url: &str,
+        chain_id: linera_sdk::base::ChainId,
+        application_id: linera_sdk::base::ApplicationId,
+    ) -> Self {
+        Self::new(format!("{}/chains/{}/applications/{}", node_url.trim_end_matches('/'), chain_id, application_id))
+    }
+}
+
+#[cfg(feature = "http")]
+#[derive(serde::Deserialize)]
+struct HttpResponse {
+    #[serde(default)]
+    data: Value,
+    #[serde(default)]
+    errors: Vec<GraphQLError>,
+}
+
+#[cfg(feature = "http")]
+#[async_trait(?Send)]
+impl Transport for HttpTransport {
+    async fn execute(&self, query: &str) -> Result<Value, ClientError> {
+        let response: HttpResponse = self.client
+            .post(&self.url)
+            .json(&serde_json::json!({ "query": query }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if !response.errors.is_empty() {
+            return Err(ClientError::GraphQL(response.errors));
+        }
+        Ok(response.data)
+    }
+}
+
+/*
+测试链上的应用(注意：ActiveChain::graphql_query遇到查询错误会直接panic)
+*/
+#[cfg(feature = "test")]
+pub struct ActiveChainTransport<'a> {
+    chain: &'a linera_sdk::test::ActiveChain,
+    application_id: linera_sdk::base::ApplicationId<fungible::FungibleTokenAbi>,
+}
+
+#[cfg(feature = "test")]
+impl<'a> ActiveChainTransport<'a> {
+    pub fn new(
+        chain: &'a linera_sdk::test::ActiveChain,
+        application_id: linera_sdk::base::ApplicationId<fungible::FungibleTokenAbi>,
+    ) -> Self {
+        ActiveChainTransport { chain, application_id }
+    }
+}
+
+#[cfg(feature = "test")]
+#[async_trait(?Send)]
+impl Transport for ActiveChainTransport<'_> {
+    async fn execute(&self, query: &str) -> Result<Value, ClientError> {
+        Ok(self.chain.graphql_query(self.application_id, query.to_string()).await)
+    }
+}
@@ -6,13 +6,14 @@ use linera_sdk::{
     base::{SessionId, WithContractAbi}, CalleeContext, Contract, ExecutionResult, MessageContext,
     OperationContext, SessionCallResult, ViewStateStorage,
 };
-use linera_sdk::base::{Amount, ApplicationId, Owner};
+use linera_sdk::base::{Amount, ApplicationId, BlockHeight, ChainId, Owner};
 use linera_sdk::contract::system_api;
+use linera_views::views::ViewError;
 use thiserror::Error;
 
-use fungible::{Account, AccountOwner, ApplicationCallOpt, MessageOpt, OperationOpt};
+use fungible::{export_hash, Account, AccountOwner, ApplicationCallOpt, ErrorCode, EventKind, ExportedState, InitialBalance, InitializationArgument, MessageOpt, OperationOpt, ProcessedRequest, RequestKind, ResponseOpt, Snapshot, StateExport, TransferLimits};
 
-use crate::state::InsufficientBalanceError;
+use crate::state::{InsufficientBalanceError, LockNotFoundError, SCHEMA_VERSION, SnapshotNotFoundError, StateError, TransferLimitError};
 
 use self::state::FungibleToken;
 
@@ -38,18 +39,62 @@ impl Contract for FungibleToken {
     type Storage = ViewStateStorage<Self>;
 
     // 合约部署时调用(注意： 我们指定了参数amount所以在部署合约的时候需要传递amount参数。--json-argument '"50000"')
-    // 注意：参数_argument是Amount类型是因为我们在lib.rs文件里面将该类型指定为Amount
+    // 注意：参数_argument是InitializationArgument类型是因为我们在lib.rs文件里面将该类型指定为InitializationArgument
     async fn initialize(
         &mut self,
         _context: &OperationContext,
         _argument: Self::InitializationArgument,
     ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
-        log::debug!("{} 正在部署FungibleToken合约，初始余额 {}",_context.authenticated_signer.unwrap(),_argument);
+        log::debug!("{:?} 正在部署FungibleToken合约，参数 {:?}",_context.authenticated_signer,_argument);
+        // 新部署的合约直接是最新的状态结构
+        self.schema_version.set(SCHEMA_VERSION);
         // 获取到调用合约签名者并且合约签名者不为空
         if let Some(owner) = _context.authenticated_signer {
-            //let amount = Amount::from_str("50000").unwrap();
-            // 给合约部署者加余额
-            self.initialize_account(AccountOwner::User(owner), _argument).await;
+            // 合约部署者成为管理员
+            self.admin.set(Some(AccountOwner::User(owner)));
+            match _argument {
+                InitializationArgument::Amount(amount) => {
+                    // 给合约部署者加余额
+                    self.initialize_account(AccountOwner::User(owner), amount).await?;
+                    self.emit(EventKind::Mint, AccountOwner::User(owner), amount, None).await?;
+                }
+                InitializationArgument::Distribution {metadata,accounts} => {
+                    self.metadata.set(Some(metadata));
+                    // 同一个账户出现多次时余额累加
+                    for InitialBalance {owner,amount} in accounts {
+                        self.credit(owner, amount).await?;
+                        self.mint(amount);
+                        self.emit(EventKind::Mint, owner, amount, None).await?;
+                    }
+                }
+                InitializationArgument::Import {state,hash} => {
+                    // 先验证哈希再解码，导出的状态里没有管理员时部署者还是管理员
+                    let actual = export_hash(&state);
+                    if actual != hash {
+                        return Err(ContractError::InvalidExport {expected: hash, actual});
+                    }
+                    let mut export: StateExport = bcs::from_bytes(&state)?;
+                    // 余额总和要等于总供应量，否则说明其它链上还有余额没有Claim，或者不是在部署合约的链上导出的
+                    let balances = export.accounts
+                        .iter()
+                        .fold(Amount::default(), |sum, account| sum.saturating_add(account.balance.total()));
+                    // 旧版本升级的总供应量不包括升级时其它链上的余额，Claim回来之后余额总和会更大
+                    let matches = if export.legacy_supply { balances >= export.total_supply } else { balances == export.total_supply };
+                    if !matches {
+                        return Err(ContractError::ExportSupplyMismatch {total_supply: export.total_supply, balances});
+                    }
+                    export.total_supply = balances;
+                    let accounts: Vec<_> = export.accounts.iter().map(|account| (account.owner, account.balance.total())).collect();
+                    let has_admin = export.admin.is_some();
+                    self.import_state(export).await?;
+                    if !has_admin {
+                        self.admin.set(Some(AccountOwner::User(owner)));
+                    }
+                    for (owner,amount) in accounts {
+                        self.emit(EventKind::Mint, owner, amount, None).await?;
+                    }
+                }
+            }
         }
         Ok(ExecutionResult::default())
     }
@@ -60,21 +105,90 @@ impl Contract for FungibleToken {
         context: &OperationContext,
         operation: Self::Operation,
     ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        // 旧版本的状态先升级
+        self.migrate().await?;
         // 匹配操作
         match operation {
             // 如果是转账操作
-            OperationOpt::Transfer { owner, amount, target_account } => {
+            OperationOpt::Transfer { owner, amount, target_account, request_id } => {
                 // 验证签名
                 Self::check_account_authentication(None,context.authenticated_signer, owner)?;
-                // 减去自己账户余额
-                self.debit(owner, amount).await?;
-                // 返回完成转账信息
-                Ok(self.finish_transfer_to_account(amount, target_account).await)
+                // 重复的请求不再执行(注意：第一次执行时的消息已经发出去了，这里不再发送)
+                let request = Self::new_request(request_id,RequestKind::Transfer,amount,None,Some(target_account));
+                if self.processed_response(owner,&request).await?.is_some() {
+                    return Ok(ExecutionResult::default());
+                }
+                // 检查转账限额，减去自己账户余额，返回完成转账信息
+                let result = self.transfer_out(owner, amount, target_account).await?;
+                self.record_processed(owner,request,ResponseOpt::Ok,Some(context.height)).await?;
+                Ok(result)
             }
-            OperationOpt::Claim {source_account,amount,target_account} => {
+            OperationOpt::Claim {source_account,amount,target_account,request_id} => {
                 // 验证spource_account签名
                 Self::check_account_authentication(None,context.authenticated_signer,source_account.owner)?;
-                self.claim(source_account,amount,target_account).await
+                let request = Self::new_request(request_id,RequestKind::Claim,amount,Some(source_account),Some(target_account));
+                if self.processed_response(source_account.owner,&request).await?.is_some() {
+                    return Ok(ExecutionResult::default());
+                }
+                let result = self.claim(source_account,amount,target_account).await?;
+                self.record_processed(source_account.owner,request,ResponseOpt::Ok,Some(context.height)).await?;
+                Ok(result)
+            }
+            OperationOpt::TakeSnapshot => {
+                let snapshot_id = self.take_snapshot(Snapshot {
+                    height: Some(context.height),
+                    timestamp: system_api::current_system_time(),
+                });
+                log::debug!("创建快照 {}",snapshot_id);
+                Ok(ExecutionResult::default())
+            }
+            OperationOpt::Mint {target_account,amount,request_id} => {
+                // 铸币者自己的请求ID
+                let minter = self.check_minter(None,context.authenticated_signer).await?;
+                let request = Self::new_request(request_id,RequestKind::Mint,amount,None,Some(target_account));
+                if self.processed_response(minter,&request).await?.is_some() {
+                    return Ok(ExecutionResult::default());
+                }
+                let result = self.mint_to_account(amount,target_account).await?;
+                self.record_processed(minter,request,ResponseOpt::Ok,Some(context.height)).await?;
+                Ok(result)
+            }
+            OperationOpt::Burn {owner,amount,request_id} => {
+                Self::check_account_authentication(None,context.authenticated_signer,owner)?;
+                let request = Self::new_request(request_id,RequestKind::Burn,amount,None,None);
+                if self.processed_response(owner,&request).await?.is_some() {
+                    return Ok(ExecutionResult::default());
+                }
+                let result = self.burn_from_account(owner,amount).await?;
+                self.record_processed(owner,request,ResponseOpt::Ok,Some(context.height)).await?;
+                Ok(result)
+            }
+            OperationOpt::SetMinter {minter,enabled} => {
+                self.check_admin(None,context.authenticated_signer)?;
+                self.minters.insert(&minter,enabled)?;
+                Ok(ExecutionResult::default())
+            }
+            OperationOpt::TransferAdmin {new_admin} => {
+                self.check_admin(None,context.authenticated_signer)?;
+                self.admin.set(Some(new_admin));
+                Ok(ExecutionResult::default())
+            }
+            OperationOpt::SetLimits {chain_id,limits} => {
+                self.check_admin(None,context.authenticated_signer)?;
+                Ok(self.set_limits(chain_id,limits))
+            }
+            OperationOpt::ExportSnapshot => {
+                self.check_admin(None,context.authenticated_signer)?;
+                let state = bcs::to_bytes(&self.export_state().await?)?;
+                let hash = export_hash(&state);
+                log::debug!("导出状态 {}",hash);
+                self.last_export.set(Some(ExportedState {height: context.height, state, hash}));
+                Ok(ExecutionResult::default())
+            }
+            OperationOpt::SetLocker {locker,enabled} => {
+                self.check_admin(None,context.authenticated_signer)?;
+                self.lockers.insert(&locker,enabled)?;
+                Ok(ExecutionResult::default())
             }
         }
     }
@@ -85,17 +199,32 @@ impl Contract for FungibleToken {
         _context: &MessageContext,
         _message: Self::Message,
     ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        self.migrate().await?;
         // 匹配操作
         match _message {
             MessageOpt::Credit { amount, owner } => {
-                self.credit(owner, amount).await;
+                self.credit(owner, amount).await?;
+                self.emit(EventKind::TransferIn, owner, amount, None).await?;
                 Ok(ExecutionResult::default())
             }
             MessageOpt::Withdraw {owner,amount,target_account } => {
                 // 验证owner签名
                 Self::check_account_authentication(None,_context.authenticated_signer,owner)?;
-                self.debit(owner,amount).await?;
-                Ok(self.finish_transfer_to_account(amount,target_account).await)
+                self.transfer_out(owner,amount,target_account).await
+            }
+            MessageOpt::Burned {amount} => {
+                // 只有部署合约的链记录总供应量
+                self.burn(amount);
+                Ok(ExecutionResult::default())
+            }
+            MessageOpt::SetLimits {limits} => {
+                // 只接受部署合约的链发来的限额(管理员在那条链上已经验证过)
+                if _context.message_id.chain_id == system_api::current_application_id().creation.chain_id {
+                    self.limits.set(limits);
+                } else {
+                    log::warn!("忽略不是部署合约的链发来的转账限额 {:?}",_context.message_id.chain_id);
+                }
+                Ok(ExecutionResult::default())
             }
         }
     }
@@ -106,28 +235,148 @@ impl Contract for FungibleToken {
         call: Self::ApplicationCall,
         _forwarded_sessions: Vec<SessionId>,
     ) -> Result<ApplicationCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        self.migrate().await?;
         match call {
             ApplicationCallOpt::Balance {owner} => {
                 let mut res = ApplicationCallResult::default();
-                let balance = self.balance(&owner).await;
-                // 注意：value是Amount类型是因为我们在lib.rs文件里面把Response的类型定义成了Amount
-                res.value = balance;
+                // 只返回可以转出的部分
+                let balance = self.current_balance(&owner,system_api::current_system_time()).await?.available;
+                // 注意：value是ResponseOpt类型是因为我们在lib.rs文件里面把Response的类型定义成了ResponseOpt
+                res.value = ResponseOpt::Amount(balance);
+                Ok(res)
+            }
+            ApplicationCallOpt::BalanceAt {owner,snapshot_id} => {
+                let mut res = ApplicationCallResult::default();
+                res.value = ResponseOpt::Amount(self.balance_at(&owner,snapshot_id).await?);
                 Ok(res)
             }
-            ApplicationCallOpt::Transfer {owner,amount,target_account} => {
+            ApplicationCallOpt::TotalSupplyAt {snapshot_id} => {
+                let mut res = ApplicationCallResult::default();
+                res.value = ResponseOpt::Amount(self.total_supply_at(snapshot_id)?);
+                Ok(res)
+            }
+            ApplicationCallOpt::TakeSnapshot => {
+                // 只有管理员和锁定程序可以创建快照(比如作为管理员的治理程序)
+                if self.check_admin(context.authenticated_caller_id,context.authenticated_signer).is_err() {
+                    self.check_locker(context.authenticated_caller_id).await?;
+                }
+                let mut res = ApplicationCallResult::default();
+                // 相同链程序调用的上下文中没有区块高度
+                let snapshot_id = self.take_snapshot(Snapshot {
+                    height: None,
+                    timestamp: system_api::current_system_time(),
+                });
+                res.value = ResponseOpt::SnapshotId(snapshot_id);
+                Ok(res)
+            }
+            ApplicationCallOpt::Transfer {owner,amount,target_account,request_id} => {
                 // 验证发起者签名
                 Self::check_account_authentication(context.authenticated_caller_id,context.authenticated_signer,owner)?;
-                self.debit(owner,amount).await?;
                 let mut res = ApplicationCallResult::default();
-                let execution_res = self.finish_transfer_to_account(amount,target_account).await;
+                // 重复的请求直接返回第一次的结果
+                let request = Self::new_request(request_id,RequestKind::Transfer,amount,None,Some(target_account));
+                if let Some(response) = self.processed_response(owner,&request).await? {
+                    res.value = response;
+                    return Ok(res);
+                }
+                let execution_res = self.transfer_out(owner,amount,target_account).await?;
                 res.execution_result = execution_res;
+                self.record_processed(owner,request,res.value,None).await?;
                 Ok(res)
             }
-            ApplicationCallOpt::Claim {source_account,amount,target_account} => {
+            ApplicationCallOpt::Claim {source_account,amount,target_account,request_id} => {
                 Self::check_account_authentication(context.authenticated_caller_id,context.authenticated_signer,source_account.owner)?;
                 let mut res = ApplicationCallResult::default();
+                let request = Self::new_request(request_id,RequestKind::Claim,amount,Some(source_account),Some(target_account));
+                if let Some(response) = self.processed_response(source_account.owner,&request).await? {
+                    res.value = response;
+                    return Ok(res);
+                }
                 let execution_res = self.claim(source_account,amount,target_account).await?;
                 res.execution_result = execution_res;
+                self.record_processed(source_account.owner,request,res.value,None).await?;
+                Ok(res)
+            }
+            ApplicationCallOpt::Mint {target_account,amount,request_id} => {
+                let minter = self.check_minter(context.authenticated_caller_id,context.authenticated_signer).await?;
+                let mut res = ApplicationCallResult::default();
+                let request = Self::new_request(request_id,RequestKind::Mint,amount,None,Some(target_account));
+                if let Some(response) = self.processed_response(minter,&request).await? {
+                    res.value = response;
+                    return Ok(res);
+                }
+                res.execution_result = self.mint_to_account(amount,target_account).await?;
+                self.record_processed(minter,request,res.value,None).await?;
+                Ok(res)
+            }
+            ApplicationCallOpt::Burn {owner,amount,request_id} => {
+                Self::check_account_authentication(context.authenticated_caller_id,context.authenticated_signer,owner)?;
+                let mut res = ApplicationCallResult::default();
+                let request = Self::new_request(request_id,RequestKind::Burn,amount,None,None);
+                if let Some(response) = self.processed_response(owner,&request).await? {
+                    res.value = response;
+                    return Ok(res);
+                }
+                res.execution_result = self.burn_from_account(owner,amount).await?;
+                self.record_processed(owner,request,res.value,None).await?;
+                Ok(res)
+            }
+            ApplicationCallOpt::SetMinter {minter,enabled} => {
+                self.check_admin(context.authenticated_caller_id,context.authenticated_signer)?;
+                self.minters.insert(&minter,enabled)?;
+                Ok(ApplicationCallResult::default())
+            }
+            ApplicationCallOpt::TransferAdmin {new_admin} => {
+                self.check_admin(context.authenticated_caller_id,context.authenticated_signer)?;
+                self.admin.set(Some(new_admin));
+                Ok(ApplicationCallResult::default())
+            }
+            ApplicationCallOpt::SetLimits {chain_id,limits} => {
+                self.check_admin(context.authenticated_caller_id,context.authenticated_signer)?;
+                let mut res = ApplicationCallResult::default();
+                res.execution_result = self.set_limits(chain_id,limits);
+                Ok(res)
+            }
+            ApplicationCallOpt::SetLocker {locker,enabled} => {
+                self.check_admin(context.authenticated_caller_id,context.authenticated_signer)?;
+                self.lockers.insert(&locker,enabled)?;
+                Ok(ApplicationCallResult::default())
+            }
+            ApplicationCallOpt::Lock {owner,amount,expires_at} => {
+                // 调用程序必须是锁定程序，同时要验证owner签名(用户账户需要用户签名的区块触发这次调用)
+                let locker = self.check_locker(context.authenticated_caller_id).await?;
+                Self::check_account_authentication(context.authenticated_caller_id,context.authenticated_signer,owner)?;
+                let lock_id = self.lock(owner,locker,amount,expires_at,system_api::current_system_time()).await?;
+                log::debug!("{:?} 锁定 {:?} 的 {}，锁定ID {}",locker,owner,amount,lock_id);
+                let mut res = ApplicationCallResult::default();
+                res.value = ResponseOpt::LockId(lock_id);
+                Ok(res)
+            }
+            ApplicationCallOpt::Unlock {owner,lock_id} => {
+                // 只能解锁调用程序自己锁定的代币(注意：不要求还是锁定程序，被管理员移除之后也可以释放已经锁定的代币)
+                let locker = context.authenticated_caller_id.ok_or(ContractError::NotLocker {caller: None})?;
+                let amount = self.unlock(owner,locker,lock_id).await?;
+                let mut res = ApplicationCallResult::default();
+                res.value = ResponseOpt::Amount(amount);
+                Ok(res)
+            }
+            ApplicationCallOpt::CheckTransfer {owner,amount} => {
+                let mut res = ApplicationCallResult::default();
+                res.value = match self.check_transfer(owner,amount,system_api::current_system_time()).await {
+                    Ok(_) => ResponseOpt::Ok,
+                    // 存储错误不是检查结果
+                    Err(StateError::View(error)) => return Err(ContractError::Storage(error)),
+                    Err(error) => ResponseOpt::Rejected(error.code()),
+                };
+                Ok(res)
+            }
+            ApplicationCallOpt::CheckMint => {
+                let mut res = ApplicationCallResult::default();
+                res.value = match self.check_minter(context.authenticated_caller_id,context.authenticated_signer).await {
+                    Ok(_) => ResponseOpt::Ok,
+                    Err(ContractError::Storage(error)) => return Err(ContractError::Storage(error)),
+                    Err(error) => ResponseOpt::Rejected(error.code()),
+                };
                 Ok(res)
             }
         }
@@ -155,31 +404,179 @@ impl FungibleToken {
             AccountOwner::User(address) if authenticated_signed == Some(address) => Ok(()),
             // 验证程序ID
             AccountOwner::Application(id) if authenticated_application_id == Some(id) => Ok(()),
-            _ => Err(ContractError::IncorrectAuthentication)
+            _ => Err(ContractError::IncorrectAuthentication {
+                expected: owner,
+                signer: authenticated_signed,
+                caller: authenticated_application_id,
+            })
+        }
+    }
+
+    // 验证调用者是不是管理员
+    fn check_admin(
+        &self,
+        authenticated_application_id: Option<ApplicationId>,
+        authenticated_signed: Option<Owner>) -> Result<(), ContractError> {
+        let admin = *self.admin.get();
+        let not_admin = || ContractError::NotAdmin {
+            admin,
+            signer: authenticated_signed,
+            caller: authenticated_application_id,
+        };
+        let owner = admin.ok_or_else(not_admin)?;
+        Self::check_account_authentication(authenticated_application_id,authenticated_signed,owner)
+            .map_err(|_| not_admin())
+    }
+
+    // 验证调用者是不是铸币者(签名者或调用程序其中一个是铸币者就可以)，返回是铸币者的那个账户
+    async fn check_minter(
+        &self,
+        authenticated_application_id: Option<ApplicationId>,
+        authenticated_signed: Option<Owner>) -> Result<AccountOwner, ContractError> {
+        if let Some(id) = authenticated_application_id {
+            let minter = AccountOwner::Application(id);
+            if self.is_minter(&minter).await? {
+                return Ok(minter);
+            }
+        }
+        if let Some(address) = authenticated_signed {
+            let minter = AccountOwner::User(address);
+            if self.is_minter(&minter).await? {
+                return Ok(minter);
+            }
+        }
+        Err(ContractError::NotMinter {
+            signer: authenticated_signed,
+            caller: authenticated_application_id,
+        })
+    }
+
+    // 带request_id的请求，执行结果和区块高度在执行成功之后再填(没有request_id时返回None)
+    fn new_request(
+        request_id: Option<String>,
+        kind: RequestKind,
+        amount: Amount,
+        source: Option<Account>,
+        target: Option<Account>) -> Option<ProcessedRequest> {
+        request_id.map(|request_id| ProcessedRequest {
+            request_id,
+            kind,
+            amount,
+            response: ResponseOpt::default(),
+            height: None,
+            timestamp: system_api::current_system_time(),
+            source,
+            target,
+        })
+    }
+
+    /*
+    owner已经处理过同一个请求时返回第一次的结果(没有request_id时每次都执行)
+    request_id一样但是请求内容不一样时返回错误，不能把第一次的结果当成这次的结果
+    */
+    async fn processed_response(&self, owner: AccountOwner, request: &Option<ProcessedRequest>) -> Result<Option<ResponseOpt>, ContractError> {
+        let request = match request {
+            Some(request) => request,
+            None => return Ok(None),
+        };
+        let processed = match self.processed_request(&owner, &request.request_id, request.timestamp).await? {
+            Some(processed) => processed,
+            None => return Ok(None),
+        };
+        if !processed.same_request(request) {
+            return Err(ContractError::RequestIdConflict { owner, request_id: request.request_id.clone() });
+        }
+        log::info!("{:?} 的请求 {} 已经处理过，不再执行", owner, request.request_id);
+        Ok(Some(processed.response))
+    }
+
+    // 记录执行成功的请求
+    async fn record_processed(
+        &mut self,
+        owner: AccountOwner,
+        request: Option<ProcessedRequest>,
+        response: ResponseOpt,
+        height: Option<BlockHeight>) -> Result<(), ContractError> {
+        if let Some(request) = request {
+            self.record_request(owner, ProcessedRequest { response, height, ..request }).await?;
+        }
+        Ok(())
+    }
+
+    // 验证调用程序是不是锁定程序，返回调用程序ID
+    async fn check_locker(&self, authenticated_application_id: Option<ApplicationId>) -> Result<ApplicationId, ContractError> {
+        if let Some(id) = authenticated_application_id {
+            if self.is_locker(&id).await? {
+                return Ok(id);
+            }
+        }
+        Err(ContractError::NotLocker {caller: authenticated_application_id})
+    }
+
+    // 铸造代币并转到目标账户
+    async fn mint_to_account(&mut self, amount: Amount, account: Account) -> Result<ExecutionResult<MessageOpt>,ContractError> {
+        self.mint(amount);
+        self.finish_transfer_to_account(amount,account,EventKind::Mint).await
+    }
+
+    // 销毁账户中的代币，不在部署合约的链上时通知部署合约的链减少总供应量
+    async fn burn_from_account(&mut self, owner: AccountOwner, amount: Amount) -> Result<ExecutionResult<MessageOpt>,ContractError> {
+        self.debit(owner,amount,system_api::current_system_time()).await?;
+        self.emit(EventKind::Burn,owner,amount,None).await?;
+        let creator_chain_id = system_api::current_application_id().creation.chain_id;
+        if creator_chain_id == system_api::current_chain_id() {
+            self.burn(amount);
+            Ok(ExecutionResult::default())
+        } else {
+            Ok(ExecutionResult::default().with_message(creator_chain_id,MessageOpt::Burned {amount}))
+        }
+    }
+
+    // 设置某条链的转账限额，不是当前链时发消息给那条链
+    fn set_limits(&mut self, chain_id: ChainId, limits: TransferLimits) -> ExecutionResult<MessageOpt> {
+        if chain_id == system_api::current_chain_id() {
+            self.limits.set(limits);
+            ExecutionResult::default()
+        } else {
+            ExecutionResult::default().with_message(chain_id,MessageOpt::SetLimits {limits})
         }
     }
 
-    // 完成转账
-    async fn finish_transfer_to_account(&mut self, amount: Amount, account: Account) -> ExecutionResult<MessageOpt> {
+    // 检查转账限额、减去转出账户余额并转到目标账户(Transfer、Claim和Withdraw共用)
+    async fn transfer_out(&mut self, owner: AccountOwner, amount: Amount, target_account: Account) -> Result<ExecutionResult<MessageOpt>,ContractError> {
+        let now = system_api::current_system_time();
+        self.record_outflow(owner, amount, now).await?;
+        self.debit(owner, amount, now).await?;
+        self.emit(EventKind::TransferOut, owner, amount, Some(target_account)).await?;
+        self.finish_transfer_to_account(amount, target_account, EventKind::TransferIn).await
+    }
+
+    // 记录余额变化事件
+    async fn emit(&mut self, kind: EventKind, owner: AccountOwner, amount: Amount, target_account: Option<Account>) -> Result<(), ContractError> {
+        Ok(self.push_event(kind, owner, amount, target_account, system_api::current_system_time()).await?)
+    }
+
+    // 完成转账(kind是目标账户在当前链上时记录的事件类型)
+    async fn finish_transfer_to_account(&mut self, amount: Amount, account: Account, kind: EventKind) -> Result<ExecutionResult<MessageOpt>,ContractError> {
         // 如果账户的链ID等于当前链ID
         if account.chain_id == system_api::current_chain_id() {
             // 目标账户增加余额
-            self.credit(account.owner, amount).await;
-            ExecutionResult::default()
+            self.credit(account.owner, amount).await?;
+            self.emit(kind, account.owner, amount, None).await?;
+            Ok(ExecutionResult::default())
         } else {
             let message = MessageOpt::Credit {
                 owner: account.owner,
                 amount: amount,
             };
             // 不签名调用其它链或程序
-            ExecutionResult::default().with_message(account.chain_id, message)
+            Ok(ExecutionResult::default().with_message(account.chain_id, message))
         }
     }
     // 获取代币
     async fn claim(&mut self,source_account: Account,amount:Amount,target_account:Account) -> Result<ExecutionResult<MessageOpt>,ContractError> {
         if source_account.chain_id == system_api::current_chain_id() {
-            self.debit(source_account.owner,amount).await?;
-            Ok(self.finish_transfer_to_account(amount,target_account).await)
+            self.transfer_out(source_account.owner,amount,target_account).await
         } else {
             let message = MessageOpt::Withdraw {
                 owner: source_account.owner,
@@ -192,25 +589,146 @@ impl FungibleToken {
     }
 }
 
-// 自定义一些合约错误
+/*
+自定义一些合约错误(注意：错误信息以[错误码]开头，错误码定义在lib.rs的ErrorCode里面，客户端可以按错误码本地化和处理)
+*/
 #[derive(Debug, Error)]
 pub enum ContractError {
-    #[error("Failed to deserialize BCS bytes")]
+    #[error("[{}] Failed to deserialize BCS bytes: {0}", ErrorCode::BcsError.code())]
     BcsError(#[from] bcs::Error),
 
-    #[error("Failed to deserialize JSON string")]
+    #[error("[{}] Failed to deserialize JSON string: {0}", ErrorCode::JsonError.code())]
     JsonError(#[from] serde_json::Error),
 
-    // 签名验证失败错误
-    #[error("Incorrect Authentication")]
-    IncorrectAuthentication,
+    #[error("[{}] Session not supported", ErrorCode::SessionNotSupported.code())]
+    SessionNotSupported,
 
-    // 如果碰到InsufficientBalanceError错误会自动转成InsufficientBalance错误
-    #[error("Insufficient Balance")]
-    InsufficientBalance(#[from] InsufficientBalanceError),
+    // 存储读写失败，拒绝这次操作
+    #[error("[{}] Storage error: {0}", ErrorCode::Storage.code())]
+    Storage(#[from] ViewError),
 
-    #[error("Session not supported")]
-    SessionNotSupported,
+    // 签名验证失败错误(expected是需要验证的账户，signer和caller是实际的签名者和调用程序)
+    #[error("[{}] Incorrect authentication: expected {expected:?}, signer {signer:?}, caller {caller:?}", ErrorCode::IncorrectAuthentication.code())]
+    IncorrectAuthentication { expected: AccountOwner, signer: Option<Owner>, caller: Option<ApplicationId> },
+
+    // 不是管理员
+    #[error("[{}] Only the admin {admin:?} can do this: signer {signer:?}, caller {caller:?}", ErrorCode::NotAdmin.code())]
+    NotAdmin { admin: Option<AccountOwner>, signer: Option<Owner>, caller: Option<ApplicationId> },
+
+    // 不是铸币者
+    #[error("[{}] Only a minter can mint: signer {signer:?}, caller {caller:?}", ErrorCode::NotMinter.code())]
+    NotMinter { signer: Option<Owner>, caller: Option<ApplicationId> },
+
+    // 调用程序不是锁定程序
+    #[error("[{}] Only a locker application can do this: caller {caller:?}", ErrorCode::NotLocker.code())]
+    NotLocker { caller: Option<ApplicationId> },
+
+    #[error("[{}] Insufficient balance for {owner:?}: requested {requested}, available {available}", ErrorCode::InsufficientBalance.code())]
+    InsufficientBalance { owner: AccountOwner, requested: Amount, available: Amount },
+
+    // 查询的快照还没有创建
+    #[error("[{}] Snapshot {snapshot_id} not found", ErrorCode::SnapshotNotFound.code())]
+    SnapshotNotFound { snapshot_id: u64 },
+
+    // 导入的状态和哈希不一致
+    #[error("[{}] Invalid export: expected hash {expected}, actual {actual}", ErrorCode::InvalidExport.code())]
+    InvalidExport { expected: String, actual: String },
+
+    // 导入的余额总和和总供应量不一致
+    #[error("[{}] Invalid export: total supply {total_supply}, sum of balances {balances}", ErrorCode::InvalidExport.code())]
+    ExportSupplyMismatch { total_supply: Amount, balances: Amount },
+
+    // 锁定记录不存在(不存在、不是调用程序锁定的或者已经过期结算掉了)
+    #[error("[{}] Lock {lock_id} of {owner:?} not found", ErrorCode::LockNotFound.code())]
+    LockNotFound { owner: AccountOwner, lock_id: u64 },
+
+    // 同一个request_id已经用在了内容不一样的请求上
+    #[error("[{}] Request {request_id} of {owner:?} was already used for a different request", ErrorCode::RequestIdConflict.code())]
+    RequestIdConflict { owner: AccountOwner, request_id: String },
+
+    // 超过转账限额
+    #[error("[{}] Amount {amount} exceeds the maximum transfer amount {max_transfer}", ErrorCode::MaxTransferExceeded.code())]
+    MaxTransferExceeded { amount: Amount, max_transfer: Amount },
+
+    #[error("[{}] Amount {amount} exceeds the remaining daily quota {remaining} of {owner:?}", ErrorCode::OwnerCapExceeded.code())]
+    OwnerCapExceeded { owner: AccountOwner, amount: Amount, remaining: Amount },
+
+    #[error("[{}] Amount {amount} exceeds the remaining daily quota {remaining} of the chain", ErrorCode::ChainCapExceeded.code())]
+    ChainCapExceeded { amount: Amount, remaining: Amount },
+}
+
+impl ContractError {
+    // 错误对应的错误码
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ContractError::BcsError(_) => ErrorCode::BcsError,
+            ContractError::JsonError(_) => ErrorCode::JsonError,
+            ContractError::SessionNotSupported => ErrorCode::SessionNotSupported,
+            ContractError::Storage(_) => ErrorCode::Storage,
+            ContractError::IncorrectAuthentication { .. } => ErrorCode::IncorrectAuthentication,
+            ContractError::NotAdmin { .. } => ErrorCode::NotAdmin,
+            ContractError::NotMinter { .. } => ErrorCode::NotMinter,
+            ContractError::NotLocker { .. } => ErrorCode::NotLocker,
+            ContractError::InsufficientBalance { .. } => ErrorCode::InsufficientBalance,
+            ContractError::SnapshotNotFound { .. } => ErrorCode::SnapshotNotFound,
+            ContractError::InvalidExport { .. } => ErrorCode::InvalidExport,
+            ContractError::ExportSupplyMismatch { .. } => ErrorCode::InvalidExport,
+            ContractError::LockNotFound { .. } => ErrorCode::LockNotFound,
+            ContractError::RequestIdConflict { .. } => ErrorCode::RequestIdConflict,
+            ContractError::MaxTransferExceeded { .. } => ErrorCode::MaxTransferExceeded,
+            ContractError::OwnerCapExceeded { .. } => ErrorCode::OwnerCapExceeded,
+            ContractError::ChainCapExceeded { .. } => ErrorCode::ChainCapExceeded,
+        }
+    }
+}
+
+impl From<InsufficientBalanceError> for ContractError {
+    fn from(error: InsufficientBalanceError) -> Self {
+        let InsufficientBalanceError { owner, requested, available } = error;
+        ContractError::InsufficientBalance { owner, requested, available }
+    }
+}
+
+impl From<SnapshotNotFoundError> for ContractError {
+    fn from(error: SnapshotNotFoundError) -> Self {
+        ContractError::SnapshotNotFound { snapshot_id: error.0 }
+    }
+}
+
+impl From<LockNotFoundError> for ContractError {
+    fn from(error: LockNotFoundError) -> Self {
+        let LockNotFoundError { owner, lock_id } = error;
+        ContractError::LockNotFound { owner, lock_id }
+    }
+}
+
+impl From<TransferLimitError> for ContractError {
+    fn from(error: TransferLimitError) -> Self {
+        match error {
+            TransferLimitError::MaxTransferExceeded { amount, max_transfer } => {
+                ContractError::MaxTransferExceeded { amount, max_transfer }
+            }
+            TransferLimitError::OwnerCapExceeded { owner, amount, remaining } => {
+                ContractError::OwnerCapExceeded { owner, amount, remaining }
+            }
+            TransferLimitError::ChainCapExceeded { amount, remaining } => {
+                ContractError::ChainCapExceeded { amount, remaining }
+            }
+        }
+    }
+}
+
+// 状态异常转成对应的合约错误
+impl From<StateError> for ContractError {
+    fn from(error: StateError) -> Self {
+        match error {
+            StateError::View(error) => ContractError::Storage(error),
+            StateError::InsufficientBalance(error) => error.into(),
+            StateError::SnapshotNotFound(error) => error.into(),
+            StateError::TransferLimit(error) => error.into(),
+            StateError::LockNotFound(error) => error.into(),
+        }
+    }
 }
 
 /*
@@ -223,9 +741,10 @@ pub mod tests {
 
     use futures::FutureExt;
     use linera_sdk::{Contract, OperationContext};
-    use linera_sdk::base::{BlockHeight, ChainId};
-    use linera_sdk::views::ViewStorageContext;
-    use linera_views::views::{View, ViewError};
+    use linera_sdk::base::{BlockHeight, BytecodeId, ChainId, MessageId, Timestamp};
+    use linera_sdk::views::{MapView, ViewStorageContext};
+    use fungible::{AccountBalance, Quota};
+    use linera_views::views::{RootView, View, ViewError};
     use webassembly_test::webassembly_test;
 
     use super::*;
@@ -237,15 +756,135 @@ pub mod tests {
         let result = create_and_init(initial_amount);
         if let Ok(fungible_token) = result {
             let account_owner = AccountOwner::User(creator());
-            let balance = fungible_token.balance(&account_owner).now_or_never().unwrap();
+            let balance = fungible_token.balance(&account_owner).now_or_never().unwrap().unwrap();
             // 测试查询余额(注意：调用now_or_never()函数表示立即执行，如果不调用该函数则不会执行因为链上调用是异步的)
             assert_eq!(balance,initial_amount)
         }
     }
 
+    // 最早版本的状态结构，只有accounts
+    #[derive(RootView)]
+    #[view(context = "ViewStorageContext")]
+    struct LegacyFungibleToken {
+        accounts: MapView<AccountOwner,Amount>,
+    }
+
+    #[webassembly_test]
+    pub fn migrate_legacy_layout() {
+        linera_sdk::test::mock_key_value_store();
+        let owner = AccountOwner::User(creator());
+        let holder = AccountOwner::User(holder());
+        let amount = Amount::from_str("1000").unwrap();
+        let holder_amount = Amount::from_str("500").unwrap();
+        // 用旧版本的结构写入数据
+        let mut legacy = LegacyFungibleToken::load(ViewStorageContext::default()).now_or_never().unwrap().unwrap();
+        legacy.accounts.insert(&owner, amount).unwrap();
+        legacy.accounts.insert(&holder, holder_amount).unwrap();
+        legacy.save().now_or_never().unwrap().unwrap();
+
+        // 新版本可以直接读旧数据，但是版本是0
+        let mut fungible_token = FungibleToken::load(ViewStorageContext::default()).now_or_never().unwrap().unwrap();
+        assert_eq!(*fungible_token.schema_version.get(), 0);
+        assert_eq!(fungible_token.balance(&owner).now_or_never().unwrap().unwrap(), amount);
+
+        // 升级只改版本号和总供应量，不写入账户，重复升级不会改变状态
+        fungible_token.migrate().now_or_never().unwrap().unwrap();
+        fungible_token.migrate().now_or_never().unwrap().unwrap();
+        assert_eq!(*fungible_token.schema_version.get(), SCHEMA_VERSION);
+        // 旧版本没有记录总供应量，用旧余额的总和补上
+        let total_supply = amount.saturating_add(holder_amount);
+        assert_eq!(*fungible_token.total_supply.get(), total_supply);
+        assert_eq!(fungible_token.legacy_accounts.indices().now_or_never().unwrap().unwrap().len(), 2);
+        assert_eq!(fungible_token.holders().now_or_never().unwrap().unwrap().len(), 2);
+
+        // 升级之后创建的快照能查到没有迁移的旧余额
+        fungible_token.take_snapshot(Snapshot { height: None, timestamp: Timestamp::from(0) });
+        assert_eq!(fungible_token.balance_at(&owner, 0).now_or_never().unwrap().unwrap(), amount);
+        assert_eq!(fungible_token.total_supply_at(0).unwrap(), total_supply);
+
+        // 第一次写入时迁移这个账户：旧余额作为可用余额，快照时的余额不变
+        let spent = Amount::from_str("300").unwrap();
+        fungible_token.debit(owner, spent, Timestamp::from(0)).now_or_never().unwrap().unwrap();
+        assert!(fungible_token.legacy_accounts.get(&owner).now_or_never().unwrap().unwrap().is_none());
+        let balance = fungible_token.accounts.get(&owner).now_or_never().unwrap().unwrap();
+        assert_eq!(balance, Some(AccountBalance { available: amount.saturating_sub(spent), locked: Amount::default() }));
+        assert_eq!(fungible_token.balance_at(&owner, 0).now_or_never().unwrap().unwrap(), amount);
+
+        // 没有写入的账户还在旧结构里面，查询和导出都能看到
+        assert_eq!(fungible_token.balance(&holder).now_or_never().unwrap().unwrap(), holder_amount);
+        assert_eq!(fungible_token.balance_at(&holder, 0).now_or_never().unwrap().unwrap(), holder_amount);
+        let export = fungible_token.export_state().now_or_never().unwrap().unwrap();
+        assert_eq!(export.accounts.len(), 2);
+        assert!(export.legacy_supply);
+    }
+
+    #[webassembly_test]
+    pub fn lock_and_unlock() {
+        let mut fungible_token = create_and_init(Amount::from_str("1000").unwrap()).unwrap();
+        let owner = AccountOwner::User(creator());
+        let amount = Amount::from_str("300").unwrap();
+        let lock_id = fungible_token.lock(owner, locker(), amount, Timestamp::from(100), Timestamp::from(0)).now_or_never().unwrap().unwrap();
+        let balance = fungible_token.account_balance(&owner).now_or_never().unwrap().unwrap();
+        assert_eq!(balance, AccountBalance { available: Amount::from_str("700").unwrap(), locked: amount });
+
+        // 锁定的代币不能转出，但是还算在余额总和里面
+        assert!(fungible_token.debit(owner, Amount::from_str("800").unwrap(), Timestamp::from(0)).now_or_never().unwrap().is_err());
+        assert_eq!(fungible_token.balance(&owner).now_or_never().unwrap().unwrap(), Amount::from_str("1000").unwrap());
+
+        // 解锁后回到可用余额，同一个锁定不能解锁两次
+        assert_eq!(fungible_token.unlock(owner, locker(), lock_id).now_or_never().unwrap().unwrap(), amount);
+        let balance = fungible_token.account_balance(&owner).now_or_never().unwrap().unwrap();
+        assert_eq!(balance, AccountBalance { available: Amount::from_str("1000").unwrap(), locked: Amount::default() });
+        assert!(fungible_token.unlock(owner, locker(), lock_id).now_or_never().unwrap().is_err());
+
+        // 到期之后不用解锁，查询时算作可用，转出时自动结算
+        fungible_token.lock(owner, locker(), amount, Timestamp::from(100), Timestamp::from(0)).now_or_never().unwrap().unwrap();
+        let balance = fungible_token.current_balance(&owner, Timestamp::from(100)).now_or_never().unwrap().unwrap();
+        assert_eq!(balance.available, Amount::from_str("1000").unwrap());
+        fungible_token.debit(owner, Amount::from_str("1000").unwrap(), Timestamp::from(100)).now_or_never().unwrap().unwrap();
+        assert!(fungible_token.locks_of(&owner).now_or_never().unwrap().unwrap().is_empty());
+    }
+
+    #[webassembly_test]
+    pub fn export_and_import_locks() {
+        let mut fungible_token = create_and_init(Amount::from_str("1000").unwrap()).unwrap();
+        let owner = AccountOwner::User(creator());
+        let amount = Amount::from_str("300").unwrap();
+        fungible_token.lockers.insert(&locker(), true).unwrap();
+        let lock_id = fungible_token.lock(owner, locker(), amount, Timestamp::from(100), Timestamp::from(0)).now_or_never().unwrap().unwrap();
+        let export = fungible_token.export_state().now_or_never().unwrap().unwrap();
+
+        // 导入到新的存储之后，锁定的部分还是锁定的，锁定程序还能解锁
+        linera_sdk::test::mock_key_value_store();
+        let mut imported = FungibleToken::load(ViewStorageContext::default()).now_or_never().unwrap().unwrap();
+        imported.import_state(export).now_or_never().unwrap().unwrap();
+        let balance = imported.account_balance(&owner).now_or_never().unwrap().unwrap();
+        assert_eq!(balance, AccountBalance { available: Amount::from_str("700").unwrap(), locked: amount });
+        assert!(imported.is_locker(&locker()).now_or_never().unwrap().unwrap());
+        assert_eq!(*imported.next_lock_id.get(), lock_id + 1);
+        assert_eq!(imported.unlock(owner, locker(), lock_id).now_or_never().unwrap().unwrap(), amount);
+    }
+
+    #[webassembly_test]
+    pub fn application_accounts_skip_limits() {
+        let mut fungible_token = create_and_init(Amount::from_str("1000").unwrap()).unwrap();
+        let owner = AccountOwner::User(creator());
+        let escrow = AccountOwner::Application(locker());
+        let amount = Amount::from_str("50").unwrap();
+        fungible_token.limits.set(TransferLimits { max_transfer: Some(Amount::from_str("10").unwrap()), ..TransferLimits::default() });
+        fungible_token.credit(escrow, amount).now_or_never().unwrap().unwrap();
+
+        // 用户账户超过单笔限额，程序托管的账户不受限制
+        assert!(fungible_token.record_outflow(owner, amount, Timestamp::from(0)).now_or_never().unwrap().is_err());
+        fungible_token.record_outflow(escrow, amount, Timestamp::from(0)).now_or_never().unwrap().unwrap();
+        assert_eq!(fungible_token.remaining_quota(&escrow, Timestamp::from(0)).now_or_never().unwrap().unwrap(), Quota::default());
+    }
+
     fn create_and_init(amount: Amount) -> Result<FungibleToken, ViewError> {
         // 模拟创建键值存储
         linera_sdk::test::mock_key_value_store();
+        // 部署时会记录余额变化事件，需要模拟系统时间
+        linera_sdk::test::mock_system_timestamp(Timestamp::from(0));
         // 拿到键值存储上下文
         let store = ViewStorageContext::default();
         // 给FungibleToken结构体对象加载 键值存储对象上下文(注意：FungibleToken本身就是ViewStorage因为代码上加了ViewStorageContext标识)
@@ -255,7 +894,7 @@ pub mod tests {
             // Result有两个范型，？号表示直接取左边的数据，如果没取到就直接返回右边的数据(注意：这个？号表达式有个前提就是当前这个函数的返回值是Result类型)
             let mut fungible_token = load_res?;
             // 测试部署合约回调函数 initialize 的逻辑是否正确
-            let result = fungible_token.initialize(&dummy_context(), amount).now_or_never().unwrap();
+            let result = fungible_token.initialize(&dummy_context(), amount.into()).now_or_never().unwrap();
             assert!(result.is_ok());
             return Ok(fungible_token);
         } else {
@@ -275,6 +914,22 @@ pub mod tests {
         }
     }
 
+    fn locker() -> ApplicationId {
+        let message_id = MessageId {
+            chain_id: ChainId([0; 4].into()),
+            height: BlockHeight(0),
+            index: 0,
+        };
+        ApplicationId {
+            bytecode_id: BytecodeId::new(message_id),
+            creation: message_id,
+        }
+    }
+
+    fn holder() -> Owner {
+        "e476187f6ddfeb9d588c7b45d3df334d5501d6499b3f9ad5595cae86cce16a65".parse().unwrap()
+    }
+
     fn creator() -> Owner {
         return "1c02a28d03e846b113de238d8880df3c9c802143b73aea5d173466701bee1786"
             .parse()
@@ -1,6 +1,8 @@
-use async_graphql::{InputObject, Request, Response, scalar};
-use linera_sdk::base::{Amount, ApplicationId, ChainId, ContractAbi, Owner, ServiceAbi};
+use async_graphql::{Enum, InputObject, Request, Response, scalar, SimpleObject};
+use linera_sdk::base::{Amount, ApplicationId, BlockHeight, ChainId, ContractAbi, Owner, ServiceAbi, Timestamp};
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+
 
 /*
 合约上下文相关定义文件
@@ -12,7 +14,7 @@ pub struct FungibleTokenAbi;
 impl ContractAbi for FungibleTokenAbi {
     type Parameters = ();
     // 合约部署时的参数
-    type InitializationArgument = Amount;
+    type InitializationArgument = InitializationArgument;
     // 操作类型
     type Operation = OperationOpt;
     // 消息类型
@@ -20,7 +22,7 @@ impl ContractAbi for FungibleTokenAbi {
     type ApplicationCall = ApplicationCallOpt;
     type SessionCall = ();
     type SessionState = ();
-    type Response = Amount;
+    type Response = ResponseOpt;
 }
 
 // 合约查询相关Abi
@@ -30,25 +32,144 @@ impl ServiceAbi for FungibleTokenAbi {
     type QueryResponse = Response;
 }
 
-// 相同链程序相互调用操作枚举
+/*
+合约部署参数(注意：不带标签，直接传金额 --json-argument '"50000"' 表示全部发给部署者，和以前一样；
+也可以传 {"metadata": {...}, "accounts": [...]} 设置代币信息并发给多个账户)
+*/
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum InitializationArgument {
+    Amount(Amount),
+    Distribution {
+        metadata: TokenMetadata,
+        accounts: Vec<InitialBalance>
+    },
+    // 导入旧版本用ExportSnapshot导出的状态，hash必须和state的哈希一致
+    Import {
+        state: Vec<u8>,
+        hash: String
+    }
+}
+
+impl From<Amount> for InitializationArgument {
+    fn from(amount: Amount) -> Self {
+        InitializationArgument::Amount(amount)
+    }
+}
+
+// 代币信息
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize, SimpleObject)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+// 部署时的初始余额(注意：都在部署合约的链上)
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct InitialBalance {
+    pub owner: AccountOwner,
+    pub amount: Amount,
+}
+
+/*
+相同链程序相互调用操作枚举(注意：Transfer、Claim、Mint和Burn可以带上调用方生成的request_id，
+同一个账户重复的request_id不会再执行，直接返回第一次的结果；同一个request_id的请求内容不一样时返回RequestIdConflict错误，参考ProcessedRequest)
+*/
 #[derive(Debug,Deserialize,Serialize)]
 pub enum ApplicationCallOpt {
+    // 查询可用余额(注意：不包括锁定的代币，已经过期的锁定算作可用)
     Balance {
         owner: AccountOwner
     },
+    // 查询某个快照时账户的余额
+    BalanceAt {
+        owner: AccountOwner,
+        snapshot_id: u64
+    },
+    // 查询某个快照时的总供应量
+    TotalSupplyAt {
+        snapshot_id: u64
+    },
+    // 创建快照，返回快照ID(注意：只有管理员和锁定程序可以调用)
+    TakeSnapshot,
     Transfer {
         owner: AccountOwner,
         amount: Amount,
-        target_account: Account
+        target_account: Account,
+        #[serde(default)]
+        request_id: Option<String>
     },
     Claim {
         source_account: Account,
         amount: Amount,
-        target_account: Account
-    }
+        target_account: Account,
+        #[serde(default)]
+        request_id: Option<String>
+    },
+    // 铸造代币(注意：只有管理员或铸币者可以调用，只在部署合约的链上有效)
+    Mint {
+        target_account: Account,
+        amount: Amount,
+        #[serde(default)]
+        request_id: Option<String>
+    },
+    // 销毁自己账户中的代币
+    Burn {
+        owner: AccountOwner,
+        amount: Amount,
+        #[serde(default)]
+        request_id: Option<String>
+    },
+    // 添加或移除铸币者(仅管理员)
+    SetMinter {
+        minter: AccountOwner,
+        enabled: bool
+    },
+    // 转移管理员权限(仅管理员)
+    TransferAdmin {
+        new_admin: AccountOwner
+    },
+    // 设置chain_id链上的转账限额(仅管理员，不是当前链时发消息给那条链)
+    SetLimits {
+        chain_id: ChainId,
+        limits: TransferLimits
+    },
+    // 添加或移除锁定程序(仅管理员)
+    SetLocker {
+        locker: ApplicationId,
+        enabled: bool
+    },
+    /*
+    锁定owner账户中的可用代币直到expires_at，返回锁定ID(注意：调用程序必须是锁定程序，并且owner要验证签名；
+    到期之前只有调用程序可以解锁，到期之后自动回到可用余额)
+    */
+    Lock {
+        owner: AccountOwner,
+        amount: Amount,
+        expires_at: Timestamp
+    },
+    // 解锁调用程序自己锁定的代币
+    Unlock {
+        owner: AccountOwner,
+        lock_id: u64
+    },
+    /*
+    预检查owner转出amount(余额、锁定和转账限额)，可以转出时返回Ok，否则返回Rejected和错误码，不修改状态
+    (注意：不检查签名；跨链消息里的转账失败会让整个消息失败，调用方可以先预检查，不能转时自己处理)
+    */
+    CheckTransfer {
+        owner: AccountOwner,
+        amount: Amount
+    },
+    // 预检查调用者能不能铸币(签名者或调用程序是铸币者)，可以铸币时返回Ok，否则返回Rejected和错误码，不修改状态
+    CheckMint
 }
 
-// 操作枚举(注意：操作相关用于前端调用)
+/*
+操作枚举(注意：操作相关用于前端调用；Transfer、Claim、Mint和Burn可以带上钱包生成的request_id，
+网络超时后用同一个request_id重试不会重复扣款)
+*/
 #[derive(Debug, Deserialize, Serialize)]
 pub enum OperationOpt {
     // 转账
@@ -56,14 +177,70 @@ pub enum OperationOpt {
         owner: AccountOwner,
         amount: Amount,
         target_account: Account,
+        #[serde(default)]
+        request_id: Option<String>,
     },
     // 获取代币
     Claim {
         source_account: Account,
         amount: Amount,
-        target_account: Account
+        target_account: Account,
+        #[serde(default)]
+        request_id: Option<String>
+    },
+    // 创建快照(注意：快照ID就是快照日志中的下标，可以在查询服务的snapshots中看到)
+    TakeSnapshot,
+    // 铸造代币(注意：只有管理员或铸币者可以调用，只在部署合约的链上有效)
+    Mint {
+        target_account: Account,
+        amount: Amount,
+        #[serde(default)]
+        request_id: Option<String>
+    },
+    // 销毁自己账户中的代币
+    Burn {
+        owner: AccountOwner,
+        amount: Amount,
+        #[serde(default)]
+        request_id: Option<String>
+    },
+    // 添加或移除铸币者(仅管理员)
+    SetMinter {
+        minter: AccountOwner,
+        enabled: bool
+    },
+    // 转移管理员权限(仅管理员)
+    TransferAdmin {
+        new_admin: AccountOwner
+    },
+    // 设置chain_id链上的转账限额(仅管理员，不是当前链时发消息给那条链)
+    SetLimits {
+        chain_id: ChainId,
+        limits: TransferLimits
+    },
+    // 导出当前链上的状态用于迁移到新版本(仅管理员)，结果在查询服务的lastExport中
+    ExportSnapshot,
+    // 添加或移除锁定程序(仅管理员)
+    SetLocker {
+        locker: ApplicationId,
+        enabled: bool
     }
+}
 
+// 相同链程序相互调用的返回值
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ResponseOpt {
+    // 没有返回值的调用
+    #[default]
+    Ok,
+    // 余额或总供应量
+    Amount(Amount),
+    // TakeSnapshot创建的快照ID
+    SnapshotId(u64),
+    // Lock创建的锁定ID
+    LockId(u64),
+    // CheckTransfer和CheckMint预检查没有通过时的错误码
+    Rejected(ErrorCode)
 }
 
 // 消息枚举(注意：消息相关操作用于内部调用就是链上程序相互调用)
@@ -79,6 +256,105 @@ pub enum MessageOpt {
         owner: AccountOwner,
         amount: Amount,
         target_account: Account
+    },
+    // 其它链上销毁了代币，通知部署合约的链减少总供应量
+    Burned {
+        amount: Amount
+    },
+    // 部署合约的链上的管理员设置了这条链的转账限额
+    SetLimits {
+        limits: TransferLimits
+    }
+}
+
+/*
+余额检查点：epoch是写入时已经创建的快照数量，快照n包含所有epoch小于等于n的检查点
+(注意：没有用区块高度区分，因为相同链程序调用的上下文中拿不到区块高度，而且同一个区块中快照前后的转账也要区分开)
+*/
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, SimpleObject)]
+pub struct Checkpoint {
+    pub epoch: u64,
+    pub amount: Amount,
+}
+
+// 快照信息
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, SimpleObject)]
+pub struct Snapshot {
+    // 创建快照的区块高度(注意：相同链程序调用创建的快照没有区块高度)
+    pub height: Option<BlockHeight>,
+    pub timestamp: Timestamp,
+}
+
+/*
+账户余额：available是可以转出的部分，locked是被程序锁定的部分(注意：锁定的代币还在账户里面，快照和导出按两部分之和计算)
+*/
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize, SimpleObject)]
+pub struct AccountBalance {
+    pub available: Amount,
+    pub locked: Amount,
+}
+
+impl AccountBalance {
+    // 可用和锁定的总和
+    pub fn total(&self) -> Amount {
+        self.available.saturating_add(self.locked)
+    }
+}
+
+// 锁定记录，locker是锁定代币的程序
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct Lock {
+    pub id: u64,
+    pub locker: ApplicationId,
+    pub amount: Amount,
+    pub expires_at: Timestamp,
+}
+
+// 带request_id的请求类型
+#[derive(Clone, Copy, Debug, Deserialize, Enum, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RequestKind {
+    Transfer,
+    Claim,
+    Mint,
+    Burn,
+}
+
+/*
+处理过的请求(注意：只记录执行成功的请求，失败的请求整个区块都会回滚，用同一个request_id重试是安全的；
+每个账户只保留最近的记录，过期之后同一个request_id会被当成新请求；
+重试时类型、数量、源账户和目标账户都要和第一次一样，否则当成request_id冲突)
+*/
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessedRequest {
+    pub request_id: String,
+    pub kind: RequestKind,
+    pub amount: Amount,
+    // 第一次执行时的返回值，重复的程序调用直接返回这个值
+    #[graphql(skip)]
+    #[serde(default)]
+    pub response: ResponseOpt,
+    // 执行时的区块高度(注意：相同链程序调用的上下文中没有区块高度)
+    pub height: Option<BlockHeight>,
+    pub timestamp: Timestamp,
+    // Claim的源账户，其它请求没有
+    #[serde(default)]
+    pub source: Option<Account>,
+    // Transfer、Claim和Mint的目标账户，Burn没有
+    #[serde(default)]
+    pub target: Option<Account>,
+}
+
+impl ProcessedRequest {
+    // 是不是同一个请求(不比较执行结果、区块高度和时间)
+    pub fn same_request(&self, other: &ProcessedRequest) -> bool {
+        self.request_id == other.request_id
+            && self.kind == other.kind
+            && self.amount == other.amount
+            && self.source == other.source
+            && self.target == other.target
     }
 }
 
@@ -92,10 +368,213 @@ pub enum AccountOwner {
 }
 
 /*
-账户信息
+账户信息(注意：同时作为GraphQL输入和输出类型，输出类型名称为AccountOutput，输入类型名称保持Account不变)
 */
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize,InputObject)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize,InputObject,SimpleObject)]
+#[graphql(name = "AccountOutput", input_name = "Account")]
 pub struct Account {
+    // 注意：GraphQL返回的字段名是chainId，加别名方便客户端直接反序列化查询结果
+    #[serde(alias = "chainId")]
     pub chain_id: ChainId,
     pub owner: AccountOwner,
 }
+
+/*
+转账限额，None表示不限制(注意：限额是每条链各自记录的，同时作为GraphQL输入和输出类型)
+*/
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize, InputObject, SimpleObject)]
+#[graphql(input_name = "TransferLimitsInput")]
+#[serde(rename_all = "camelCase")]
+pub struct TransferLimits {
+    // 单笔转出的最大数量
+    pub max_transfer: Option<Amount>,
+    // 每个账户每天最多转出的数量
+    pub daily_owner_cap: Option<Amount>,
+    // 当前链上所有账户每天最多转出的数量
+    pub daily_chain_cap: Option<Amount>,
+}
+
+// 一个时间窗口内的转出数量
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize, SimpleObject)]
+pub struct Outflow {
+    pub window_start: Timestamp,
+    pub amount: Amount,
+}
+
+// 剩余额度，None表示不限制
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct Quota {
+    pub max_transfer: Option<Amount>,
+    pub owner_remaining: Option<Amount>,
+    pub chain_remaining: Option<Amount>,
+}
+
+/*
+错误码(注意：合约错误和查询错误共用这一套错误码，数值一旦发布就不能修改，只能新增)
+1xx通用错误，2xx权限错误，3xx余额和快照错误，4xx转账限额错误，5xx查询错误
+*/
+#[derive(Clone, Copy, Debug, Deserialize, Enum, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    BcsError = 100,
+    JsonError = 101,
+    SessionNotSupported = 102,
+    Storage = 103,
+    IncorrectAuthentication = 200,
+    NotAdmin = 201,
+    NotMinter = 202,
+    NotLocker = 203,
+    InsufficientBalance = 300,
+    SnapshotNotFound = 301,
+    InvalidExport = 302,
+    LockNotFound = 303,
+    RequestIdConflict = 304,
+    MaxTransferExceeded = 400,
+    OwnerCapExceeded = 401,
+    ChainCapExceeded = 402,
+    QueriesNotSupported = 500,
+    InvalidQuery = 501,
+}
+
+impl ErrorCode {
+    // 所有错误码，查询服务的errorCodes返回这个列表
+    pub const ALL: [ErrorCode; 18] = [
+        ErrorCode::BcsError,
+        ErrorCode::JsonError,
+        ErrorCode::SessionNotSupported,
+        ErrorCode::Storage,
+        ErrorCode::IncorrectAuthentication,
+        ErrorCode::NotAdmin,
+        ErrorCode::NotMinter,
+        ErrorCode::NotLocker,
+        ErrorCode::InsufficientBalance,
+        ErrorCode::SnapshotNotFound,
+        ErrorCode::InvalidExport,
+        ErrorCode::LockNotFound,
+        ErrorCode::RequestIdConflict,
+        ErrorCode::MaxTransferExceeded,
+        ErrorCode::OwnerCapExceeded,
+        ErrorCode::ChainCapExceeded,
+        ErrorCode::QueriesNotSupported,
+        ErrorCode::InvalidQuery,
+    ];
+
+    // 数字错误码
+    pub fn code(self) -> u16 {
+        self as u16
+    }
+}
+
+// 错误码信息
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, SimpleObject)]
+pub struct ErrorCodeInfo {
+    pub code: u16,
+    pub name: ErrorCode,
+}
+
+/*
+模拟转账的结果(注意：只是按当前状态预测，真正执行时状态可能已经变化)
+*/
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferSimulation {
+    // 是否会成功
+    pub success: bool,
+    // 失败时的错误码和错误信息
+    pub error_code: Option<ErrorCode>,
+    pub error_message: Option<String>,
+    // 签名验证结果，没有传签名者或者转出账户是程序账户时无法验证为None
+    pub authenticated: Option<bool>,
+    // 转出账户转账前后的可用余额
+    pub source_balance: Amount,
+    pub source_balance_after: Amount,
+    // 目标账户转账后的可用余额，目标账户在其它链上时为None
+    pub target_balance_after: Option<Amount>,
+    // 目标账户在其它链上时会发送Credit消息
+    pub cross_chain_message: bool,
+}
+
+// 余额变化事件类型
+#[derive(Clone, Copy, Debug, Deserialize, Enum, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EventKind {
+    // 转出(包括Transfer、Claim和其它链发来的Withdraw)
+    TransferOut,
+    // 转入(注意：其它链上铸造到这条链的代币也是通过Credit消息转入的，所以也是TransferIn)
+    TransferIn,
+    Mint,
+    Burn,
+}
+
+/*
+余额变化事件，按账户保存，查询服务的订阅从这里读取(注意：id在整条链上递增，订阅时用since传入上次收到的id+1)
+*/
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenEvent {
+    pub id: u64,
+    pub kind: EventKind,
+    pub owner: AccountOwner,
+    pub amount: Amount,
+    // 事件之后的余额(可用和锁定的总和)
+    pub balance: Amount,
+    // 转出的目标账户，其它事件没有
+    pub target_account: Option<Account>,
+    pub timestamp: Timestamp,
+}
+
+// 这个是graphql解析使用
+scalar!(EventKey);
+
+// 某个账户的第index个事件，用作MapView的键
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct EventKey {
+    pub owner: AccountOwner,
+    pub index: u64,
+}
+
+/*
+迁移用的状态导出(注意：列表都排好序，同样的状态得到同样的BCS字节和哈希)
+只包含当前链上的余额，其它链上的余额需要先Claim到部署合约的链上再导出，导入时要求余额总和等于总供应量；
+legacy_supply为true时总供应量是升级时从当前链上的旧余额加出来的，不包括当时其它链上的余额，
+导入时只要求余额总和不小于总供应量，导入后以余额总和作为总供应量；
+余额分成可用和锁定两部分导出，连同锁定记录、锁定程序和下一个锁定ID一起导入，锁定程序导入后还能解锁；
+当前版本的代币没有授权额度(allowance)，所以也没有对应的字段
+*/
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StateExport {
+    pub accounts: Vec<ExportedAccount>,
+    pub total_supply: Amount,
+    pub admin: Option<AccountOwner>,
+    pub minters: Vec<AccountOwner>,
+    pub limits: TransferLimits,
+    pub metadata: Option<TokenMetadata>,
+    pub lockers: Vec<ApplicationId>,
+    pub next_lock_id: u64,
+    pub legacy_supply: bool,
+}
+
+// 导出的账户：locks是还没有结算的锁定记录，它们的总和就是balance.locked
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ExportedAccount {
+    pub owner: AccountOwner,
+    pub balance: AccountBalance,
+    pub locks: Vec<Lock>,
+}
+
+// 导出结果：state是StateExport的BCS字节，hash是state的sha3-256哈希(十六进制)
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, SimpleObject)]
+pub struct ExportedState {
+    pub height: BlockHeight,
+    pub state: Vec<u8>,
+    pub hash: String,
+}
+
+// 导出状态的哈希
+pub fn export_hash(state: &[u8]) -> String {
+    Sha3_256::digest(state)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -2,14 +2,17 @@
 
 mod state;
 
-use self::state::FungibleToken;
+use self::state::{FungibleToken, StateError, TransferLimitError, EVENTS_PAGE_SIZE};
 use async_trait::async_trait;
 use linera_sdk::{base::WithServiceAbi, QueryContext, Service, ViewStateStorage};
 use std::sync::Arc;
-use async_graphql::{EmptySubscription, Object, Response, Schema};
-use linera_sdk::base::{Amount};
+use async_graphql::{ErrorExtensions, InputType, MergedObject, Name, Object, Request, Response, Schema, Subscription, Value};
+use async_graphql::parser::{self, types::{DocumentOperations, OperationType, Selection}};
+use futures::{Stream, StreamExt, stream};
+use linera_sdk::base::{Amount, ApplicationId, ChainId, Owner};
+use linera_sdk::service::system_api;
 use thiserror::Error;
-use fungible::{Account, AccountOwner, OperationOpt};
+use fungible::{Account, AccountBalance, AccountOwner, ErrorCode, ErrorCodeInfo, EventKind, OperationOpt, ProcessedRequest, Quota, TokenEvent, TransferLimits, TransferSimulation};
 
 /*
 查询服务相关实现(就是定义一些只读的数据给前端调用)
@@ -33,29 +36,321 @@ impl Service for FungibleToken {
         _context: &QueryContext,
         request: Self::Query,
     ) -> Result<Response, Self::Error> {
+        // 状态视图和自定义查询合并成一个查询根
+        let query_root = QueryRoot(self.clone(), TokenQuery { state: self.clone() });
         // 注意：这个MutationRoot可以使用GraphQLMutationRoot注解生成，具体可以参考meta-fungible模块lib.rs文件OperationPP枚举，然后看service.rs怎么使用
-        let schema = Schema::build(self.clone(),MutationRoot {},EmptySubscription).finish();
-        let response = schema.execute(request).await;
+        let schema = Schema::build(query_root,MutationRoot {},SubscriptionRoot { state: self.clone() }).finish();
+        // 订阅请求把已经产生的事件合并成一个响应返回
+        let response = match subscription_fields(&request) {
+            Some(fields) => execute_subscription(&schema, request, fields).await,
+            None => schema.execute(request).await,
+        };
         return Ok(response);
     }
 }
 
+#[derive(MergedObject)]
+struct QueryRoot(Arc<FungibleToken>, TokenQuery);
+
+// 需要计算的查询
+struct TokenQuery {
+    state: Arc<FungibleToken>,
+}
+
+#[Object]
+impl TokenQuery {
+    // 查询账户当前的可用和锁定余额(注意：accounts里面是上次操作时的余额，这里把已经过期的锁定算作可用)
+    async fn balance_of(&self, owner: AccountOwner) -> async_graphql::Result<AccountBalance> {
+        self.state.current_balance(&owner, system_api::current_system_time()).await.map_err(graphql_error)
+    }
+
+    // 有余额记录的所有账户(注意：accountsKeys里面没有还没迁移的旧账户，这里包括)
+    async fn holders(&self) -> async_graphql::Result<Vec<AccountOwner>> {
+        self.state.holders().await.map_err(graphql_error)
+    }
+
+    // 查询某个快照时账户的余额
+    async fn balance_at(&self, owner: AccountOwner, snapshot_id: u64) -> async_graphql::Result<Amount> {
+        self.state.balance_at(&owner, snapshot_id).await.map_err(graphql_error)
+    }
+
+    // 查询某个快照时的总供应量
+    async fn total_supply_at(&self, snapshot_id: u64) -> async_graphql::Result<Amount> {
+        self.state.total_supply_at(snapshot_id).map_err(|error| graphql_error(error.into()))
+    }
+
+    // 查询账户在当前链上今天剩余的转账额度
+    async fn remaining_quota(&self, owner: AccountOwner) -> async_graphql::Result<Quota> {
+        self.state.remaining_quota(&owner, system_api::current_system_time()).await.map_err(graphql_error)
+    }
+
+    /*
+    模拟一笔转账(注意：和合约执行Transfer一样检查签名、转账限额和余额，但不产生操作；当前代币没有手续费)
+    signer是将要签名的用户，不传时不检查签名；在源账户所在的链上查询也可以用来预测Claim
+    */
+    async fn simulate_transfer(
+        &self,
+        owner: AccountOwner,
+        amount: Amount,
+        target: Account,
+        signer: Option<Owner>,
+    ) -> async_graphql::Result<TransferSimulation> {
+        let now = system_api::current_system_time();
+        let source_balance = self.state.current_balance(&owner, now).await.map_err(graphql_error)?.available;
+        let cross_chain_message = target.chain_id != system_api::current_chain_id();
+        let authenticated = match (owner, signer) {
+            (AccountOwner::User(address), Some(signer)) => Some(address == signer),
+            _ => None,
+        };
+        let mut simulation = TransferSimulation {
+            success: false,
+            error_code: None,
+            error_message: None,
+            authenticated,
+            source_balance,
+            source_balance_after: source_balance,
+            target_balance_after: None,
+            cross_chain_message,
+        };
+        if authenticated == Some(false) {
+            simulation.error_code = Some(ErrorCode::IncorrectAuthentication);
+            simulation.error_message = Some(format!("Incorrect authentication: expected {:?}, signer {:?}", owner, signer));
+            return Ok(simulation);
+        }
+        match self.state.check_transfer(owner, amount, now).await {
+            Ok(balance_after) => {
+                simulation.success = true;
+                simulation.source_balance_after = balance_after;
+                if !cross_chain_message {
+                    // 转给自己时余额不变
+                    let target_balance = if target.owner == owner {
+                        balance_after
+                    } else {
+                        self.state.current_balance(&target.owner, now).await.map_err(graphql_error)?.available
+                    };
+                    let target_balance_after = target_balance.saturating_add(amount);
+                    if target.owner == owner {
+                        simulation.source_balance_after = target_balance_after;
+                    }
+                    simulation.target_balance_after = Some(target_balance_after);
+                }
+            }
+            // 存储错误不是预测结果，直接返回查询错误
+            Err(StateError::View(error)) => return Err(graphql_error(error.into())),
+            Err(error) => {
+                simulation.error_code = Some(error.code());
+                simulation.error_message = Some(error.to_string());
+            }
+        }
+        Ok(simulation)
+    }
+
+    // 查询owner的某个请求有没有执行过(注意：只记录执行成功的请求，null表示没有执行过或者记录已经过期，可以用同一个request_id重试)
+    async fn request_status(&self, owner: AccountOwner, request_id: String) -> async_graphql::Result<Option<ProcessedRequest>> {
+        self.state
+            .processed_request(&owner, &request_id, system_api::current_system_time())
+            .await
+            .map_err(graphql_error)
+    }
+
+    // 所有错误码(注意：合约错误信息以[错误码]开头，查询错误的extensions里面有code、name和结构化字段)
+    async fn error_codes(&self) -> Vec<ErrorCodeInfo> {
+        ErrorCode::ALL
+            .iter()
+            .map(|&name| ErrorCodeInfo { code: name.code(), name })
+            .collect()
+    }
+}
+
+// 状态异常转成带错误码和结构化字段的GraphQL错误
+fn graphql_error(error: StateError) -> async_graphql::Error {
+    let code = error.code();
+    async_graphql::Error::new(error.to_string()).extend_with(|_, extensions| {
+        extensions.set("code", code.code());
+        extensions.set("name", code.to_value());
+        let fields = match &error {
+            StateError::View(_) => vec![],
+            StateError::InsufficientBalance(error) => vec![
+                ("owner", error.owner.to_value()),
+                ("requested", error.requested.to_value()),
+                ("available", error.available.to_value()),
+            ],
+            StateError::SnapshotNotFound(error) => vec![("snapshotId", error.0.to_value())],
+            StateError::LockNotFound(error) => vec![
+                ("owner", error.owner.to_value()),
+                ("lockId", error.lock_id.to_value()),
+            ],
+            StateError::TransferLimit(TransferLimitError::MaxTransferExceeded { amount, max_transfer }) => vec![
+                ("amount", amount.to_value()),
+                ("maxTransfer", max_transfer.to_value()),
+            ],
+            StateError::TransferLimit(TransferLimitError::OwnerCapExceeded { owner, amount, remaining }) => vec![
+                ("owner", owner.to_value()),
+                ("amount", amount.to_value()),
+                ("remaining", remaining.to_value()),
+            ],
+            StateError::TransferLimit(TransferLimitError::ChainCapExceeded { amount, remaining }) => vec![
+                ("amount", amount.to_value()),
+                ("remaining", remaining.to_value()),
+            ],
+        };
+        for (name, value) in fields {
+            extensions.set(name, value);
+        }
+    })
+}
+
+/*
+订阅(注意：节点服务每出一个新区块，客户端带上since(上次收到的最后一个事件id+1)重新订阅，
+流从存储里按页读取since之后的所有事件，读完就结束)
+*/
+struct SubscriptionRoot {
+    state: Arc<FungibleToken>,
+}
+
+#[Subscription]
+impl SubscriptionRoot {
+    // 账户余额变化(包括转入、转出、铸造和销毁)
+    async fn balance_changed(&self, owner: AccountOwner, since: Option<u64>) -> impl Stream<Item = async_graphql::Result<TokenEvent>> {
+        event_stream(self.state.clone(), owner, since.unwrap_or_default())
+    }
+
+    // 账户的转账(只有转入和转出)
+    async fn transfers(&self, owner: AccountOwner, since: Option<u64>) -> impl Stream<Item = async_graphql::Result<TokenEvent>> {
+        event_stream(self.state.clone(), owner, since.unwrap_or_default()).filter(|event| {
+            let keep = match event {
+                Ok(event) => matches!(event.kind, EventKind::TransferIn | EventKind::TransferOut),
+                Err(_) => true,
+            };
+            async move { keep }
+        })
+    }
+}
+
+// 按页读取账户since之后的事件，一页读完再读下一页，不满一页说明已经读完
+fn event_stream(state: Arc<FungibleToken>, owner: AccountOwner, since: u64) -> impl Stream<Item = async_graphql::Result<TokenEvent>> {
+    stream::unfold(Some(since), move |since| {
+        let state = state.clone();
+        async move {
+            let since = since?;
+            match state.events_page(&owner, since, EVENTS_PAGE_SIZE).await {
+                Ok(events) => {
+                    let next = match events.last() {
+                        Some(last) if events.len() == EVENTS_PAGE_SIZE => Some(last.id + 1),
+                        _ => None,
+                    };
+                    Some((events.into_iter().map(Ok).collect::<Vec<_>>(), next))
+                }
+                Err(error) => Some((vec![Err(graphql_error(error))], None)),
+            }
+        }
+    })
+    .flat_map(stream::iter)
+}
+
+// 如果请求是订阅，返回订阅的字段名(有别名时是别名)
+fn subscription_fields(request: &Request) -> Option<Vec<Name>> {
+    let document = parser::parse_query(&request.query).ok()?;
+    let operation = match &document.operations {
+        DocumentOperations::Single(operation) => operation,
+        DocumentOperations::Multiple(operations) => operations.get(request.operation_name.as_deref()?)?,
+    };
+    if operation.node.ty != OperationType::Subscription {
+        return None;
+    }
+    let fields = operation.node.selection_set.node.items
+        .iter()
+        .filter_map(|selection| match &selection.node {
+            Selection::Field(field) => Some(field.node.response_key().node.clone()),
+            _ => None,
+        })
+        .collect();
+    Some(fields)
+}
+
+// 执行订阅，每个字段的结果是收到的事件列表
+async fn execute_subscription(schema: &Schema<QueryRoot, MutationRoot, SubscriptionRoot>, request: Request, fields: Vec<Name>) -> Response {
+    let mut items: Vec<(Name, Vec<Value>)> = fields.into_iter().map(|field| (field, Vec::new())).collect();
+    let mut errors = Vec::new();
+    let mut responses = schema.execute_stream(request);
+    while let Some(response) = responses.next().await {
+        errors.extend(response.errors);
+        if let Value::Object(data) = response.data {
+            for (field, value) in data {
+                if let Some((_, values)) = items.iter_mut().find(|(name, _)| *name == field) {
+                    values.push(value);
+                }
+            }
+        }
+    }
+    let data = items.into_iter().map(|(field, values)| (field, Value::List(values))).collect();
+    let mut response = Response::new(Value::Object(data));
+    response.errors = errors;
+    response
+}
+
 struct MutationRoot;
 
 #[Object]
 impl MutationRoot {
-    async fn transfer(&self,owner: AccountOwner,amount: Amount,target_account: Account) -> Vec<u8> {
+    async fn transfer(&self,owner: AccountOwner,amount: Amount,target_account: Account,request_id: Option<String>) -> Vec<u8> {
         // 直接将 OperationOpt::Transfer 序列化成Byte数组
-        bcs::to_bytes(&OperationOpt::Transfer {owner,amount,target_account}).unwrap()
+        bcs::to_bytes(&OperationOpt::Transfer {owner,amount,target_account,request_id}).unwrap()
+    }
+
+    async fn claim(&self,source_account: Account,amount: Amount,target_account: Account,request_id: Option<String>) -> Vec<u8> {
+        bcs::to_bytes(&OperationOpt::Claim {source_account,amount,target_account,request_id}).unwrap()
+    }
+
+    async fn take_snapshot(&self) -> Vec<u8> {
+        bcs::to_bytes(&OperationOpt::TakeSnapshot).unwrap()
+    }
+
+    async fn mint(&self,target_account: Account,amount: Amount,request_id: Option<String>) -> Vec<u8> {
+        bcs::to_bytes(&OperationOpt::Mint {target_account,amount,request_id}).unwrap()
+    }
+
+    async fn burn(&self,owner: AccountOwner,amount: Amount,request_id: Option<String>) -> Vec<u8> {
+        bcs::to_bytes(&OperationOpt::Burn {owner,amount,request_id}).unwrap()
+    }
+
+    async fn set_minter(&self,minter: AccountOwner,enabled: bool) -> Vec<u8> {
+        bcs::to_bytes(&OperationOpt::SetMinter {minter,enabled}).unwrap()
+    }
+
+    async fn transfer_admin(&self,new_admin: AccountOwner) -> Vec<u8> {
+        bcs::to_bytes(&OperationOpt::TransferAdmin {new_admin}).unwrap()
+    }
+
+    async fn set_limits(&self,chain_id: ChainId,limits: TransferLimits) -> Vec<u8> {
+        bcs::to_bytes(&OperationOpt::SetLimits {chain_id,limits}).unwrap()
+    }
+
+    async fn export_snapshot(&self) -> Vec<u8> {
+        bcs::to_bytes(&OperationOpt::ExportSnapshot).unwrap()
+    }
+
+    async fn set_locker(&self,locker: ApplicationId,enabled: bool) -> Vec<u8> {
+        bcs::to_bytes(&OperationOpt::SetLocker {locker,enabled}).unwrap()
     }
 }
 
 
 #[derive(Debug, Error)]
 pub enum ServiceError {
-    #[error("Queries not supported by application")]
+    #[error("[{}] Queries not supported by application", ErrorCode::QueriesNotSupported.code())]
     QueriesNotSupported,
 
-    #[error("Invalid query argument; could not deserialize request")]
+    #[error("[{}] Invalid query argument; could not deserialize request", ErrorCode::InvalidQuery.code())]
     InvalidQuery(#[from] serde_json::Error),
 }
+
+impl ServiceError {
+    // 错误对应的错误码
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ServiceError::QueriesNotSupported => ErrorCode::QueriesNotSupported,
+            ServiceError::InvalidQuery(_) => ErrorCode::InvalidQuery,
+        }
+    }
+}
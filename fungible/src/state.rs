@@ -1,8 +1,8 @@
-use linera_sdk::base::{Amount};
-use linera_sdk::views::{MapView, ViewStorageContext};
-use linera_views::views::{GraphQLView, RootView};
+use linera_sdk::base::{Amount, ApplicationId, Timestamp};
+use linera_sdk::views::{LogView, MapView, RegisterView, ViewStorageContext};
+use linera_views::views::{GraphQLView, RootView, ViewError};
 use thiserror::Error;
-use fungible::AccountOwner;
+use fungible::{Account, AccountBalance, AccountOwner, Checkpoint, ErrorCode, EventKey, EventKind, ExportedAccount, ExportedState, Lock, Outflow, ProcessedRequest, Quota, Snapshot, StateExport, TokenEvent, TokenMetadata, TransferLimits};
 
 /*
 状态文件定义数据结构以及应用核心逻辑实现
@@ -11,50 +11,684 @@ use fungible::AccountOwner;
 #[derive(RootView, GraphQLView)]
 #[view(context = "ViewStorageContext")]
 pub struct FungibleToken {
-    /* 记录账户余额（注意：可以是地址账户也可以是程序账户） */
-    pub accounts: MapView<AccountOwner,Amount>,
+    /* 版本2之前的账户余额，每个账户第一次写入时搬到accounts里面(注意：不能删除或者移动，字段位置决定存储的键) */
+    pub legacy_accounts: MapView<AccountOwner,Amount>,
+    /* 总供应量(注意：只在部署合约的链上有意义) */
+    pub total_supply: RegisterView<Amount>,
+    /* 每个账户的余额检查点，按epoch从小到大排列 */
+    pub balance_checkpoints: MapView<AccountOwner,Vec<Checkpoint>>,
+    /* 总供应量检查点，按epoch从小到大排列 */
+    pub supply_checkpoints: RegisterView<Vec<Checkpoint>>,
+    /* 快照日志，下标就是快照ID */
+    pub snapshots: LogView<Snapshot>,
+    /* 管理员(注意：只在部署合约的链上有值) */
+    pub admin: RegisterView<Option<AccountOwner>>,
+    /* 铸币者，可以调用Mint */
+    pub minters: MapView<AccountOwner,bool>,
+    /* 当前链上的转账限额 */
+    pub limits: RegisterView<TransferLimits>,
+    /* 每个账户当前时间窗口内的转出数量(注意：只在设置了每个账户的限额时记录) */
+    pub owner_outflows: MapView<AccountOwner,Outflow>,
+    /* 当前链上所有账户当前时间窗口内的转出数量(注意：只在设置了整条链的限额时记录) */
+    pub chain_outflow: RegisterView<Outflow>,
+    /* 代币信息(注意：只在部署合约的链上有值，用金额部署时没有) */
+    pub metadata: RegisterView<Option<TokenMetadata>>,
+    /* 最近一次ExportSnapshot导出的状态 */
+    pub last_export: RegisterView<Option<ExportedState>>,
+    /* 状态结构的版本，旧版本部署的合约这里是0 */
+    pub schema_version: RegisterView<u32>,
+    /* 记录账户余额，分成可用和锁定两部分（注意：可以是地址账户也可以是程序账户） */
+    pub accounts: MapView<AccountOwner,AccountBalance>,
+    /* 锁定程序，可以调用Lock锁定用户的代币 */
+    pub lockers: MapView<ApplicationId,bool>,
+    /* 每个账户还没有结算的锁定记录 */
+    pub locks: MapView<AccountOwner,Vec<Lock>>,
+    /* 下一个锁定ID */
+    pub next_lock_id: RegisterView<u64>,
+    /* 每个账户最近处理过的请求，按处理时间排列(注意：最多保留MAX_REQUESTS_PER_OWNER个，过期的在下次记录时清理) */
+    pub processed_requests: MapView<AccountOwner,Vec<ProcessedRequest>>,
+    /* 每个账户的事件数量 */
+    pub owner_event_counts: MapView<AccountOwner,u64>,
+    /* 每个账户的余额变化事件，同一个账户的下标从0开始，事件ID随下标递增 */
+    pub owner_events: MapView<EventKey,TokenEvent>,
+    /* 下一个事件ID(注意：整条链上递增) */
+    pub next_event_id: RegisterView<u64>,
+    /* 总供应量是升级时从当前链上的旧余额加出来的(注意：最早的版本没有记录总供应量，其它链上的旧余额不在里面) */
+    pub legacy_supply: RegisterView<bool>,
 }
 
+/*
+当前状态结构的版本(注意：修改已有字段的存储格式时加1，并在migrate_to里面加上从上一个版本升级的逻辑；
+migrate_to在一个区块里执行，不能按账户写入，按账户的数据要在读写这个账户时迁移；
+只在结构体末尾新增字段不需要升级，旧数据里面新字段就是默认值)
+*/
+pub const SCHEMA_VERSION: u32 = 2;
+
+// 转出额度时间窗口的长度(一天)
+const OUTFLOW_WINDOW_MICROS: u64 = 86_400_000_000;
+
+// 请求ID的有效期(一天)，钱包要在这个时间内重试
+const REQUEST_TTL_MICROS: u64 = 86_400_000_000;
+
+// 每个账户最多记录的请求数量，超过时丢掉最早的记录
+const MAX_REQUESTS_PER_OWNER: usize = 100;
+
+// 订阅时每次从存储读取的事件数量
+pub const EVENTS_PAGE_SIZE: usize = 100;
+
 
 // 定义余额不足异常
 #[derive(Clone, Copy, Debug, Error)]
-#[error("Insufficient Balance")]
-pub struct InsufficientBalanceError;
+#[error("Insufficient balance for {owner:?}: requested {requested}, available {available}")]
+pub struct InsufficientBalanceError {
+    pub owner: AccountOwner,
+    pub requested: Amount,
+    pub available: Amount,
+}
+
+// 定义快照不存在异常
+#[derive(Clone, Copy, Debug, Error)]
+#[error("Snapshot {0} not found")]
+pub struct SnapshotNotFoundError(pub u64);
+
+// 定义锁定记录不存在异常(不存在、不是调用程序锁定的或者已经过期结算掉了)
+#[derive(Clone, Copy, Debug, Error)]
+#[error("Lock {lock_id} of {owner:?} not found")]
+pub struct LockNotFoundError {
+    pub owner: AccountOwner,
+    pub lock_id: u64,
+}
+
+// 定义超过转账限额异常
+#[derive(Clone, Copy, Debug, Error)]
+pub enum TransferLimitError {
+    #[error("Amount {amount} exceeds the maximum transfer amount {max_transfer}")]
+    MaxTransferExceeded { amount: Amount, max_transfer: Amount },
+
+    #[error("Amount {amount} exceeds the remaining daily quota {remaining} of {owner:?}")]
+    OwnerCapExceeded { owner: AccountOwner, amount: Amount, remaining: Amount },
+
+    #[error("Amount {amount} exceeds the remaining daily quota {remaining} of the chain")]
+    ChainCapExceeded { amount: Amount, remaining: Amount },
+}
+
+#[allow(dead_code)]//该注解用于去除未使用警告
+impl TransferLimitError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            TransferLimitError::MaxTransferExceeded { .. } => ErrorCode::MaxTransferExceeded,
+            TransferLimitError::OwnerCapExceeded { .. } => ErrorCode::OwnerCapExceeded,
+            TransferLimitError::ChainCapExceeded { .. } => ErrorCode::ChainCapExceeded,
+        }
+    }
+}
+
+/*
+状态操作异常(注意：存储读写失败不再直接panic，而是作为View错误返回给调用方，由合约拒绝这次操作)
+*/
+#[derive(Debug, Error)]
+pub enum StateError {
+    #[error("Storage error: {0}")]
+    View(#[from] ViewError),
+
+    #[error(transparent)]
+    InsufficientBalance(#[from] InsufficientBalanceError),
+
+    #[error(transparent)]
+    SnapshotNotFound(#[from] SnapshotNotFoundError),
+
+    #[error(transparent)]
+    TransferLimit(#[from] TransferLimitError),
+
+    #[error(transparent)]
+    LockNotFound(#[from] LockNotFoundError),
+}
+
+#[allow(dead_code)]//该注解用于去除未使用警告
+impl StateError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            StateError::View(_) => ErrorCode::Storage,
+            StateError::InsufficientBalance(_) => ErrorCode::InsufficientBalance,
+            StateError::SnapshotNotFound(_) => ErrorCode::SnapshotNotFound,
+            StateError::TransferLimit(error) => error.code(),
+            StateError::LockNotFound(_) => ErrorCode::LockNotFound,
+        }
+    }
+}
 
 #[allow(dead_code)]//该注解用于去除未使用警告
 impl FungibleToken {
     // 初始账户
-    pub async fn initialize_account(&mut self, owner: AccountOwner, amount: Amount) {
-        self.accounts
-            .insert(&owner, amount)
-            .expect("账户余额记录失败!");
-    }
-    // 获取账户余额
-    pub async fn balance(&self, account: &AccountOwner) -> Amount {
-        return self.accounts
-            .get(account)
-            .await
-            .expect("获取账户余额失败!")
-            .unwrap_or_default();
+    pub async fn initialize_account(&mut self, owner: AccountOwner, amount: Amount) -> Result<(), StateError> {
+        self.set_account_balance(owner, AccountBalance { available: amount, locked: Amount::default() }).await?;
+        self.mint(amount);
+        Ok(())
+    }
+    // 获取账户余额(注意：旧版本的账户在第一次写入之前余额还在legacy_accounts里面，作为可用余额)
+    pub async fn account_balance(&self, account: &AccountOwner) -> Result<AccountBalance, StateError> {
+        if let Some(balance) = self.accounts.get(account).await? {
+            return Ok(balance);
+        }
+        let available = self.legacy_accounts.get(account).await?.unwrap_or_default();
+        Ok(AccountBalance { available, locked: Amount::default() })
+    }
+    // 获取账户余额(可用和锁定的总和)
+    pub async fn balance(&self, account: &AccountOwner) -> Result<Amount, StateError> {
+        Ok(self.account_balance(account).await?.total())
+    }
+    // 到now为止的账户余额，已经过期还没有结算的锁定算作可用(注意：不修改状态)
+    pub async fn current_balance(&self, account: &AccountOwner, now: Timestamp) -> Result<AccountBalance, StateError> {
+        let mut balance = self.account_balance(account).await?;
+        let expired = Self::expired_amount(&self.locks_of(account).await?, now);
+        balance.locked = balance.locked.saturating_sub(expired);
+        balance.available.saturating_add_assign(expired);
+        Ok(balance)
     }
     // 给某个账户加余额
-    pub async fn credit(&mut self, account: AccountOwner, amount: Amount) {
-        let mut balance = self.balance(&account).await;
+    pub async fn credit(&mut self, account: AccountOwner, amount: Amount) -> Result<(), StateError> {
+        let mut balance = self.account_balance(&account).await?;
         // 余额相加并防止溢出
-        balance.saturating_add_assign(amount);
-        self.accounts
-            .insert(&account, balance)
-            .expect("增加更新余额失败!");
+        balance.available.saturating_add_assign(amount);
+        self.set_account_balance(account, balance).await
     }
 
-    // 给账户减余额
-    pub async fn debit(&mut self, account: AccountOwner, amount: Amount) -> Result<(), InsufficientBalanceError> {
-        let mut balance = self.balance(&account).await;
+    // 给账户减可用余额(注意：先结算已经过期的锁定)
+    pub async fn debit(&mut self, account: AccountOwner, amount: Amount, now: Timestamp) -> Result<(), StateError> {
+        self.release_expired_locks(account, now).await?;
+        let mut balance = self.account_balance(&account).await?;
+        let available = balance.available;
         // 余额相减如果不足抛出 InsufficientBalanceError
-        balance.try_sub_assign(amount).map_err(|_| InsufficientBalanceError)?;
-        self.accounts
-            .insert(&account, balance)
-            .expect("减少更新余额失败!");
+        balance.available.try_sub_assign(amount).map_err(|_| InsufficientBalanceError {
+            owner: account,
+            requested: amount,
+            available,
+        })?;
+        self.set_account_balance(account, balance).await
+    }
+
+    // 是否是锁定程序
+    pub async fn is_locker(&self, locker: &ApplicationId) -> Result<bool, StateError> {
+        Ok(self.lockers.get(locker).await?.unwrap_or_default())
+    }
+
+    // 账户还没有结算的锁定记录
+    pub async fn locks_of(&self, owner: &AccountOwner) -> Result<Vec<Lock>, StateError> {
+        Ok(self.locks.get(owner).await?.unwrap_or_default())
+    }
+
+    // 把账户中的可用代币锁定到expires_at，返回锁定ID
+    pub async fn lock(&mut self, owner: AccountOwner, locker: ApplicationId, amount: Amount, expires_at: Timestamp, now: Timestamp) -> Result<u64, StateError> {
+        self.release_expired_locks(owner, now).await?;
+        let mut balance = self.account_balance(&owner).await?;
+        let available = balance.available;
+        balance.available.try_sub_assign(amount).map_err(|_| InsufficientBalanceError {
+            owner,
+            requested: amount,
+            available,
+        })?;
+        balance.locked.saturating_add_assign(amount);
+        // 总额不变，不需要写检查点
+        self.save_account_balance(owner, balance).await?;
+        let id = *self.next_lock_id.get();
+        self.next_lock_id.set(id + 1);
+        let mut locks = self.locks_of(&owner).await?;
+        locks.push(Lock { id, locker, amount, expires_at });
+        self.locks.insert(&owner, locks)?;
+        Ok(id)
+    }
+
+    // 解锁locker锁定的代币，返回解锁的数量
+    pub async fn unlock(&mut self, owner: AccountOwner, locker: ApplicationId, lock_id: u64) -> Result<Amount, StateError> {
+        let mut locks = self.locks_of(&owner).await?;
+        let index = locks
+            .iter()
+            .position(|lock| lock.id == lock_id && lock.locker == locker)
+            .ok_or(LockNotFoundError { owner, lock_id })?;
+        let lock = locks.remove(index);
+        self.save_locks(owner, locks)?;
+        self.release(owner, lock.amount).await?;
+        Ok(lock.amount)
+    }
+
+    // 结算账户已经过期的锁定，锁定的代币回到可用余额
+    pub async fn release_expired_locks(&mut self, owner: AccountOwner, now: Timestamp) -> Result<(), StateError> {
+        let locks = self.locks_of(&owner).await?;
+        if !locks.iter().any(|lock| Self::is_expired(lock, now)) {
+            return Ok(());
+        }
+        let expired = Self::expired_amount(&locks, now);
+        let locks = locks.into_iter().filter(|lock| !Self::is_expired(lock, now)).collect();
+        self.save_locks(owner, locks)?;
+        self.release(owner, expired).await
+    }
+
+    // 是否是铸币者(管理员也可以铸币)
+    pub async fn is_minter(&self, account: &AccountOwner) -> Result<bool, StateError> {
+        if *self.admin.get() == Some(*account) {
+            return Ok(true);
+        }
+        Ok(self.minters.get(account).await?.unwrap_or_default())
+    }
+
+    // 铸造代币，增加总供应量
+    pub fn mint(&mut self, amount: Amount) {
+        let mut total_supply = *self.total_supply.get();
+        total_supply.saturating_add_assign(amount);
+        self.set_total_supply(total_supply);
+    }
+
+    // 销毁代币，减少总供应量
+    pub fn burn(&mut self, amount: Amount) {
+        let total_supply = self.total_supply.get().saturating_sub(amount);
+        self.set_total_supply(total_supply);
+    }
+
+    // 创建快照，返回快照ID
+    pub fn take_snapshot(&mut self, snapshot: Snapshot) -> u64 {
+        let snapshot_id = self.snapshots.count() as u64;
+        self.snapshots.push(snapshot);
+        snapshot_id
+    }
+
+    // 获取某个快照时账户的余额
+    pub async fn balance_at(&self, account: &AccountOwner, snapshot_id: u64) -> Result<Amount, StateError> {
+        self.check_snapshot(snapshot_id)?;
+        let checkpoints = match self.balance_checkpoints.get(account).await? {
+            Some(checkpoints) => checkpoints,
+            // 还没有迁移的旧账户没有检查点，旧余额就是epoch 0时的余额
+            None => match self.legacy_accounts.get(account).await? {
+                Some(amount) => vec![Checkpoint { epoch: 0, amount }],
+                None => Vec::new(),
+            },
+        };
+        Ok(Self::amount_at(&checkpoints, snapshot_id))
+    }
+
+    // 获取某个快照时的总供应量
+    pub fn total_supply_at(&self, snapshot_id: u64) -> Result<Amount, SnapshotNotFoundError> {
+        self.check_snapshot(snapshot_id)?;
+        Ok(Self::amount_at(self.supply_checkpoints.get(), snapshot_id))
+    }
+
+    // 修改总供应量并写入检查点
+    pub fn set_total_supply(&mut self, total_supply: Amount) {
+        self.total_supply.set(total_supply);
+        let epoch = self.current_epoch();
+        Self::push_checkpoint(self.supply_checkpoints.get_mut(), epoch, total_supply);
+    }
+
+    // 检查转账限额并记录转出数量(注意：Transfer、Claim和Withdraw扣余额之前调用)
+    pub async fn record_outflow(&mut self, owner: AccountOwner, amount: Amount, now: Timestamp) -> Result<(), StateError> {
+        let (owner_outflow, chain_outflow) = self.check_outflow(owner, amount, now).await?;
+        if let Some(outflow) = owner_outflow {
+            self.owner_outflows.insert(&owner, outflow)?;
+        }
+        if let Some(outflow) = chain_outflow {
+            self.chain_outflow.set(outflow);
+        }
+        Ok(())
+    }
+
+    // 检查一笔转出能不能成功，返回转出后的可用余额(注意：不修改状态，和合约的检查顺序一样先检查限额再检查余额)
+    pub async fn check_transfer(&self, owner: AccountOwner, amount: Amount, now: Timestamp) -> Result<Amount, StateError> {
+        self.check_outflow(owner, amount, now).await?;
+        let mut balance = self.current_balance(&owner, now).await?.available;
+        let available = balance;
+        balance.try_sub_assign(amount).map_err(|_| InsufficientBalanceError {
+            owner,
+            requested: amount,
+            available,
+        })?;
+        Ok(balance)
+    }
+
+    // 某个账户到now为止的剩余额度
+    pub async fn remaining_quota(&self, owner: &AccountOwner, now: Timestamp) -> Result<Quota, StateError> {
+        if Self::is_limit_exempt(owner) {
+            return Ok(Quota::default());
+        }
+        let limits = *self.limits.get();
+        let owner_used = Self::used_amount(&self.owner_outflow(owner).await?, now);
+        let chain_used = Self::used_amount(self.chain_outflow.get(), now);
+        Ok(Quota {
+            max_transfer: limits.max_transfer,
+            owner_remaining: limits.daily_owner_cap.map(|cap| cap.saturating_sub(owner_used)),
+            chain_remaining: limits.daily_chain_cap.map(|cap| cap.saturating_sub(chain_used)),
+        })
+    }
+
+    // 检查转账限额，返回转出后每个账户和整条链的转出数量(没有设置对应限额时为None)
+    async fn check_outflow(&self, owner: AccountOwner, amount: Amount, now: Timestamp) -> Result<(Option<Outflow>, Option<Outflow>), StateError> {
+        if Self::is_limit_exempt(&owner) {
+            return Ok((None, None));
+        }
+        let limits = *self.limits.get();
+        if let Some(max_transfer) = limits.max_transfer {
+            if amount > max_transfer {
+                return Err(TransferLimitError::MaxTransferExceeded { amount, max_transfer }.into());
+            }
+        }
+        let mut owner_outflow = None;
+        if let Some(cap) = limits.daily_owner_cap {
+            let outflow = self.owner_outflow(&owner).await?;
+            let remaining = cap.saturating_sub(Self::used_amount(&outflow, now));
+            if amount > remaining {
+                return Err(TransferLimitError::OwnerCapExceeded { owner, amount, remaining }.into());
+            }
+            owner_outflow = Some(Self::add_outflow(outflow, amount, now));
+        }
+        let mut chain_outflow = None;
+        if let Some(cap) = limits.daily_chain_cap {
+            let outflow = *self.chain_outflow.get();
+            let remaining = cap.saturating_sub(Self::used_amount(&outflow, now));
+            if amount > remaining {
+                return Err(TransferLimitError::ChainCapExceeded { amount, remaining }.into());
+            }
+            chain_outflow = Some(Self::add_outflow(outflow, amount, now));
+        }
+        Ok((owner_outflow, chain_outflow))
+    }
+
+    /*
+    程序账户不受限额限制，也不算在整条链的转出数量里面(注意：程序账户里是AMM、质押、跨链桥等程序托管的代币，
+    它们经常在处理跨链消息时转出，被限额拒绝会让消息失败并堵住收件箱)
+    */
+    fn is_limit_exempt(owner: &AccountOwner) -> bool {
+        matches!(owner, AccountOwner::Application(_))
+    }
+
+    async fn owner_outflow(&self, owner: &AccountOwner) -> Result<Outflow, StateError> {
+        Ok(self.owner_outflows.get(owner).await?.unwrap_or_default())
+    }
+
+    // 时间窗口到期后重新开始计算
+    fn used_amount(outflow: &Outflow, now: Timestamp) -> Amount {
+        if now.micros() >= outflow.window_start.micros().saturating_add(OUTFLOW_WINDOW_MICROS) {
+            Amount::default()
+        } else {
+            outflow.amount
+        }
+    }
+
+    fn add_outflow(outflow: Outflow, amount: Amount, now: Timestamp) -> Outflow {
+        if Self::used_amount(&outflow, now) == Amount::default() {
+            Outflow { window_start: now, amount }
+        } else {
+            Outflow { window_start: outflow.window_start, amount: outflow.amount.saturating_add(amount) }
+        }
+    }
+
+    // 查找账户处理过的请求(已经过期的当成没有处理过)
+    pub async fn processed_request(&self, owner: &AccountOwner, request_id: &str, now: Timestamp) -> Result<Option<ProcessedRequest>, StateError> {
+        let requests = self.processed_requests.get(owner).await?.unwrap_or_default();
+        Ok(requests
+            .into_iter()
+            .find(|request| request.request_id == request_id && !Self::is_request_expired(request, now)))
+    }
+
+    // 记录处理过的请求，同时清理过期和超出数量的记录
+    pub async fn record_request(&mut self, owner: AccountOwner, request: ProcessedRequest) -> Result<(), StateError> {
+        let now = request.timestamp;
+        let mut requests: Vec<ProcessedRequest> = self.processed_requests
+            .get(&owner)
+            .await?
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|processed| !Self::is_request_expired(processed, now))
+            .collect();
+        requests.push(request);
+        if requests.len() > MAX_REQUESTS_PER_OWNER {
+            requests.drain(..requests.len() - MAX_REQUESTS_PER_OWNER);
+        }
+        self.processed_requests.insert(&owner, requests)?;
+        Ok(())
+    }
+
+    fn is_request_expired(request: &ProcessedRequest, now: Timestamp) -> bool {
+        now.micros() >= request.timestamp.micros().saturating_add(REQUEST_TTL_MICROS)
+    }
+
+    // 记录余额变化事件，balance是事件之后的余额
+    pub async fn push_event(&mut self, kind: EventKind, owner: AccountOwner, amount: Amount, target_account: Option<Account>, timestamp: Timestamp) -> Result<(), StateError> {
+        let balance = self.balance(&owner).await?;
+        let id = *self.next_event_id.get();
+        self.next_event_id.set(id + 1);
+        let index = self.owner_event_counts.get(&owner).await?.unwrap_or_default();
+        self.owner_event_counts.insert(&owner, index + 1)?;
+        self.owner_events.insert(&EventKey { owner, index }, TokenEvent {
+            id,
+            kind,
+            owner,
+            amount,
+            balance,
+            target_account,
+            timestamp,
+        })?;
+        Ok(())
+    }
+
+    // 读取某个账户ID不小于since的事件，最多limit个(注意：事件不会被清理，调用方用最后一个id+1继续读下一页)
+    pub async fn events_page(&self, owner: &AccountOwner, since: u64, limit: usize) -> Result<Vec<TokenEvent>, StateError> {
+        let count = self.owner_event_counts.get(owner).await?.unwrap_or_default();
+        // 同一个账户的事件ID随下标递增，二分查找第一个ID不小于since的下标
+        let (mut low, mut high) = (0, count);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            match self.owner_events.get(&EventKey { owner: *owner, index: middle }).await? {
+                Some(event) if event.id >= since => high = middle,
+                _ => low = middle + 1,
+            }
+        }
+        let mut events = Vec::new();
+        for index in low..count.min(low + limit as u64) {
+            if let Some(event) = self.owner_events.get(&EventKey { owner: *owner, index }).await? {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+
+    // 有余额记录的所有账户，包括还没有迁移的旧账户(按账户排序)
+    pub async fn holders(&self) -> Result<Vec<AccountOwner>, StateError> {
+        let mut owners = self.accounts.indices().await?;
+        owners.extend(self.legacy_accounts.indices().await?);
+        owners.sort();
+        owners.dedup();
+        Ok(owners)
+    }
+
+    // 导出当前链上的状态(注意：按账户排序，保证同样的状态导出的结果一样)
+    pub async fn export_state(&self) -> Result<StateExport, StateError> {
+        let owners = self.holders().await?;
+        let mut accounts = Vec::with_capacity(owners.len());
+        for owner in owners {
+            let balance = self.account_balance(&owner).await?;
+            let locks = self.locks_of(&owner).await?;
+            accounts.push(ExportedAccount { owner, balance, locks });
+        }
+        let mut lockers = Vec::new();
+        for locker in self.lockers.indices().await? {
+            if self.lockers.get(&locker).await?.unwrap_or_default() {
+                lockers.push(locker);
+            }
+        }
+        lockers.sort();
+        let mut minters = Vec::new();
+        for minter in self.minters.indices().await? {
+            if self.minters.get(&minter).await?.unwrap_or_default() {
+                minters.push(minter);
+            }
+        }
+        minters.sort();
+        Ok(StateExport {
+            accounts,
+            total_supply: *self.total_supply.get(),
+            admin: *self.admin.get(),
+            minters,
+            limits: *self.limits.get(),
+            metadata: self.metadata.get().clone(),
+            lockers,
+            next_lock_id: *self.next_lock_id.get(),
+            legacy_supply: *self.legacy_supply.get(),
+        })
+    }
+
+    // 导入旧版本导出的状态(注意：只在部署合约时调用，哈希已经验证过)
+    pub async fn import_state(&mut self, export: StateExport) -> Result<(), StateError> {
+        for ExportedAccount { owner, balance, locks } in export.accounts {
+            self.set_account_balance(owner, balance).await?;
+            self.save_locks(owner, locks)?;
+        }
+        for locker in export.lockers {
+            self.lockers.insert(&locker, true)?;
+        }
+        self.next_lock_id.set(export.next_lock_id);
+        for minter in export.minters {
+            self.minters.insert(&minter, true)?;
+        }
+        self.set_total_supply(export.total_supply);
+        self.admin.set(export.admin);
+        self.limits.set(export.limits);
+        self.metadata.set(export.metadata);
         Ok(())
     }
+
+    // 把旧版本的状态逐个版本升级到最新版本(注意：合约每个入口执行之前调用，已经是最新版本时什么都不做)
+    pub async fn migrate(&mut self) -> Result<(), StateError> {
+        while *self.schema_version.get() < SCHEMA_VERSION {
+            let version = *self.schema_version.get() + 1;
+            self.migrate_to(version).await?;
+            self.schema_version.set(version);
+            log::info!("状态升级到版本 {}", version);
+        }
+        Ok(())
+    }
+
+    async fn migrate_to(&mut self, version: u32) -> Result<(), StateError> {
+        match version {
+            /*
+            v1：最早的版本没有记录总供应量，用当前链上旧余额的总和补上，同时补上epoch 0的检查点
+            (注意：只读不写账户；账户的检查点在migrate_account里面按账户补)
+            */
+            1 => {
+                if *self.total_supply.get() == Amount::default() {
+                    let mut total_supply = Amount::default();
+                    for owner in self.legacy_accounts.indices().await? {
+                        total_supply.saturating_add_assign(self.legacy_accounts.get(&owner).await?.unwrap_or_default());
+                    }
+                    self.total_supply.set(total_supply);
+                    self.legacy_supply.set(true);
+                }
+                if self.supply_checkpoints.get().is_empty() {
+                    let total_supply = *self.total_supply.get();
+                    self.supply_checkpoints.set(vec![Checkpoint { epoch: 0, amount: total_supply }]);
+                }
+                Ok(())
+            }
+            // v2：余额从Amount改成AccountBalance，旧余额在每个账户第一次写入时搬到新的accounts里面(见migrate_account)
+            2 => Ok(()),
+            _ => unreachable!("没有版本 {} 的升级逻辑", version),
+        }
+    }
+
+    // 修改账户余额并写入检查点
+    async fn set_account_balance(&mut self, account: AccountOwner, balance: AccountBalance) -> Result<(), StateError> {
+        self.save_account_balance(account, balance).await?;
+        self.write_balance_checkpoint(account, balance.total()).await
+    }
+
+    // 写入账户余额，旧版本的账户先迁移
+    async fn save_account_balance(&mut self, account: AccountOwner, balance: AccountBalance) -> Result<(), StateError> {
+        self.migrate_account(&account).await?;
+        self.accounts.insert(&account, balance)?;
+        Ok(())
+    }
+
+    /*
+    旧版本的账户在第一次写入时迁移，不用在升级时遍历所有账户：
+    没有检查点时补上epoch 0的检查点(快照查询才能查到旧余额)，然后删除legacy_accounts里面的旧余额
+    (注意：调用方接着会把新余额写到accounts里面，新余额是从旧余额算出来的)
+    */
+    async fn migrate_account(&mut self, account: &AccountOwner) -> Result<(), StateError> {
+        let amount = match self.legacy_accounts.get(account).await? {
+            Some(amount) => amount,
+            None => return Ok(()),
+        };
+        if self.balance_checkpoints.get(account).await?.is_none() {
+            self.balance_checkpoints.insert(account, vec![Checkpoint { epoch: 0, amount }])?;
+        }
+        self.legacy_accounts.remove(account)?;
+        Ok(())
+    }
+
+    // 锁定的代币回到可用余额(总额不变，不需要写检查点)
+    async fn release(&mut self, owner: AccountOwner, amount: Amount) -> Result<(), StateError> {
+        let mut balance = self.account_balance(&owner).await?;
+        balance.locked = balance.locked.saturating_sub(amount);
+        balance.available.saturating_add_assign(amount);
+        self.save_account_balance(owner, balance).await
+    }
+
+    // 没有锁定记录时删除，避免留下空列表
+    fn save_locks(&mut self, owner: AccountOwner, locks: Vec<Lock>) -> Result<(), StateError> {
+        if locks.is_empty() {
+            self.locks.remove(&owner)?;
+        } else {
+            self.locks.insert(&owner, locks)?;
+        }
+        Ok(())
+    }
+
+    fn is_expired(lock: &Lock, now: Timestamp) -> bool {
+        now.micros() >= lock.expires_at.micros()
+    }
+
+    // 已经过期的锁定数量
+    fn expired_amount(locks: &[Lock], now: Timestamp) -> Amount {
+        locks
+            .iter()
+            .filter(|lock| Self::is_expired(lock, now))
+            .fold(Amount::default(), |total, lock| total.saturating_add(lock.amount))
+    }
+
+    // 当前epoch就是已经创建的快照数量
+    fn current_epoch(&self) -> u64 {
+        self.snapshots.count() as u64
+    }
+
+    fn check_snapshot(&self, snapshot_id: u64) -> Result<(), SnapshotNotFoundError> {
+        if snapshot_id < self.current_epoch() {
+            Ok(())
+        } else {
+            Err(SnapshotNotFoundError(snapshot_id))
+        }
+    }
+
+    // 余额变化后写入检查点
+    async fn write_balance_checkpoint(&mut self, account: AccountOwner, balance: Amount) -> Result<(), StateError> {
+        let epoch = self.current_epoch();
+        let mut checkpoints = self.balance_checkpoints.get(&account).await?.unwrap_or_default();
+        Self::push_checkpoint(&mut checkpoints, epoch, balance);
+        self.balance_checkpoints.insert(&account, checkpoints)?;
+        Ok(())
+    }
+
+    // 同一个epoch内只保留最后一次的金额
+    fn push_checkpoint(checkpoints: &mut Vec<Checkpoint>, epoch: u64, amount: Amount) {
+        match checkpoints.last_mut() {
+            Some(last) if last.epoch == epoch => last.amount = amount,
+            _ => checkpoints.push(Checkpoint { epoch, amount }),
+        }
+    }
+
+    // 找到快照时最后一个检查点的金额，快照之前没有检查点表示金额为0
+    fn amount_at(checkpoints: &[Checkpoint], snapshot_id: u64) -> Amount {
+        let index = checkpoints.partition_point(|checkpoint| checkpoint.epoch <= snapshot_id);
+        if index == 0 {
+            Amount::default()
+        } else {
+            checkpoints[index - 1].amount
+        }
+    }
 }
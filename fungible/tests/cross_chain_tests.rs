@@ -1,9 +1,9 @@
 #![cfg(not(target_arch = "wasm32"))]
 
-use async_graphql::{InputType};
-use linera_sdk::base::{Amount, ApplicationId, Owner};
-use linera_sdk::test::{ActiveChain, TestValidator};
-use fungible::{Account, AccountOwner, FungibleTokenAbi, OperationOpt};
+use linera_sdk::base::{Amount, Owner};
+use linera_sdk::test::TestValidator;
+use fungible::{Account, AccountOwner, OperationOpt};
+use fungible_client::{ActiveChainTransport, FungibleClient};
 
 #[tokio::test]
 async fn test_cross_chain_transfer() {
@@ -22,7 +22,7 @@ async fn test_cross_chain_transfer() {
         .create_application::<fungible::FungibleTokenAbi>(
             bytecode_id,
             (),
-            initial_amount,
+            initial_amount.into(),
             vec![]
         ).await;
 
@@ -41,43 +41,29 @@ async fn test_cross_chain_transfer() {
                 target_account: Account {
                     chain_id: receiver_chain.id(),
                     owner: AccountOwner::User(receiver_account)
-                }
+                },
+                request_id: None,
             },
         );
     }).await;
     // 判断发送者余额是不是等于初始金额减去发送金额
+    let sender_client = FungibleClient::new(ActiveChainTransport::new(&sender_chain, application_id));
     assert_eq!(
-        query_account(application_id, &sender_chain, AccountOwner::User(sender_account)).await,
-        Some(initial_amount.saturating_sub(transfer_amount))
+        sender_client.execute(fungible_client::balance(AccountOwner::User(sender_account))).await.unwrap(),
+        initial_amount.saturating_sub(transfer_amount)
     );
     // 让接收链接收消息
     receiver_chain.handle_received_messages().await;
 
     // 判断接收者余额是不是等于发送金额
+    let receiver_client = FungibleClient::new(ActiveChainTransport::new(&receiver_chain, application_id));
     assert_eq!(
-        query_account(application_id, &receiver_chain, AccountOwner::User(receiver_account)).await,
-        Some(transfer_amount)
-    )
-
-}
-
-async fn query_account(
-    application_id: ApplicationId<FungibleTokenAbi>,
-    chain: &ActiveChain,
-    account_owner: AccountOwner
-) -> Option<Amount> {
-    let owner_value = match account_owner {
-        AccountOwner::Application(id) => InputType::to_value(&id),
-        AccountOwner::User(address) => InputType::to_value(&address)
-    };
-    // 注意：下面是两个大括号转译成一个实际的大括号，取值的两个大括号不需要转译
-    let query = format!(
-        "query {{ accounts(accountOwner: {{ User: {} }}) }}",
-        owner_value
+        receiver_client.execute(fungible_client::balance(AccountOwner::User(receiver_account))).await.unwrap(),
+        transfer_amount
     );
-
-    let value = chain.graphql_query(application_id, query).await;
-    let balance = value.as_object()?.get("accounts")?.as_str()?;
-
-    Some(balance.parse().unwrap())
-}
\ No newline at end of file
+    // 接收者出现在持有者列表里
+    assert_eq!(
+        receiver_client.execute(fungible_client::holders()).await.unwrap(),
+        vec![AccountOwner::User(receiver_account)]
+    );
+}
@@ -0,0 +1,97 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::panic::AssertUnwindSafe;
+
+use futures::FutureExt;
+use linera_sdk::base::{Amount, Owner};
+use linera_sdk::test::TestValidator;
+use fungible::{Account, AccountBalance, AccountOwner, ExportedAccount, FungibleTokenAbi, InitializationArgument, OperationOpt, StateExport};
+use fungible_client::{ActiveChainTransport, FungibleClient};
+
+#[tokio::test]
+async fn test_export_and_import_state() {
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    let mut chain = validator.new_chain().await;
+    let admin = AccountOwner::User(Owner::from(chain.public_key()));
+    let holder = AccountOwner::User(Owner::from(validator.new_chain().await.public_key()));
+    let old_id = chain
+        .create_application::<FungibleTokenAbi>(bytecode_id, (), Amount::from(1_000u128).into(), vec![])
+        .await;
+
+    // 旧版本上转一些给另一个账户，然后导出
+    chain.add_block(|block| {
+        block
+            .with_operation(
+                old_id,
+                OperationOpt::Transfer {
+                    owner: admin,
+                    amount: Amount::from(300u128),
+                    target_account: Account { chain_id: chain.id(), owner: holder },
+                    request_id: None,
+                },
+            )
+            .with_operation(old_id, OperationOpt::ExportSnapshot);
+    }).await;
+    let export = FungibleClient::new(ActiveChainTransport::new(&chain, old_id))
+        .execute(fungible_client::last_export())
+        .await
+        .unwrap()
+        .expect("没有导出的状态");
+    let decoded: StateExport = bcs::from_bytes(&export.state).unwrap();
+    assert_eq!(decoded.total_supply, Amount::from(1_000u128));
+    assert_eq!(decoded.admin, Some(admin));
+
+    // 哈希不对时拒绝导入
+    let tampered = InitializationArgument::Import { state: export.state.clone(), hash: "00".repeat(32) };
+    let result = AssertUnwindSafe(
+        chain.create_application::<FungibleTokenAbi>(bytecode_id, (), tampered, vec![])
+    ).catch_unwind().await;
+    assert!(result.is_err());
+
+    // 余额总和和总供应量不一致时拒绝导入(比如其它链上的余额没有Claim回来)
+    let partial = StateExport {
+        accounts: vec![ExportedAccount {
+            owner: admin,
+            balance: AccountBalance { available: Amount::from(100u128), locked: Amount::default() },
+            locks: Vec::new(),
+        }],
+        total_supply: Amount::from(1_000u128),
+        ..StateExport::default()
+    };
+    let state = bcs::to_bytes(&partial).unwrap();
+    let hash = fungible::export_hash(&state);
+    let result = AssertUnwindSafe(
+        chain.create_application::<FungibleTokenAbi>(bytecode_id, (), InitializationArgument::Import { state, hash }, vec![])
+    ).catch_unwind().await;
+    assert!(result.is_err());
+
+    // 旧版本升级的总供应量不包括其它链上Claim回来的余额，导入后以余额总和为准
+    let legacy = StateExport {
+        accounts: vec![ExportedAccount {
+            owner: admin,
+            balance: AccountBalance { available: Amount::from(1_500u128), locked: Amount::default() },
+            locks: Vec::new(),
+        }],
+        total_supply: Amount::from(1_000u128),
+        legacy_supply: true,
+        ..StateExport::default()
+    };
+    let state = bcs::to_bytes(&legacy).unwrap();
+    let hash = fungible::export_hash(&state);
+    let legacy_id = chain
+        .create_application::<FungibleTokenAbi>(bytecode_id, (), InitializationArgument::Import { state, hash }, vec![])
+        .await;
+    let client = FungibleClient::new(ActiveChainTransport::new(&chain, legacy_id));
+    assert_eq!(client.execute(fungible_client::total_supply()).await.unwrap(), Amount::from(1_500u128));
+
+    // 新版本导入后余额和供应量都一样
+    let import = InitializationArgument::Import { state: export.state, hash: export.hash };
+    let new_id = chain
+        .create_application::<FungibleTokenAbi>(bytecode_id, (), import, vec![])
+        .await;
+    let client = FungibleClient::new(ActiveChainTransport::new(&chain, new_id));
+    assert_eq!(client.execute(fungible_client::balance(admin)).await.unwrap(), Amount::from(700u128));
+    assert_eq!(client.execute(fungible_client::balance(holder)).await.unwrap(), Amount::from(300u128));
+    assert_eq!(client.execute(fungible_client::total_supply()).await.unwrap(), Amount::from(1_000u128));
+    assert_eq!(client.execute(fungible_client::admin()).await.unwrap(), Some(admin));
+}
@@ -0,0 +1,82 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::panic::AssertUnwindSafe;
+
+use futures::FutureExt;
+use linera_sdk::base::{Amount, Owner, Timestamp};
+use linera_sdk::test::TestValidator;
+use fungible::{Account, AccountOwner, FungibleTokenAbi, OperationOpt, TransferLimits};
+use fungible_client::{ActiveChainTransport, FungibleClient};
+
+#[tokio::test]
+async fn test_transfer_limits() {
+    let initial_amount = Amount::from(1_000_000u128);
+    let day_micros = 86_400_000_000;
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    let mut chain = validator.new_chain().await;
+    let admin = AccountOwner::User(Owner::from(chain.public_key()));
+    let application_id = chain
+        .create_application::<FungibleTokenAbi>(bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let receiver_chain = validator.new_chain().await;
+    let target_account = Account {
+        chain_id: receiver_chain.id(),
+        owner: AccountOwner::User(Owner::from(receiver_chain.public_key())),
+    };
+    let transfer = |amount: u128| OperationOpt::Transfer {
+        owner: admin,
+        amount: Amount::from(amount),
+        target_account,
+        request_id: None,
+    };
+
+    // 管理员设置当前链的限额：单笔最多100，每个账户每天最多150
+    let start = Timestamp::now();
+    chain.add_block(|block| {
+        block
+            .with_timestamp(start)
+            .with_operation(
+                application_id,
+                OperationOpt::SetLimits {
+                    chain_id: chain.id(),
+                    limits: TransferLimits {
+                        max_transfer: Some(Amount::from(100u128)),
+                        daily_owner_cap: Some(Amount::from(150u128)),
+                        daily_chain_cap: None,
+                    },
+                },
+            )
+            .with_operation(application_id, transfer(100));
+    }).await;
+
+    // 超过单笔限额被拒绝
+    let result = AssertUnwindSafe(chain.add_block(|block| {
+        block.with_timestamp(start).with_operation(application_id, transfer(101));
+    })).catch_unwind().await;
+    assert!(result.is_err());
+
+    // 超过今天剩余的额度被拒绝
+    let result = AssertUnwindSafe(chain.add_block(|block| {
+        block.with_timestamp(start).with_operation(application_id, transfer(60));
+    })).catch_unwind().await;
+    assert!(result.is_err());
+    let quota = FungibleClient::new(ActiveChainTransport::new(&chain, application_id))
+        .execute(fungible_client::remaining_quota(admin))
+        .await
+        .unwrap();
+    assert_eq!(quota.owner_remaining, Some(Amount::from(50u128)));
+
+    // 第二天额度重新计算
+    chain.add_block(|block| {
+        block
+            .with_timestamp(Timestamp::from(start.micros() + day_micros))
+            .with_operation(application_id, transfer(60));
+    }).await;
+    receiver_chain.handle_received_messages().await;
+
+    let receiver_client = FungibleClient::new(ActiveChainTransport::new(&receiver_chain, application_id));
+    assert_eq!(
+        receiver_client.execute(fungible_client::balance(target_account.owner)).await.unwrap(),
+        Amount::from(160u128)
+    );
+}
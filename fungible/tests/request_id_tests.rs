@@ -0,0 +1,101 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::panic::AssertUnwindSafe;
+
+use futures::FutureExt;
+use linera_sdk::base::{Amount, Owner, Timestamp};
+use linera_sdk::test::TestValidator;
+use fungible::{Account, AccountOwner, FungibleTokenAbi, OperationOpt, RequestKind};
+use fungible_client::{ActiveChainTransport, FungibleClient};
+
+#[tokio::test]
+async fn test_duplicate_request_id() {
+    let day_micros = 86_400_000_000;
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    let mut chain = validator.new_chain().await;
+    let admin = AccountOwner::User(Owner::from(chain.public_key()));
+    let receiver = AccountOwner::User(Owner::from(validator.new_chain().await.public_key()));
+    let application_id = chain
+        .create_application::<FungibleTokenAbi>(bytecode_id, (), Amount::from(1_000u128).into(), vec![])
+        .await;
+    let transfer = |request_id: &str| OperationOpt::Transfer {
+        owner: admin,
+        amount: Amount::from(100u128),
+        target_account: Account { chain_id: chain.id(), owner: receiver },
+        request_id: Some(request_id.to_string()),
+    };
+
+    // 钱包超时后用同一个请求ID重试，只转一次
+    let start = Timestamp::now();
+    for _ in 0..2 {
+        chain.add_block(|block| {
+            block.with_timestamp(start).with_operation(application_id, transfer("retry-1"));
+        }).await;
+    }
+    let client = FungibleClient::new(ActiveChainTransport::new(&chain, application_id));
+    assert_eq!(client.execute(fungible_client::balance(admin)).await.unwrap(), Amount::from(900u128));
+    assert_eq!(client.execute(fungible_client::balance(receiver)).await.unwrap(), Amount::from(100u128));
+
+    // 查询请求执行的结果，没有执行过的请求返回None
+    let status = client
+        .execute(fungible_client::request_status(admin, "retry-1"))
+        .await
+        .unwrap()
+        .expect("请求没有记录");
+    assert_eq!(status.kind, RequestKind::Transfer);
+    assert_eq!(status.amount, Amount::from(100u128));
+    assert!(client.execute(fungible_client::request_status(admin, "retry-2")).await.unwrap().is_none());
+
+    // 不同的请求ID正常执行，过期之后同一个请求ID会被当成新请求
+    chain.add_block(|block| {
+        block.with_timestamp(start).with_operation(application_id, transfer("retry-2"));
+    }).await;
+    chain.add_block(|block| {
+        block
+            .with_timestamp(Timestamp::from(start.micros() + day_micros))
+            .with_operation(application_id, transfer("retry-1"));
+    }).await;
+    assert_eq!(client.execute(fungible_client::balance(admin)).await.unwrap(), Amount::from(700u128));
+}
+
+#[tokio::test]
+async fn test_conflicting_request_id() {
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    let mut chain = validator.new_chain().await;
+    let admin = AccountOwner::User(Owner::from(chain.public_key()));
+    let receiver = AccountOwner::User(Owner::from(validator.new_chain().await.public_key()));
+    let other_receiver = AccountOwner::User(Owner::from(validator.new_chain().await.public_key()));
+    let application_id = chain
+        .create_application::<FungibleTokenAbi>(bytecode_id, (), Amount::from(1_000u128).into(), vec![])
+        .await;
+    let transfer = |amount: u128, owner: AccountOwner| OperationOpt::Transfer {
+        owner: admin,
+        amount: Amount::from(amount),
+        target_account: Account { chain_id: chain.id(), owner },
+        request_id: Some(String::from("wallet-1")),
+    };
+
+    let start = Timestamp::now();
+    chain.add_block(|block| {
+        block.with_timestamp(start).with_operation(application_id, transfer(100, receiver));
+    }).await;
+
+    // 同一个请求ID用在数量或者目标账户不一样的转账上，不能当成重试直接返回成功
+    for operation in [transfer(200, receiver), transfer(100, other_receiver)] {
+        let conflict = AssertUnwindSafe(chain.add_block(|block| {
+            block.with_timestamp(start).with_operation(application_id, operation);
+        })).catch_unwind().await;
+        assert!(conflict.is_err());
+    }
+
+    // 记录里面是第一次的请求内容，余额只变了一次
+    let client = FungibleClient::new(ActiveChainTransport::new(&chain, application_id));
+    let status = client
+        .execute(fungible_client::request_status(admin, "wallet-1"))
+        .await
+        .unwrap()
+        .expect("请求没有记录");
+    assert_eq!(status.target, Some(Account { chain_id: chain.id(), owner: receiver }));
+    assert_eq!(client.execute(fungible_client::balance(admin)).await.unwrap(), Amount::from(900u128));
+    assert_eq!(client.execute(fungible_client::balance(other_receiver)).await.unwrap(), Amount::ZERO);
+}
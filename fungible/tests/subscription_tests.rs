@@ -0,0 +1,106 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use linera_sdk::base::{Amount, ApplicationId, Owner};
+use linera_sdk::test::{ActiveChain, TestValidator};
+use fungible::{Account, AccountOwner, EventKind, FungibleTokenAbi, OperationOpt, TokenEvent};
+use fungible_client::{ActiveChainTransport, FungibleClient, Query};
+
+/*
+模拟客户端订阅：节点服务每出一个新区块就带上since重新请求一次订阅，只拿到新的事件
+*/
+struct EventWatcher {
+    application_id: ApplicationId<FungibleTokenAbi>,
+    subscription: fn(AccountOwner, u64) -> Query<Vec<TokenEvent>>,
+    owner: AccountOwner,
+    since: u64,
+}
+
+impl EventWatcher {
+    fn new(
+        application_id: ApplicationId<FungibleTokenAbi>,
+        subscription: fn(AccountOwner, u64) -> Query<Vec<TokenEvent>>,
+        owner: AccountOwner,
+    ) -> Self {
+        EventWatcher { application_id, subscription, owner, since: 0 }
+    }
+
+    // 相当于收到新区块通知后拉取新的事件
+    async fn poll(&mut self, chain: &ActiveChain) -> Vec<TokenEvent> {
+        let client = FungibleClient::new(ActiveChainTransport::new(chain, self.application_id));
+        let events = client.execute((self.subscription)(self.owner, self.since)).await.unwrap();
+        if let Some(last) = events.last() {
+            self.since = last.id + 1;
+        }
+        events
+    }
+}
+
+#[tokio::test]
+async fn test_events_arrive_after_add_block() {
+    let initial_amount = Amount::from(1_000u128);
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    let mut sender_chain = validator.new_chain().await;
+    let sender = AccountOwner::User(Owner::from(sender_chain.public_key()));
+    let application_id = sender_chain
+        .create_application::<FungibleTokenAbi>(bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let receiver_chain = validator.new_chain().await;
+    let receiver = AccountOwner::User(Owner::from(receiver_chain.public_key()));
+
+    let mut sender_balance = EventWatcher::new(application_id, fungible_client::balance_changed, sender);
+    let mut sender_transfers = EventWatcher::new(application_id, fungible_client::transfers, sender);
+    let mut receiver_transfers = EventWatcher::new(application_id, fungible_client::transfers, receiver);
+
+    // 部署时的铸造是余额变化，但不是转账
+    let events = sender_balance.poll(&sender_chain).await;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, EventKind::Mint);
+    assert!(sender_transfers.poll(&sender_chain).await.is_empty());
+
+    sender_chain.add_block(|block| {
+        block.with_operation(
+            application_id,
+            OperationOpt::Transfer {
+                owner: sender,
+                amount: Amount::from(100u128),
+                target_account: Account { chain_id: receiver_chain.id(), owner: receiver },
+                request_id: None,
+            },
+        );
+    }).await;
+
+    // 新区块之后只收到新的事件
+    let events = sender_balance.poll(&sender_chain).await;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, EventKind::TransferOut);
+    assert_eq!(events[0].balance, Amount::from(900u128));
+    assert_eq!(sender_transfers.poll(&sender_chain).await.len(), 1);
+    assert!(sender_balance.poll(&sender_chain).await.is_empty());
+
+    // 接收链处理消息之后收到转入事件
+    assert!(receiver_transfers.poll(&receiver_chain).await.is_empty());
+    receiver_chain.handle_received_messages().await;
+    let events = receiver_transfers.poll(&receiver_chain).await;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, EventKind::TransferIn);
+
+    // 超过一页的事件也全部收到，不会丢掉更早的事件
+    let transfer_count = 150;
+    sender_chain.add_block(|block| {
+        for _ in 0..transfer_count {
+            block.with_operation(
+                application_id,
+                OperationOpt::Transfer {
+                    owner: sender,
+                    amount: Amount::from(1u128),
+                    target_account: Account { chain_id: receiver_chain.id(), owner: receiver },
+                    request_id: None,
+                },
+            );
+        }
+    }).await;
+    let events = sender_balance.poll(&sender_chain).await;
+    assert_eq!(events.len(), transfer_count);
+    assert_eq!(events.last().unwrap().balance, Amount::from(750u128));
+    assert!(events.windows(2).all(|pair| pair[0].id < pair[1].id));
+}
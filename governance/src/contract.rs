@@ -0,0 +1,264 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+use async_trait::async_trait;
+use linera_sdk::{
+    ApplicationCallResult,
+    base::{SessionId, WithContractAbi}, CalleeContext, Contract, ExecutionResult, MessageContext,
+    OperationContext, SessionCallResult, ViewStateStorage,
+};
+use linera_sdk::base::{Amount, ApplicationId, ChainId, Owner, Timestamp};
+use linera_sdk::contract::system_api;
+use thiserror::Error;
+
+use fungible::{AccountOwner, ResponseOpt};
+use governance::{MessageOpt, OperationOpt, Proposal, ProposalAction, ProposalStatus, Vote};
+
+use self::state::Governance;
+
+mod state;
+/*
+治理合约入口文件
+*/
+
+linera_sdk::contract!(Governance);
+
+impl WithContractAbi for Governance {
+    type Abi = governance::GovernanceAbi;
+}
+
+#[async_trait]
+impl Contract for Governance {
+    type Error = ContractError;
+    type Storage = ViewStateStorage<Self>;
+
+    // 合约部署时调用，保存治理规则
+    async fn initialize(
+        &mut self,
+        _context: &OperationContext,
+        argument: Self::InitializationArgument,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        if argument.threshold_percent > 100 {
+            return Err(ContractError::InvalidThreshold);
+        }
+        self.config.set(argument);
+        Ok(ExecutionResult::default())
+    }
+
+    // 前端调用拦截转发
+    async fn execute_operation(
+        &mut self,
+        context: &OperationContext,
+        operation: Self::Operation,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        let signer = context.authenticated_signer.ok_or(ContractError::MissingSigner)?;
+        match operation {
+            OperationOpt::Propose {action,description} => {
+                Self::check_home_chain()?;
+                // 给代币创建快照，在部署合约的链上投票时按快照时的余额计票，防止投票后转给别人再投一次
+                let (response, _) = self.call_application(true,Self::token_id()?,&fungible::ApplicationCallOpt::TakeSnapshot,vec![]).await?;
+                let snapshot_id = match response {
+                    ResponseOpt::SnapshotId(snapshot_id) => snapshot_id,
+                    _ => return Err(ContractError::UnexpectedResponse),
+                };
+                let now = system_api::current_system_time();
+                let deadline = Timestamp::from(now.micros().saturating_add(self.config.get().voting_period_micros));
+                let proposal_id = self.add_proposal(Proposal {
+                    proposer: signer,
+                    action,
+                    description,
+                    snapshot_id,
+                    deadline,
+                    yes_votes: Amount::default(),
+                    no_votes: Amount::default(),
+                    status: ProposalStatus::Active,
+                });
+                log::debug!("{} 创建提案 {}",signer,proposal_id);
+                Ok(ExecutionResult::default())
+            }
+            OperationOpt::Vote {proposal_id,support} => {
+                let home_chain_id = Self::home_chain_id();
+                if system_api::current_chain_id() == home_chain_id {
+                    self.cast_vote(proposal_id,signer,support).await?;
+                    Ok(ExecutionResult::default())
+                } else {
+                    // 快照只在部署合约的链上，其它链只转发投票，由部署合约的链按快照计票(投票人在当前链上的代币不算)
+                    // (不能按当前链的余额计票，否则投票后把代币转到别的链上还能再投一次)
+                    let message = MessageOpt::Vote {proposal_id,support};
+                    Ok(ExecutionResult::default().with_authenticated_message(home_chain_id,message))
+                }
+            }
+            OperationOpt::Execute {proposal_id} => {
+                Self::check_home_chain()?;
+                let mut proposal = self.proposal(proposal_id).await.ok_or(ContractError::ProposalNotFound)?;
+                if proposal.status != ProposalStatus::Active {
+                    return Err(ContractError::ProposalClosed);
+                }
+                if system_api::current_system_time() < proposal.deadline {
+                    return Err(ContractError::VotingNotFinished);
+                }
+                if self.is_passed(&proposal) {
+                    let call = Self::action_call(proposal.action.clone());
+                    self.call_application(true,Self::token_id()?,&call,vec![]).await?;
+                    proposal.status = ProposalStatus::Executed;
+                } else {
+                    proposal.status = ProposalStatus::Defeated;
+                }
+                self.update_proposal(proposal_id,proposal);
+                Ok(ExecutionResult::default())
+            }
+        }
+    }
+
+    // 跨链相互调用
+    async fn execute_message(
+        &mut self,
+        context: &MessageContext,
+        message: Self::Message,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        match message {
+            MessageOpt::Vote {proposal_id,support} => {
+                let voter = context.authenticated_signer.ok_or(ContractError::MissingSigner)?;
+                // 消息执行失败会阻塞整个收件箱，所以无效的投票只记录日志然后忽略
+                if let Err(error) = self.cast_vote(proposal_id,voter,support).await {
+                    log::warn!("忽略 {} 对提案 {} 的投票: {}",voter,proposal_id,error);
+                }
+                Ok(ExecutionResult::default())
+            }
+        }
+    }
+
+    async fn handle_application_call(
+        &mut self,
+        _context: &CalleeContext,
+        _call: Self::ApplicationCall,
+        _forwarded_sessions: Vec<SessionId>,
+    ) -> Result<ApplicationCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        Err(ContractError::ApplicationCallNotSupported)
+    }
+
+    async fn handle_session_call(
+        &mut self,
+        _context: &CalleeContext,
+        _session: Self::SessionState,
+        _call: Self::SessionCall,
+        _forwarded_sessions: Vec<SessionId>,
+    ) -> Result<SessionCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        Err(ContractError::SessionNotSupported)
+    }
+}
+
+impl Governance {
+    // 治理的fungible程序ID
+    fn token_id() -> Result<ApplicationId<fungible::FungibleTokenAbi>, ContractError> {
+        Self::parameters()
+    }
+
+    // 部署合约的链，提案都保存在这条链上
+    fn home_chain_id() -> ChainId {
+        system_api::current_application_id().creation.chain_id
+    }
+
+    fn check_home_chain() -> Result<(), ContractError> {
+        if system_api::current_chain_id() == Self::home_chain_id() {
+            Ok(())
+        } else {
+            Err(ContractError::NotHomeChain)
+        }
+    }
+
+    // 获取还在投票中并且voter还没有投过票的提案
+    async fn open_proposal(&self, proposal_id: u64, voter: Owner) -> Result<Proposal, ContractError> {
+        let proposal = self.proposal(proposal_id).await.ok_or(ContractError::ProposalNotFound)?;
+        if proposal.status != ProposalStatus::Active || system_api::current_system_time() >= proposal.deadline {
+            return Err(ContractError::ProposalClosed);
+        }
+        if self.has_voted(proposal_id,voter).await {
+            return Err(ContractError::AlreadyVoted);
+        }
+        Ok(proposal)
+    }
+
+    // 在部署合约的链上按快照时的余额计票
+    async fn cast_vote(&mut self, proposal_id: u64, voter: Owner, support: bool) -> Result<(), ContractError> {
+        let mut proposal = self.open_proposal(proposal_id,voter).await?;
+        let weight = self.token_amount(fungible::ApplicationCallOpt::BalanceAt {
+            owner: AccountOwner::User(voter),
+            snapshot_id: proposal.snapshot_id,
+        }).await?;
+        self.record_vote(proposal_id,&mut proposal,voter,Vote {support,weight});
+        self.update_proposal(proposal_id,proposal);
+        Ok(())
+    }
+
+    // 调用代币查询余额
+    async fn token_amount(&mut self, call: fungible::ApplicationCallOpt) -> Result<Amount, ContractError> {
+        let (response, _) = self.call_application(true,Self::token_id()?,&call,vec![]).await?;
+        match response {
+            ResponseOpt::Amount(amount) => Ok(amount),
+            _ => Err(ContractError::UnexpectedResponse),
+        }
+    }
+
+    // 把提案操作转成代币调用
+    fn action_call(action: ProposalAction) -> fungible::ApplicationCallOpt {
+        match action {
+            ProposalAction::Mint {target_account,amount} => {
+                fungible::ApplicationCallOpt::Mint {target_account,amount,request_id: None}
+            }
+            ProposalAction::SetMinter {minter,enabled} => {
+                fungible::ApplicationCallOpt::SetMinter {minter,enabled}
+            }
+            ProposalAction::TransferAdmin {new_admin} => {
+                fungible::ApplicationCallOpt::TransferAdmin {new_admin}
+            }
+            ProposalAction::Transfer {amount,target_account} => fungible::ApplicationCallOpt::Transfer {
+                // 治理程序的金库账户
+                owner: AccountOwner::Application(system_api::current_application_id()),
+                amount,
+                target_account,
+                request_id: None,
+            },
+        }
+    }
+}
+
+// 自定义一些合约错误
+#[derive(Debug, Error)]
+pub enum ContractError {
+    #[error("Failed to deserialize BCS bytes")]
+    BcsError(#[from] bcs::Error),
+
+    #[error("Failed to deserialize JSON string")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Threshold must be a percentage between 0 and 100")]
+    InvalidThreshold,
+
+    #[error("Operation requires an authenticated signer")]
+    MissingSigner,
+
+    // 提案和执行只能在部署合约的链上进行
+    #[error("Operation must be executed on the governance home chain")]
+    NotHomeChain,
+
+    #[error("Proposal not found")]
+    ProposalNotFound,
+
+    #[error("Proposal is closed")]
+    ProposalClosed,
+
+    #[error("Voter has already voted")]
+    AlreadyVoted,
+
+    #[error("Voting period has not finished")]
+    VotingNotFinished,
+
+    #[error("Unexpected response from the token application")]
+    UnexpectedResponse,
+
+    #[error("Application calls not supported")]
+    ApplicationCallNotSupported,
+
+    #[error("Session not supported")]
+    SessionNotSupported,
+}
@@ -0,0 +1,145 @@
+use async_graphql::{Enum, Request, Response, scalar, SimpleObject};
+use linera_sdk::base::{Amount, ApplicationId, ContractAbi, Owner, ServiceAbi, Timestamp};
+use linera_sdk::graphql::GraphQLMutationRoot;
+use serde::{Deserialize, Serialize};
+use fungible::{Account, AccountOwner};
+
+/*
+治理程序上下文相关定义文件
+*/
+
+pub struct GovernanceAbi;
+
+// 合约写入相关Abi
+impl ContractAbi for GovernanceAbi {
+    // 参数是治理的fungible程序ID(注意：治理程序要成为该代币的管理员才能执行提案)
+    type Parameters = ApplicationId<fungible::FungibleTokenAbi>;
+    // 合约部署时的参数
+    type InitializationArgument = GovernanceConfig;
+    type Operation = OperationOpt;
+    type Message = MessageOpt;
+    type ApplicationCall = ();
+    type SessionCall = ();
+    type SessionState = ();
+    type Response = ();
+}
+
+// 合约查询相关Abi
+impl ServiceAbi for GovernanceAbi {
+    type Parameters = ApplicationId<fungible::FungibleTokenAbi>;
+    type Query = Request;
+    type QueryResponse = Response;
+}
+
+// 治理规则
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, SimpleObject)]
+pub struct GovernanceConfig {
+    // 法定票数：赞成票加反对票至少要达到这个数量
+    pub quorum: Amount,
+    // 通过阈值：赞成票占总票数的百分比至少要达到这个值
+    pub threshold_percent: u8,
+    // 投票时长(微秒)
+    pub voting_period_micros: u64,
+}
+
+// 操作枚举(注意：提案和执行只能在部署合约的链上进行，投票可以在任何链上进行)
+#[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
+pub enum OperationOpt {
+    // 创建提案，同时给代币创建一个快照
+    Propose {
+        action: ProposalAction,
+        description: String
+    },
+    /*
+    投票(注意：都按部署合约的链上快照时的余额计票，在其它链上投票只是把投票发给部署合约的链；
+    fungible的快照是每条链各自的，只有创建提案时在部署合约的链上的代币才有票数，投票人在其它链上的代币不算)
+    */
+    Vote {
+        proposal_id: u64,
+        support: bool
+    },
+    // 投票结束后执行提案，通过的提案会调用代币的管理员操作
+    Execute {
+        proposal_id: u64
+    }
+}
+
+// 消息枚举
+#[derive(Debug, Deserialize, Serialize)]
+pub enum MessageOpt {
+    // 其它链上的投票(注意：这个消息是签名发送的，投票人就是签名者，票数在部署合约的链上按快照计算)
+    Vote {
+        proposal_id: u64,
+        support: bool
+    }
+}
+
+// 这个是graphql解析使用
+scalar!(ProposalAction);
+
+// 提案通过后要执行的代币操作
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ProposalAction {
+    // 铸造代币
+    Mint {
+        target_account: Account,
+        amount: Amount
+    },
+    // 添加或移除铸币者
+    SetMinter {
+        minter: AccountOwner,
+        enabled: bool
+    },
+    // 转移代币管理员
+    TransferAdmin {
+        new_admin: AccountOwner
+    },
+    // 从治理程序的金库账户转账
+    Transfer {
+        amount: Amount,
+        target_account: Account
+    }
+}
+
+// 提案状态
+#[derive(Clone, Copy, Debug, Deserialize, Enum, Eq, PartialEq, Serialize)]
+pub enum ProposalStatus {
+    // 投票中或者等待执行
+    Active,
+    // 已通过并执行
+    Executed,
+    // 未通过
+    Defeated,
+}
+
+// 提案
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct Proposal {
+    pub proposer: Owner,
+    pub action: ProposalAction,
+    pub description: String,
+    // 创建提案时给代币创建的快照ID
+    pub snapshot_id: u64,
+    // 投票截止时间
+    pub deadline: Timestamp,
+    pub yes_votes: Amount,
+    pub no_votes: Amount,
+    pub status: ProposalStatus,
+}
+
+// 这个是graphql解析使用
+scalar!(Ballot);
+
+// 某个投票人对某个提案的投票，用作MapView的键
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Ballot {
+    pub proposal_id: u64,
+    pub voter: Owner,
+}
+
+// 投票内容
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct Vote {
+    pub support: bool,
+    pub weight: Amount,
+}
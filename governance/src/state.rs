@@ -0,0 +1,80 @@
+use linera_sdk::base::Owner;
+use linera_sdk::views::{MapView, RegisterView, ViewStorageContext};
+use linera_views::views::{GraphQLView, RootView};
+use governance::{Ballot, GovernanceConfig, Proposal, Vote};
+
+/*
+状态文件定义数据结构以及应用核心逻辑实现(注意：状态只在部署合约的链上有意义)
+*/
+
+#[derive(RootView, GraphQLView)]
+#[view(context = "ViewStorageContext")]
+pub struct Governance {
+    /* 治理规则 */
+    pub config: RegisterView<GovernanceConfig>,
+    /* 所有提案，键是提案ID */
+    pub proposals: MapView<u64, Proposal>,
+    /* 下一个提案ID */
+    pub next_proposal_id: RegisterView<u64>,
+    /* 每个投票人对每个提案的投票 */
+    pub votes: MapView<Ballot, Vote>,
+}
+
+#[allow(dead_code)]//该注解用于去除未使用警告
+impl Governance {
+    // 添加提案，返回提案ID
+    pub fn add_proposal(&mut self, proposal: Proposal) -> u64 {
+        let proposal_id = *self.next_proposal_id.get();
+        self.next_proposal_id.set(proposal_id + 1);
+        self.proposals
+            .insert(&proposal_id, proposal)
+            .expect("记录提案失败!");
+        proposal_id
+    }
+
+    // 获取提案
+    pub async fn proposal(&self, proposal_id: u64) -> Option<Proposal> {
+        self.proposals
+            .get(&proposal_id)
+            .await
+            .expect("获取提案失败!")
+    }
+
+    // 更新提案
+    pub fn update_proposal(&mut self, proposal_id: u64, proposal: Proposal) {
+        self.proposals
+            .insert(&proposal_id, proposal)
+            .expect("更新提案失败!");
+    }
+
+    // 是否已经投过票
+    pub async fn has_voted(&self, proposal_id: u64, voter: Owner) -> bool {
+        self.votes
+            .get(&Ballot { proposal_id, voter })
+            .await
+            .expect("获取投票失败!")
+            .is_some()
+    }
+
+    // 记录投票并计票
+    pub fn record_vote(&mut self, proposal_id: u64, proposal: &mut Proposal, voter: Owner, vote: Vote) {
+        if vote.support {
+            proposal.yes_votes.saturating_add_assign(vote.weight);
+        } else {
+            proposal.no_votes.saturating_add_assign(vote.weight);
+        }
+        self.votes
+            .insert(&Ballot { proposal_id, voter }, vote)
+            .expect("记录投票失败!");
+    }
+
+    // 提案是否通过：总票数达到法定票数，并且赞成票占比达到阈值
+    pub fn is_passed(&self, proposal: &Proposal) -> bool {
+        let config = self.config.get();
+        let yes = u128::from(proposal.yes_votes);
+        let total = yes.saturating_add(u128::from(proposal.no_votes));
+        total > 0
+            && total >= u128::from(config.quorum)
+            && yes.saturating_mul(100) >= total.saturating_mul(u128::from(config.threshold_percent))
+    }
+}
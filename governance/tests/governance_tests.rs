@@ -0,0 +1,120 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use linera_sdk::base::{Amount, Owner, Timestamp};
+use linera_sdk::test::TestValidator;
+use fungible::{Account, AccountOwner, FungibleTokenAbi};
+use governance::{GovernanceAbi, GovernanceConfig, OperationOpt, ProposalAction};
+use fungible_client::{ActiveChainTransport, FungibleClient};
+
+#[tokio::test]
+async fn test_remote_vote_decides_proposal() {
+    let initial_amount = Amount::from(1_000_000u128);
+    let voter_amount = Amount::from(600_000u128);
+    let late_amount = Amount::from(300_000u128);
+    let mint_amount = Amount::from(5_000u128);
+    let voting_period_micros = 60_000_000;
+    // 模拟一个验证节点，当前项目就是治理程序
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    // 部署合约的链，提案都保存在这条链上
+    let mut home_chain = validator.new_chain().await;
+    let home_owner = AccountOwner::User(Owner::from(home_chain.public_key()));
+    // 投票人所在的另一条链
+    let voter_chain = validator.new_chain().await;
+    let voter_owner = AccountOwner::User(Owner::from(voter_chain.public_key()));
+
+    let fungible_bytecode_id = home_chain.publish_bytecodes_in("../fungible").await;
+    let token_id = home_chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let governance_id = home_chain
+        .create_application::<GovernanceAbi>(
+            bytecode_id,
+            token_id,
+            GovernanceConfig {
+                quorum: Amount::from(500_000u128),
+                threshold_percent: 50,
+                voting_period_micros,
+            },
+            vec![token_id.forget_abi()],
+        ).await;
+
+    // 把代币管理员交给治理程序，并给投票人在部署合约的链上的账户转账
+    home_chain.add_block(|block| {
+        block
+            .with_operation(
+                token_id,
+                fungible::OperationOpt::TransferAdmin {
+                    new_admin: AccountOwner::Application(governance_id.forget_abi()),
+                },
+            )
+            .with_operation(
+                token_id,
+                fungible::OperationOpt::Transfer {
+                    owner: home_owner,
+                    amount: voter_amount,
+                    target_account: Account { chain_id: home_chain.id(), owner: voter_owner },
+                    request_id: None,
+                },
+            );
+    }).await;
+    voter_chain.register_application(governance_id).await;
+
+    // 在部署合约的链上创建提案：给部署者铸币
+    home_chain.add_block(|block| {
+        block.with_operation(
+            governance_id,
+            OperationOpt::Propose {
+                action: ProposalAction::Mint {
+                    target_account: Account { chain_id: home_chain.id(), owner: home_owner },
+                    amount: mint_amount,
+                },
+                description: String::from("Mint for the treasury"),
+            },
+        );
+    }).await;
+
+    // 快照之后再给投票人在另一条链上转账，这部分不计入票数
+    home_chain.add_block(|block| {
+        block.with_operation(
+            token_id,
+            fungible::OperationOpt::Transfer {
+                owner: home_owner,
+                amount: late_amount,
+                target_account: Account { chain_id: voter_chain.id(), owner: voter_owner },
+                request_id: None,
+            },
+        );
+    }).await;
+    voter_chain.handle_received_messages().await;
+
+    // 部署者投反对票(快照时余额400000)，另一条链上的投票人投赞成票(快照时余额600000)
+    home_chain.add_block(|block| {
+        block.with_operation(governance_id, OperationOpt::Vote { proposal_id: 0, support: false });
+    }).await;
+    voter_chain.add_block(|block| {
+        block.with_operation(governance_id, OperationOpt::Vote { proposal_id: 0, support: true });
+    }).await;
+    home_chain.handle_received_messages().await;
+    let value = home_chain
+        .graphql_query(governance_id, String::from("query { proposals(u64: 0) { yesVotes noVotes } }"))
+        .await;
+    let votes = |field: &str| value["proposals"][field].as_str().unwrap().parse::<Amount>().unwrap();
+    assert_eq!(votes("yesVotes"), voter_amount);
+    assert_eq!(votes("noVotes"), initial_amount.saturating_sub(voter_amount));
+
+    // 投票结束后执行提案
+    let after_deadline = Timestamp::from(Timestamp::now().micros() + voting_period_micros);
+    home_chain.add_block(|block| {
+        block
+            .with_timestamp(after_deadline)
+            .with_operation(governance_id, OperationOpt::Execute { proposal_id: 0 });
+    }).await;
+
+    // 远程的赞成票让提案通过，铸币已经执行
+    let home_balance = initial_amount
+        .saturating_sub(voter_amount)
+        .saturating_sub(late_amount)
+        .saturating_add(mint_amount);
+    let home_token = FungibleClient::new(ActiveChainTransport::new(&home_chain, token_id));
+    assert_eq!(home_token.execute(fungible_client::balance(home_owner)).await.unwrap(), home_balance);
+}
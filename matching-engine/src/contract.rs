@@ -0,0 +1,326 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+use async_trait::async_trait;
+use linera_sdk::{
+    ApplicationCallResult,
+    base::{SessionId, WithContractAbi}, CalleeContext, Contract, ExecutionResult, MessageContext,
+    OperationContext, SessionCallResult, ViewStateStorage,
+};
+use linera_sdk::base::{Amount, ApplicationId, ChainId, Owner};
+use linera_sdk::contract::system_api;
+use thiserror::Error;
+
+use fungible::{Account, AccountOwner, FungibleTokenAbi, ResponseOpt};
+use matching_engine::{MessageOpt, OperationOpt, Order, Side};
+
+use self::state::MatchingEngine;
+
+mod state;
+/*
+撮合引擎合约入口文件
+*/
+
+linera_sdk::contract!(MatchingEngine);
+
+impl WithContractAbi for MatchingEngine {
+    type Abi = matching_engine::MatchingEngineAbi;
+}
+
+#[async_trait]
+impl Contract for MatchingEngine {
+    type Error = ContractError;
+    type Storage = ViewStateStorage<Self>;
+
+    // 合约部署时调用，检查交易对参数
+    async fn initialize(
+        &mut self,
+        _context: &OperationContext,
+        _argument: Self::InitializationArgument,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        let parameters = Self::parameters()?;
+        if parameters.base == parameters.quote {
+            return Err(ContractError::IdenticalTokens);
+        }
+        Ok(ExecutionResult::default())
+    }
+
+    // 前端调用拦截转发
+    async fn execute_operation(
+        &mut self,
+        context: &OperationContext,
+        operation: Self::Operation,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        let signer = context.authenticated_signer.ok_or(ContractError::MissingSigner)?;
+        let chain_id = system_api::current_chain_id();
+        let home_chain_id = Self::home_chain_id();
+        match operation {
+            OperationOpt::PlaceOrder {side,price,amount} => {
+                let (token, escrow) = Self::escrow(side,price,amount)?;
+                // 把签名者的代币托管到撮合引擎在部署合约的链上的程序账户(注意：在其它链上fungible程序会发消息给部署合约的链入账)
+                let call = fungible::ApplicationCallOpt::Transfer {
+                    owner: AccountOwner::User(signer),
+                    amount: escrow,
+                    target_account: Self::engine_account(),
+                    request_id: None,
+                };
+                self.call_application(true,token,&call,vec![]).await?;
+                if chain_id == home_chain_id {
+                    self.place_order(signer,chain_id,side,price,amount).await?;
+                    Ok(ExecutionResult::default())
+                } else {
+                    // 这条消息排在fungible程序的入账消息后面，部署合约的链处理它时托管的代币已经到账
+                    let message = MessageOpt::PlaceOrder {side,price,amount};
+                    Ok(ExecutionResult::default().with_authenticated_message(home_chain_id,message))
+                }
+            }
+            OperationOpt::CancelOrder {order_id} => {
+                if chain_id == home_chain_id {
+                    self.cancel(signer,order_id).await?;
+                    Ok(ExecutionResult::default())
+                } else {
+                    let message = MessageOpt::CancelOrder {order_id};
+                    Ok(ExecutionResult::default().with_authenticated_message(home_chain_id,message))
+                }
+            }
+        }
+    }
+
+    // 跨链相互调用
+    async fn execute_message(
+        &mut self,
+        context: &MessageContext,
+        message: Self::Message,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        let owner = context.authenticated_signer.ok_or(ContractError::MissingSigner)?;
+        match message {
+            MessageOpt::PlaceOrder {side,price,amount} => {
+                let chain_id = context.message_id.chain_id;
+                // 消息执行失败会阻塞整个收件箱，所以先检查撮合引擎的账户够不够这个订单的所有转出，
+                // 检查不通过就退回托管的代币然后忽略这个订单；检查通过之后撮合和结算不会再失败
+                if let Err(error) = self.check_payouts(side,price,amount).await {
+                    log::warn!("忽略 {} 的下单并退回托管的代币: {}",owner,error);
+                    self.refund_escrow(owner,chain_id,side,price,amount).await?;
+                    return Ok(ExecutionResult::default());
+                }
+                self.place_order(owner,chain_id,side,price,amount).await?;
+            }
+            MessageOpt::CancelOrder {order_id} => {
+                // 消息执行失败会阻塞整个收件箱，所以无效的撤单只记录日志然后忽略
+                if let Err(error) = self.cancel(owner,order_id).await {
+                    log::warn!("忽略 {} 对订单 {} 的撤单: {}",owner,order_id,error);
+                }
+            }
+        }
+        Ok(ExecutionResult::default())
+    }
+
+    async fn handle_application_call(
+        &mut self,
+        _context: &CalleeContext,
+        _call: Self::ApplicationCall,
+        _forwarded_sessions: Vec<SessionId>,
+    ) -> Result<ApplicationCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        Err(ContractError::ApplicationCallNotSupported)
+    }
+
+    async fn handle_session_call(
+        &mut self,
+        _context: &CalleeContext,
+        _session: Self::SessionState,
+        _call: Self::SessionCall,
+        _forwarded_sessions: Vec<SessionId>,
+    ) -> Result<SessionCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        Err(ContractError::SessionNotSupported)
+    }
+}
+
+impl MatchingEngine {
+    // 部署合约的链，订单簿和托管的代币都在这条链上
+    fn home_chain_id() -> ChainId {
+        system_api::current_application_id().creation.chain_id
+    }
+
+    // 撮合引擎在fungible程序中的账户
+    fn engine_account() -> Account {
+        Account {
+            chain_id: Self::home_chain_id(),
+            owner: AccountOwner::Application(system_api::current_application_id()),
+        }
+    }
+
+    // 某个方向托管的代币：买单托管quote代币，卖单托管base代币
+    fn escrow_token(side: Side) -> Result<ApplicationId<FungibleTokenAbi>, ContractError> {
+        let parameters = Self::parameters()?;
+        Ok(match side {
+            Side::Bid => parameters.quote,
+            Side::Ask => parameters.base,
+        })
+    }
+
+    // 下单需要托管的代币和数量(注意：买单按价格算出的quote代币向下取整后不能是0)
+    fn escrow(side: Side, price: u64, amount: Amount) -> Result<(ApplicationId<FungibleTokenAbi>, Amount), ContractError> {
+        if price == 0 || amount == Amount::default() {
+            return Err(ContractError::InvalidOrder);
+        }
+        let escrow = match side {
+            Side::Bid => matching_engine::quote_amount(amount,price).ok_or(ContractError::Overflow)?,
+            Side::Ask => amount,
+        };
+        if escrow == Amount::default() {
+            return Err(ContractError::InvalidOrder);
+        }
+        Ok((Self::escrow_token(side)?, escrow))
+    }
+
+    // 撮合引擎的程序账户在某个代币中的可用余额
+    async fn engine_balance(&mut self, token: ApplicationId<FungibleTokenAbi>) -> Result<Amount, ContractError> {
+        let call = fungible::ApplicationCallOpt::Balance { owner: Self::engine_account().owner };
+        let (response, _) = self.call_application(true,token,&call,vec![]).await?;
+        Ok(match response {
+            ResponseOpt::Amount(balance) => balance,
+            _ => Amount::default(),
+        })
+    }
+
+    // 检查撮合引擎的账户够不够下单后的所有转出：付给挂单方和退回的托管代币都不超过托管的数量，
+    // 吃单方得到的另一种代币按订单簿预先算出来(注意：撮合引擎的账户不受转账限额限制，所以只需要检查余额)
+    async fn check_payouts(&mut self, side: Side, price: u64, amount: Amount) -> Result<(), ContractError> {
+        let (token, escrow) = Self::escrow(side,price,amount)?;
+        let mut proceeds = Amount::default();
+        for (fill_price, fill_amount) in self.preview_fills(side,price,amount).await {
+            let proceed = match side {
+                Side::Bid => fill_amount,
+                Side::Ask => matching_engine::quote_amount(fill_amount,fill_price).ok_or(ContractError::Overflow)?,
+            };
+            proceeds.saturating_add_assign(proceed);
+        }
+        for (token, requested) in [(token, escrow), (Self::escrow_token(side.opposite())?, proceeds)] {
+            let available = self.engine_balance(token).await?;
+            if available < requested {
+                return Err(ContractError::InsufficientEngineBalance {available, requested});
+            }
+        }
+        Ok(())
+    }
+
+    // 退回其它链上失败订单的托管代币(注意：订单无效或者托管的代币没有到账时没有可以退的，只记录日志)
+    async fn refund_escrow(&mut self, owner: Owner, chain_id: ChainId, side: Side, price: u64, amount: Amount) -> Result<(), ContractError> {
+        let (token, escrow) = match Self::escrow(side,price,amount) {
+            Ok(escrow) => escrow,
+            Err(error) => {
+                log::warn!("{} 的订单无效，没有可以退回的托管代币: {}",owner,error);
+                return Ok(());
+            }
+        };
+        let available = self.engine_balance(token).await?;
+        if available < escrow {
+            log::warn!("{} 的托管代币没有到账: 需要 {} 可用 {}",owner,escrow,available);
+            return Ok(());
+        }
+        self.pay(token,owner,chain_id,escrow).await
+    }
+
+    // 撮合新订单并结算，剩下的部分挂到订单簿上(注意：代币已经托管)
+    async fn place_order(&mut self, owner: Owner, chain_id: ChainId, side: Side, price: u64, amount: Amount) -> Result<u64, ContractError> {
+        let mut order = Order {
+            owner,
+            chain_id,
+            side,
+            price,
+            amount,
+            filled: Amount::default(),
+        };
+        let order_id = self.add_order(order.clone());
+        let fills = self.match_order(order_id,&mut order).await;
+        let parameters = Self::parameters()?;
+        for fill in fills {
+            let (buyer, seller) = match side {
+                Side::Bid => (&order, &fill.maker),
+                Side::Ask => (&fill.maker, &order),
+            };
+            let quote = matching_engine::quote_amount(fill.amount,fill.price).ok_or(ContractError::Overflow)?;
+            self.pay(parameters.base,buyer.owner,buyer.chain_id,fill.amount).await?;
+            self.pay(parameters.quote,seller.owner,seller.chain_id,quote).await?;
+            // 买单吃单时成交价可能比出价低，退回多托管的quote代币
+            if side == Side::Bid && fill.price < price {
+                let refund = matching_engine::quote_amount(fill.amount,price - fill.price).ok_or(ContractError::Overflow)?;
+                self.pay(parameters.quote,owner,chain_id,refund).await?;
+            }
+        }
+        if order.amount == Amount::default() {
+            self.cancel_order(order_id);
+        } else {
+            self.rest_order(order_id,&order).await;
+        }
+        log::debug!("{} 下单 {} 成交 {}",owner,order_id,order.filled);
+        Ok(order_id)
+    }
+
+    // 撤单并退回还没成交部分的托管代币
+    async fn cancel(&mut self, owner: Owner, order_id: u64) -> Result<(), ContractError> {
+        let order = self.order(order_id).await.ok_or(ContractError::OrderNotFound)?;
+        if order.owner != owner {
+            return Err(ContractError::NotOrderOwner);
+        }
+        self.cancel_order(order_id);
+        let refund = match order.side {
+            Side::Bid => matching_engine::quote_amount(order.amount,order.price).ok_or(ContractError::Overflow)?,
+            Side::Ask => order.amount,
+        };
+        self.pay(Self::escrow_token(order.side)?,order.owner,order.chain_id,refund).await
+    }
+
+    // 从撮合引擎的程序账户转出(注意：目标账户在其它链上时fungible程序会发消息入账)
+    async fn pay(&mut self, token: ApplicationId<FungibleTokenAbi>, owner: Owner, chain_id: ChainId, amount: Amount) -> Result<(), ContractError> {
+        if amount == Amount::default() {
+            return Ok(());
+        }
+        let call = fungible::ApplicationCallOpt::Transfer {
+            owner: Self::engine_account().owner,
+            amount,
+            target_account: Account { chain_id, owner: AccountOwner::User(owner) },
+            request_id: None,
+        };
+        self.call_application(true,token,&call,vec![]).await?;
+        Ok(())
+    }
+}
+
+// 自定义一些合约错误
+#[derive(Debug, Error)]
+pub enum ContractError {
+    #[error("Failed to deserialize BCS bytes")]
+    BcsError(#[from] bcs::Error),
+
+    #[error("Failed to deserialize JSON string")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Base and quote tokens must be different applications")]
+    IdenticalTokens,
+
+    #[error("Operation requires an authenticated signer")]
+    MissingSigner,
+
+    // 价格和数量都必须大于0，托管的代币也必须大于0
+    #[error("Order price, amount and escrow must be greater than zero")]
+    InvalidOrder,
+
+    #[error("Arithmetic overflow")]
+    Overflow,
+
+    #[error("Order not found")]
+    OrderNotFound,
+
+    #[error("Only the order owner can cancel the order")]
+    NotOrderOwner,
+
+    // 撮合引擎账户里的代币不够这个订单的转出
+    #[error("Insufficient engine balance: requested {requested}, available {available}")]
+    InsufficientEngineBalance { available: Amount, requested: Amount },
+
+    #[error("Application calls not supported")]
+    ApplicationCallNotSupported,
+
+    #[error("Session not supported")]
+    SessionNotSupported,
+}
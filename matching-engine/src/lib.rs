@@ -0,0 +1,130 @@
+use async_graphql::{Enum, Request, Response, SimpleObject};
+use linera_sdk::base::{Amount, ApplicationId, ChainId, ContractAbi, Owner, ServiceAbi};
+use linera_sdk::graphql::GraphQLMutationRoot;
+use serde::{Deserialize, Serialize};
+use fungible::FungibleTokenAbi;
+
+/*
+订单簿撮合引擎上下文相关定义文件
+*/
+
+pub struct MatchingEngineAbi;
+
+// 合约写入相关Abi
+impl ContractAbi for MatchingEngineAbi {
+    // 参数是交易对的两个fungible程序ID
+    type Parameters = MatchingParameters;
+    type InitializationArgument = ();
+    type Operation = OperationOpt;
+    type Message = MessageOpt;
+    type ApplicationCall = ();
+    type SessionCall = ();
+    type SessionState = ();
+    type Response = ();
+}
+
+// 合约查询相关Abi
+impl ServiceAbi for MatchingEngineAbi {
+    type Parameters = MatchingParameters;
+    type Query = Request;
+    type QueryResponse = Response;
+}
+
+// 交易对参数：用quote代币买卖base代币
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct MatchingParameters {
+    pub base: ApplicationId<FungibleTokenAbi>,
+    pub quote: ApplicationId<FungibleTokenAbi>,
+}
+
+// 买卖方向
+#[derive(Clone, Copy, Debug, Deserialize, Enum, Eq, PartialEq, Serialize)]
+pub enum Side {
+    // 买单：托管quote代币，买入base代币
+    Bid,
+    // 卖单：托管base代币，卖出换quote代币
+    Ask,
+}
+
+impl Side {
+    // 对手方向
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::Bid => Side::Ask,
+            Side::Ask => Side::Bid,
+        }
+    }
+}
+
+/*
+操作枚举(注意：订单簿在部署合约的链上，在其它链上下单会把托管的代币转到部署合约的链，再发消息过去下单。
+price是放大了PRICE_SCALE倍的定点数：每10^9个最小单位的base代币值多少个最小单位的quote代币，所以价格可以小于1，最多9位小数)
+*/
+#[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
+pub enum OperationOpt {
+    // 下限价单，能成交的部分立即成交，剩下的部分挂在订单簿上
+    PlaceOrder {
+        side: Side,
+        price: u64,
+        amount: Amount
+    },
+    // 撤单，退回还没成交部分的托管代币
+    CancelOrder {
+        order_id: u64
+    }
+}
+
+// 消息枚举(注意：这些消息都是签名发送的，下单人就是签名者)
+#[derive(Debug, Deserialize, Serialize)]
+pub enum MessageOpt {
+    // 其它链上的下单，托管的代币已经转到部署合约的链
+    PlaceOrder {
+        side: Side,
+        price: u64,
+        amount: Amount
+    },
+    // 其它链上的撤单
+    CancelOrder {
+        order_id: u64
+    }
+}
+
+// 订单
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct Order {
+    pub owner: Owner,
+    // 下单的链，成交和撤单的代币都转到这条链上
+    pub chain_id: ChainId,
+    pub side: Side,
+    pub price: u64,
+    // 还没成交的base代币数量
+    pub amount: Amount,
+    // 已经成交的base代币数量
+    pub filled: Amount,
+}
+
+// 成交记录(注意：成交价是挂单的价格)
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct Trade {
+    pub maker_order_id: u64,
+    pub taker_order_id: u64,
+    // 吃单方向
+    pub taker_side: Side,
+    pub price: u64,
+    pub amount: Amount,
+}
+
+// 订单簿某个价格的挂单总量
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct PriceLevel {
+    pub price: u64,
+    pub amount: Amount,
+}
+
+// 价格放大的倍数：price是1000000000表示1个最小单位的base代币值1个最小单位的quote代币
+pub const PRICE_SCALE: u64 = 1_000_000_000;
+
+// 计算amount个base代币按price要多少quote代币(按最小单位向下取整)，超出u128时返回None
+pub fn quote_amount(amount: Amount, price: u64) -> Option<Amount> {
+    fixed_math::mul_div(u128::from(amount), u128::from(price), u128::from(PRICE_SCALE)).map(Amount::from)
+}
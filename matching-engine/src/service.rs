@@ -0,0 +1,74 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+mod state;
+
+use self::state::MatchingEngine;
+use async_trait::async_trait;
+use linera_sdk::{base::WithServiceAbi, QueryContext, Service, ViewStateStorage};
+use std::sync::Arc;
+use async_graphql::{EmptySubscription, MergedObject, Object, Response, Schema};
+use linera_sdk::graphql::GraphQLMutationRoot;
+use thiserror::Error;
+use matching_engine::{OperationOpt, PriceLevel, Side};
+
+/*
+查询服务相关实现(就是定义一些只读的数据给前端调用)
+*/
+
+linera_sdk::service!(MatchingEngine);
+
+impl WithServiceAbi for MatchingEngine {
+    type Abi = matching_engine::MatchingEngineAbi;
+}
+
+#[async_trait]
+impl Service for MatchingEngine {
+    type Error = ServiceError;
+    type Storage = ViewStateStorage<Self>;
+
+    async fn handle_query(
+        self: Arc<Self>,
+        _context: &QueryContext,
+        request: Self::Query,
+    ) -> Result<Response, Self::Error> {
+        let query_root = QueryRoot(self.clone(), BookQuery { state: self.clone() });
+        let schema = Schema::build(query_root,OperationOpt::mutation_root(),EmptySubscription).finish();
+        let response = schema.execute(request).await;
+        Ok(response)
+    }
+}
+
+#[derive(MergedObject)]
+struct QueryRoot(Arc<MatchingEngine>, BookQuery);
+
+// 订单簿相关查询
+struct BookQuery {
+    state: Arc<MatchingEngine>,
+}
+
+#[Object]
+impl BookQuery {
+    // 最高买价
+    async fn best_bid(&self) -> Option<u64> {
+        self.state.best_price(Side::Bid).await
+    }
+
+    // 最低卖价
+    async fn best_ask(&self) -> Option<u64> {
+        self.state.best_price(Side::Ask).await
+    }
+
+    // 订单簿深度：从最优价格开始最多limit个价格档位
+    async fn depth(&self, side: Side, #[graphql(default = 10)] limit: usize) -> Vec<PriceLevel> {
+        self.state.depth(side, limit).await
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ServiceError {
+    #[error("Queries not supported by application")]
+    QueriesNotSupported,
+
+    #[error("Invalid query argument; could not deserialize request")]
+    InvalidQuery(#[from] serde_json::Error),
+}
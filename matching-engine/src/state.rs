@@ -0,0 +1,249 @@
+use linera_sdk::base::Amount;
+use linera_sdk::views::{LogView, MapView, RegisterView, ViewStorageContext};
+use linera_views::collection_view::CustomCollectionView;
+use linera_views::queue_view::QueueView;
+use linera_views::views::{GraphQLView, RootView};
+use matching_engine::{Order, PriceLevel, Side, Trade};
+
+/*
+状态文件定义数据结构以及应用核心逻辑实现(注意：状态只在部署合约的链上有意义)
+*/
+
+// 价格档位：键是价格(按大小顺序存储)，值是按下单时间排队的订单ID
+type PriceLevels = CustomCollectionView<ViewStorageContext, u64, QueueView<ViewStorageContext, u64>>;
+
+#[derive(RootView, GraphQLView)]
+#[view(context = "ViewStorageContext")]
+pub struct MatchingEngine {
+    /* 还没有完全成交的订单，键是订单ID */
+    pub orders: MapView<u64, Order>,
+    /* 下一个订单ID */
+    pub next_order_id: RegisterView<u64>,
+    /* 买单价格档位 */
+    pub bids: PriceLevels,
+    /* 卖单价格档位 */
+    pub asks: PriceLevels,
+    /* 成交记录 */
+    pub trades: LogView<Trade>,
+}
+
+// 一次成交，合约根据它结算代币
+#[derive(Clone, Debug)]
+pub struct Fill {
+    pub maker_order_id: u64,
+    pub maker: Order,
+    pub price: u64,
+    pub amount: Amount,
+}
+
+#[allow(dead_code)]//该注解用于去除未使用警告
+impl MatchingEngine {
+    // 记录新订单，返回订单ID
+    pub fn add_order(&mut self, order: Order) -> u64 {
+        let order_id = *self.next_order_id.get();
+        self.next_order_id.set(order_id + 1);
+        self.orders
+            .insert(&order_id, order)
+            .expect("记录订单失败!");
+        order_id
+    }
+
+    // 获取订单
+    pub async fn order(&self, order_id: u64) -> Option<Order> {
+        self.orders
+            .get(&order_id)
+            .await
+            .expect("获取订单失败!")
+    }
+
+    // 撤单(注意：只删除订单，价格档位队列中的订单ID在撮合时再删除)
+    pub fn cancel_order(&mut self, order_id: u64) {
+        self.orders
+            .remove(&order_id)
+            .expect("删除订单失败!");
+    }
+
+    // 把剩下的部分挂到订单簿上
+    pub async fn rest_order(&mut self, order_id: u64, order: &Order) {
+        self.orders
+            .insert(&order_id, order.clone())
+            .expect("记录订单失败!");
+        self.book_mut(order.side)
+            .load_entry_mut(&order.price)
+            .await
+            .expect("获取价格档位失败!")
+            .push_back(order_id);
+    }
+
+    // 最优价格：买单是最高价，卖单是最低价(注意：撤单不会删除价格档位，所以跳过只剩已撤销订单的价格档位)
+    pub async fn best_price(&self, side: Side) -> Option<u64> {
+        for price in self.prices(side).await {
+            if !self.level_orders(side, price).await.is_empty() {
+                return Some(price);
+            }
+        }
+        None
+    }
+
+    // 不修改订单簿，预先算出按price下amount个base代币的订单会产生的成交：(成交价, 成交数量)
+    pub async fn preview_fills(&self, side: Side, price: u64, amount: Amount) -> Vec<(u64, Amount)> {
+        let mut fills = Vec::new();
+        let mut remaining = amount;
+        let maker_side = side.opposite();
+        for level_price in self.prices(maker_side).await {
+            if remaining == Amount::default() || !Self::crossed(side, price, level_price) {
+                break;
+            }
+            for maker in self.level_orders(maker_side, level_price).await {
+                if remaining == Amount::default() {
+                    break;
+                }
+                let fill_amount = remaining.min(maker.amount);
+                remaining = remaining.saturating_sub(fill_amount);
+                fills.push((level_price, fill_amount));
+            }
+        }
+        fills
+    }
+
+    // 按价格优先、时间优先撮合taker，返回所有成交(注意：taker的数量会被更新，但不会写入订单簿)
+    pub async fn match_order(&mut self, taker_order_id: u64, taker: &mut Order) -> Vec<Fill> {
+        let mut fills = Vec::new();
+        let maker_side = taker.side.opposite();
+        // 价格档位只读取一次，从最优价格开始逐档撮合
+        for price in self.prices(maker_side).await {
+            if taker.amount == Amount::default() || !Self::crossed(taker.side, taker.price, price) {
+                break;
+            }
+            while taker.amount > Amount::default() {
+                let front = self.book_mut(maker_side)
+                    .load_entry_mut(&price)
+                    .await
+                    .expect("获取价格档位失败!")
+                    .front()
+                    .await
+                    .expect("获取订单队列失败!");
+                let maker_order_id = match front {
+                    Some(maker_order_id) => maker_order_id,
+                    None => {
+                        // 这个价格档位已经吃完，继续下一档
+                        self.book_mut(maker_side).remove_entry(&price).expect("删除价格档位失败!");
+                        break;
+                    }
+                };
+                let mut maker = match self.order(maker_order_id).await {
+                    Some(maker) => maker,
+                    None => {
+                        // 已经撤销的订单，在这里从队列中删除
+                        self.pop_front(maker_side, price).await;
+                        continue;
+                    }
+                };
+                let amount = taker.amount.min(maker.amount);
+                taker.amount = taker.amount.saturating_sub(amount);
+                taker.filled.saturating_add_assign(amount);
+                maker.amount = maker.amount.saturating_sub(amount);
+                maker.filled.saturating_add_assign(amount);
+                if maker.amount == Amount::default() {
+                    self.cancel_order(maker_order_id);
+                    self.pop_front(maker_side, price).await;
+                } else {
+                    self.orders
+                        .insert(&maker_order_id, maker.clone())
+                        .expect("记录订单失败!");
+                }
+                self.trades.push(Trade {
+                    maker_order_id,
+                    taker_order_id,
+                    taker_side: taker.side,
+                    price,
+                    amount,
+                });
+                fills.push(Fill { maker_order_id, maker, price, amount });
+            }
+        }
+        fills
+    }
+
+    // 订单簿深度：从最优价格开始，每个价格档位的挂单总量
+    pub async fn depth(&self, side: Side, limit: usize) -> Vec<PriceLevel> {
+        let mut levels = Vec::new();
+        for price in self.prices(side).await {
+            if levels.len() >= limit {
+                break;
+            }
+            let mut amount = Amount::default();
+            for order in self.level_orders(side, price).await {
+                amount.saturating_add_assign(order.amount);
+            }
+            if amount > Amount::default() {
+                levels.push(PriceLevel { price, amount });
+            }
+        }
+        levels
+    }
+
+    // 所有价格档位，从最优价格开始排列
+    async fn prices(&self, side: Side) -> Vec<u64> {
+        let mut prices = self.book(side)
+            .indices()
+            .await
+            .expect("获取价格档位失败!");
+        if side == Side::Bid {
+            prices.reverse();
+        }
+        prices
+    }
+
+    // 某个价格档位上还没撤销的订单，按下单时间排列
+    async fn level_orders(&self, side: Side, price: u64) -> Vec<Order> {
+        let order_ids = self.book(side)
+            .try_load_entry(&price)
+            .await
+            .expect("获取价格档位失败!")
+            .elements()
+            .await
+            .expect("获取订单队列失败!");
+        let mut orders = Vec::new();
+        for order_id in order_ids {
+            if let Some(order) = self.order(order_id).await {
+                orders.push(order);
+            }
+        }
+        orders
+    }
+
+    // taker的价格能不能和这个价格档位成交
+    fn crossed(taker_side: Side, taker_price: u64, level_price: u64) -> bool {
+        match taker_side {
+            Side::Bid => level_price <= taker_price,
+            Side::Ask => level_price >= taker_price,
+        }
+    }
+
+    // 删除价格档位队首的订单ID，队列空了就删除这个价格档位
+    async fn pop_front(&mut self, side: Side, price: u64) {
+        let level = self.book_mut(side)
+            .load_entry_mut(&price)
+            .await
+            .expect("获取价格档位失败!");
+        level.delete_front();
+        if level.count() == 0 {
+            self.book_mut(side).remove_entry(&price).expect("删除价格档位失败!");
+        }
+    }
+
+    fn book(&self, side: Side) -> &PriceLevels {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    fn book_mut(&mut self, side: Side) -> &mut PriceLevels {
+        match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        }
+    }
+}
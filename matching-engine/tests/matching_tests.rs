@@ -0,0 +1,154 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use linera_sdk::base::{Amount, Owner};
+use linera_sdk::test::TestValidator;
+use fungible::{Account, AccountOwner, FungibleTokenAbi};
+use matching_engine::{MatchingEngineAbi, MatchingParameters, OperationOpt, PRICE_SCALE, Side};
+use fungible_client::{ActiveChainTransport, FungibleClient};
+
+#[tokio::test]
+async fn test_remote_ask_fills_bids_by_price() {
+    let initial_amount = Amount::from(1_000_000u128);
+    let seller_amount = Amount::from(300u128);
+    // 模拟一个验证节点，当前项目就是撮合引擎
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    // 部署合约的链，订单簿在这条链上
+    let mut home_chain = validator.new_chain().await;
+    let buyer = AccountOwner::User(Owner::from(home_chain.public_key()));
+    // 卖家在另一条链上
+    let seller_chain = validator.new_chain().await;
+    let seller = AccountOwner::User(Owner::from(seller_chain.public_key()));
+
+    let fungible_bytecode_id = home_chain.publish_bytecodes_in("../fungible").await;
+    let base = home_chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let quote = home_chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let engine_id = home_chain
+        .create_application::<MatchingEngineAbi>(
+            bytecode_id,
+            MatchingParameters { base, quote },
+            (),
+            vec![base.forget_abi(), quote.forget_abi()],
+        ).await;
+
+    // 给卖家转一些base代币，买家挂两个买单：价格2数量100，价格3数量50
+    home_chain.add_block(|block| {
+        block
+            .with_operation(
+                base,
+                fungible::OperationOpt::Transfer {
+                    owner: buyer,
+                    amount: seller_amount,
+                    target_account: Account { chain_id: seller_chain.id(), owner: seller },
+                    request_id: None,
+                },
+            )
+            .with_operation(
+                engine_id,
+                OperationOpt::PlaceOrder { side: Side::Bid, price: 2 * PRICE_SCALE, amount: Amount::from(100u128) },
+            )
+            .with_operation(
+                engine_id,
+                OperationOpt::PlaceOrder { side: Side::Bid, price: 3 * PRICE_SCALE, amount: Amount::from(50u128) },
+            );
+    }).await;
+    seller_chain.handle_received_messages().await;
+    seller_chain.register_application(engine_id).await;
+
+    // 卖家在自己的链上挂价格2数量120的卖单，先吃掉价格3的买单，再吃掉价格2的买单中的70
+    seller_chain.add_block(|block| {
+        block.with_operation(
+            engine_id,
+            OperationOpt::PlaceOrder { side: Side::Ask, price: 2 * PRICE_SCALE, amount: Amount::from(120u128) },
+        );
+    }).await;
+    home_chain.handle_received_messages().await;
+    let value = home_chain.graphql_query(engine_id, String::from("query { bestBid bestAsk }")).await;
+    assert_eq!(value["bestBid"].as_u64(), Some(2 * PRICE_SCALE));
+    assert!(value["bestAsk"].is_null());
+
+    // 撤销剩下的买单，退回30 * 2个quote代币
+    home_chain.add_block(|block| {
+        block.with_operation(engine_id, OperationOpt::CancelOrder { order_id: 0 });
+    }).await;
+    seller_chain.handle_received_messages().await;
+
+    let home_base = FungibleClient::new(ActiveChainTransport::new(&home_chain, base));
+    assert_eq!(
+        home_base.execute(fungible_client::balance(buyer)).await.unwrap(),
+        initial_amount.saturating_sub(seller_amount).saturating_add(Amount::from(120u128))
+    );
+    let home_quote = FungibleClient::new(ActiveChainTransport::new(&home_chain, quote));
+    assert_eq!(
+        home_quote.execute(fungible_client::balance(buyer)).await.unwrap(),
+        initial_amount.saturating_sub(Amount::from(290u128))
+    );
+    let seller_base = FungibleClient::new(ActiveChainTransport::new(&seller_chain, base));
+    assert_eq!(seller_base.execute(fungible_client::balance(seller)).await.unwrap(), Amount::from(180u128));
+    let seller_quote = FungibleClient::new(ActiveChainTransport::new(&seller_chain, quote));
+    assert_eq!(seller_quote.execute(fungible_client::balance(seller)).await.unwrap(), Amount::from(290u128));
+    let engine = AccountOwner::Application(engine_id.forget_abi());
+    assert_eq!(home_quote.execute(fungible_client::balance(engine)).await.unwrap(), Amount::default());
+}
+
+#[tokio::test]
+async fn test_fractional_price_and_cancelled_level() {
+    let initial_amount = Amount::from(1_000_000u128);
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    let mut chain = validator.new_chain().await;
+    let owner = AccountOwner::User(Owner::from(chain.public_key()));
+
+    let fungible_bytecode_id = chain.publish_bytecodes_in("../fungible").await;
+    let base = chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let quote = chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let engine_id = chain
+        .create_application::<MatchingEngineAbi>(
+            bytecode_id,
+            MatchingParameters { base, quote },
+            (),
+            vec![base.forget_abi(), quote.forget_abi()],
+        ).await;
+
+    // 价格0.5数量100的买单托管50个quote代币，价格0.25数量40的买单托管10个quote代币，然后撤销价格0.5的买单
+    chain.add_block(|block| {
+        block
+            .with_operation(
+                engine_id,
+                OperationOpt::PlaceOrder { side: Side::Bid, price: PRICE_SCALE / 2, amount: Amount::from(100u128) },
+            )
+            .with_operation(
+                engine_id,
+                OperationOpt::PlaceOrder { side: Side::Bid, price: PRICE_SCALE / 4, amount: Amount::from(40u128) },
+            )
+            .with_operation(engine_id, OperationOpt::CancelOrder { order_id: 0 });
+    }).await;
+
+    // 只剩已撤销订单的价格档位不算最优价格
+    let value = chain.graphql_query(engine_id, String::from("query { bestBid depth(side: Bid) { price } }")).await;
+    assert_eq!(value["bestBid"].as_u64(), Some(PRICE_SCALE / 4));
+    assert_eq!(value["depth"].as_array().unwrap().len(), 1);
+
+    // 卖单按价格0.25成交，卖出40个base代币得到10个quote代币
+    chain.add_block(|block| {
+        block.with_operation(
+            engine_id,
+            OperationOpt::PlaceOrder { side: Side::Ask, price: PRICE_SCALE / 4, amount: Amount::from(40u128) },
+        );
+    }).await;
+    let value = chain.graphql_query(engine_id, String::from("query { bestBid }")).await;
+    assert!(value["bestBid"].is_null());
+
+    let quote_client = FungibleClient::new(ActiveChainTransport::new(&chain, quote));
+    assert_eq!(quote_client.execute(fungible_client::balance(owner)).await.unwrap(), initial_amount);
+    let engine = AccountOwner::Application(engine_id.forget_abi());
+    assert_eq!(quote_client.execute(fungible_client::balance(engine)).await.unwrap(), Amount::default());
+    let base_client = FungibleClient::new(ActiveChainTransport::new(&chain, base));
+    assert_eq!(base_client.execute(fungible_client::balance(engine)).await.unwrap(), Amount::default());
+}
@@ -2,16 +2,21 @@
 
 mod state;
 
-use self::state::MetaFungible;
+use self::state::{InsufficientSharesError, MetaFungible};
 use async_trait::async_trait;
 use linera_sdk::{
     base::{SessionId, WithContractAbi},
     ApplicationCallResult, CalleeContext, Contract, ExecutionResult, MessageContext,
     OperationContext, SessionCallResult, ViewStateStorage,
 };
-use linera_sdk::base::ApplicationId;
+use linera_sdk::base::{Amount, ApplicationId, BlockHeight, ChainId, Owner};
+use linera_sdk::contract::system_api;
 use thiserror::Error;
-use meta_fungible::OperationPP;
+use fungible::{Account, AccountOwner, ResponseOpt};
+use meta_fungible::{
+    FungibleCall, MessagePP, OperationPP, RemoteTransfer, RemoteTransferStatus, TokenOwner, TransferRecord,
+    TransferStatus,
+};
 
 linera_sdk::contract!(MetaFungible);
 
@@ -20,9 +25,257 @@ impl WithContractAbi for MetaFungible {
 }
 
 impl MetaFungible {
-    fn fungible_id() -> Result<ApplicationId<fungible::FungibleTokenAbi>,ContractError> {
+    // 默认的fungible程序ID
+    fn default_token() -> Result<ApplicationId<fungible::FungibleTokenAbi>,ContractError> {
         Self::parameters()
     }
+
+    // 金库账户，也就是MetaFungible自己在fungible程序中的账户
+    fn vault_owner() -> AccountOwner {
+        AccountOwner::Application(system_api::current_application_id())
+    }
+
+    // 按顺序执行操作中的所有fungible调用，value中是每个调用的结果，execution_result中是要发出的跨链消息
+    async fn execute(
+        &mut self,
+        operation: OperationPP,
+        caller: Option<Owner>,
+        height: Option<BlockHeight>,
+    ) -> Result<ApplicationCallResult<MessagePP, Vec<ResponseOpt>, ()>, ContractError> {
+        let mut res = ApplicationCallResult::default();
+        let (token, calls) = match operation {
+            OperationPP::Transfer {token,owner,amount,target_account} => {
+                (token, vec![FungibleCall::Transfer {owner,amount,target_account}])
+            }
+            OperationPP::Claim {token,source_account,amount,target_account} => {
+                (token, vec![FungibleCall::Claim {source_account,amount,target_account}])
+            }
+            OperationPP::Batch {token,calls} => {
+                if calls.is_empty() {
+                    return Err(ContractError::EmptyBatch);
+                }
+                (token, calls)
+            }
+            OperationPP::RegisterToken {token,name} => {
+                self.check_admin(caller)?;
+                Self::check_creator_chain()?;
+                self.tokens.insert(&token,name.clone()).expect("登记代币失败!");
+                res.execution_result = self.broadcast(MessagePP::TokenRegistered {token,name}).await;
+                return Ok(res);
+            }
+            OperationPP::UnregisterToken {token} => {
+                self.check_admin(caller)?;
+                Self::check_creator_chain()?;
+                self.tokens.remove(&token).expect("移除代币失败!");
+                res.execution_result = self.broadcast(MessagePP::TokenUnregistered {token}).await;
+                return Ok(res);
+            }
+            OperationPP::SubscribeRegistry => {
+                // 部署链上的注册表就是最新的，不用订阅
+                let creator_chain_id = Self::creator_chain_id();
+                if creator_chain_id != system_api::current_chain_id() {
+                    res.execution_result = ExecutionResult::default().with_message(creator_chain_id,MessagePP::Subscribe);
+                }
+                return Ok(res);
+            }
+            OperationPP::Deposit {token,amount} => {
+                self.check_registered(&token).await?;
+                let owner = AccountOwner::User(caller.ok_or(ContractError::MissingSigner)?);
+                // 把签名者的代币转到金库账户，再记上相同数量的份额
+                let call = FungibleCall::Transfer {
+                    owner,
+                    amount,
+                    target_account: Account {
+                        chain_id: system_api::current_chain_id(),
+                        owner: Self::vault_owner(),
+                    },
+                };
+                self.forward_call(token,call,caller,height).await?;
+                self.add_shares(TokenOwner {token,owner},amount).await;
+                return Ok(res);
+            }
+            OperationPP::Withdraw {token,amount,target_account} => {
+                self.check_registered(&token).await?;
+                let owner = AccountOwner::User(caller.ok_or(ContractError::MissingSigner)?);
+                // 先扣份额，再以MetaFungible自己的身份从金库账户转出
+                self.remove_shares(TokenOwner {token,owner},amount).await?;
+                let call = FungibleCall::Transfer {
+                    owner: Self::vault_owner(),
+                    amount,
+                    target_account,
+                };
+                self.forward_call(token,call,caller,height).await?;
+                return Ok(res);
+            }
+            OperationPP::RemoteTransfer {chain_id,token,amount,target_account} => {
+                self.check_registered(&token).await?;
+                let owner = AccountOwner::User(caller.ok_or(ContractError::MissingSigner)?);
+                // 在本链记录请求，然后签名发给chain_id链上的MetaFungible，由它代为转账
+                let request_id = self.add_remote_transfer(RemoteTransfer {
+                    chain_id,
+                    token,
+                    owner,
+                    amount,
+                    target_account,
+                    status: RemoteTransferStatus::Pending,
+                    reason: None,
+                }).await;
+                let message = MessagePP::TransferRequest {
+                    request_id,
+                    token,
+                    owner,
+                    amount,
+                    target_account,
+                };
+                res.execution_result = ExecutionResult::default().with_authenticated_message(chain_id,message);
+                return Ok(res);
+            }
+        };
+        self.check_registered(&token).await?;
+        let mut results = Vec::with_capacity(calls.len());
+        for call in calls {
+            // 金库账户只能通过Withdraw转出，否则任何人都可以借MetaFungible的身份转走金库里的代币
+            if call.source_owner() == Some(Self::vault_owner()) {
+                return Err(ContractError::VaultAccountNotAllowed);
+            }
+            res.value.push(self.forward_call(token,call,caller,height).await?);
+        }
+        Ok(res)
+    }
+
+    // 处理其它链发来的转账请求，返回要回复给来源链的消息
+    async fn handle_transfer_request(
+        &mut self,
+        context: &MessageContext,
+        request_id: u64,
+        token: ApplicationId,
+        owner: AccountOwner,
+        amount: Amount,
+        target_account: Account,
+    ) -> Result<MessagePP, ContractError> {
+        // 来源链的注册表可能还没有同步，以当前链的注册表为准
+        if !self.is_registered(&token).await {
+            return Ok(MessagePP::TransferFailed {
+                request_id,
+                reason: format!("Token {} is not registered", token),
+            });
+        }
+        // 只能代签名者自己转账，所以金库账户也不能通过这种方式转出
+        let signer = context.authenticated_signer;
+        if signer.map(AccountOwner::User) != Some(owner) {
+            return Ok(MessagePP::TransferFailed {
+                request_id,
+                reason: String::from("Incorrect Authentication"),
+            });
+        }
+        // 调用失败会让整个消息失败并堵住收件箱，所以先预检查余额、锁定和转账限额，不能转时回复失败消息
+        let (response, _) = self.call_application(
+            true,
+            token.with_abi::<fungible::FungibleTokenAbi>(),
+            &fungible::ApplicationCallOpt::CheckTransfer {owner,amount},
+            vec![],
+        ).await?;
+        if let ResponseOpt::Rejected(code) = response {
+            return Ok(MessagePP::TransferFailed {
+                request_id,
+                reason: format!("[{}] {:?}", code.code(), code),
+            });
+        }
+        let call = FungibleCall::Transfer {owner,amount,target_account};
+        self.forward_call(token,call,signer,Some(context.height)).await?;
+        Ok(MessagePP::TransferCompleted {request_id})
+    }
+
+    // 部署MetaFungible的链，代币注册表以这条链为准
+    fn creator_chain_id() -> ChainId {
+        system_api::current_application_id().creation.chain_id
+    }
+
+    // 代币注册表只能在部署MetaFungible的链上修改
+    fn check_creator_chain() -> Result<(), ContractError> {
+        if Self::creator_chain_id() == system_api::current_chain_id() {
+            Ok(())
+        } else {
+            Err(ContractError::NotCreatorChain)
+        }
+    }
+
+    // 把注册表的修改发给所有订阅的链
+    async fn broadcast(&self, message: MessagePP) -> ExecutionResult<MessagePP> {
+        let mut result = ExecutionResult::default();
+        for chain_id in self.subscriber_chains().await {
+            result = result.with_message(chain_id,message.clone());
+        }
+        result
+    }
+
+    // 只能路由到已经登记的代币
+    async fn check_registered(&self, token: &ApplicationId) -> Result<(), ContractError> {
+        if self.is_registered(token).await {
+            Ok(())
+        } else {
+            Err(ContractError::UnknownToken(*token))
+        }
+    }
+
+    // 验证调用者是不是管理员
+    fn check_admin(&self, caller: Option<Owner>) -> Result<(), ContractError> {
+        match *self.admin.get() {
+            Some(admin) if caller == Some(admin) => Ok(()),
+            _ => Err(ContractError::NotAdmin)
+        }
+    }
+
+    // 把一个调用转发给token对应的fungible程序，转账和获取代币成功后记录下来
+    async fn forward_call(
+        &mut self,
+        token: ApplicationId,
+        call: FungibleCall,
+        caller: Option<Owner>,
+        height: Option<BlockHeight>,
+    ) -> Result<ResponseOpt, ContractError> {
+        let current_chain_id = system_api::current_chain_id();
+        let record = match &call {
+            FungibleCall::Balance {..} => None,
+            FungibleCall::Transfer {owner,amount,target_account} => Some(TransferRecord {
+                token,
+                caller,
+                owner: *owner,
+                amount: *amount,
+                target_account: *target_account,
+                status: Self::transfer_status(target_account.chain_id == current_chain_id),
+                height,
+            }),
+            FungibleCall::Claim {source_account,amount,target_account} => Some(TransferRecord {
+                token,
+                caller,
+                owner: source_account.owner,
+                amount: *amount,
+                target_account: *target_account,
+                // source_account不在当前链上时fungible程序会发出Withdraw消息
+                status: Self::transfer_status(
+                    source_account.chain_id == current_chain_id && target_account.chain_id == current_chain_id
+                ),
+                height,
+            }),
+        };
+        // 调用另一个程序
+        let fungible_id = token.with_abi::<fungible::FungibleTokenAbi>();
+        let (value, _) = self.call_application(true,fungible_id,&call.into(),vec![]).await?;
+        if let Some(record) = record {
+            self.record_transfer(record).await;
+        }
+        Ok(value)
+    }
+
+    // 目标账户不在当前链上时fungible程序只是发出了消息，还未入账
+    fn transfer_status(local: bool) -> TransferStatus {
+        if local {
+            TransferStatus::Completed
+        } else {
+            TransferStatus::Pending
+        }
+    }
 }
 
 #[async_trait]
@@ -32,48 +285,94 @@ impl Contract for MetaFungible {
 
     async fn initialize(
         &mut self,
-        _context: &OperationContext,
+        context: &OperationContext,
         _argument: Self::InitializationArgument,
     ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        // 部署者成为管理员，并把参数中的fungible程序登记为默认代币
+        self.admin.set(context.authenticated_signer);
+        self.tokens
+            .insert(&Self::default_token()?.forget_abi(),String::from("default"))
+            .expect("登记默认代币失败!");
         Ok(ExecutionResult::default())
     }
     // 前端调用拦截转发
     async fn execute_operation(
         &mut self,
-        _context: &OperationContext,
-        _operation: Self::Operation,
+        context: &OperationContext,
+        operation: Self::Operation,
     ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
-        match _operation {
-            OperationPP::Transfer {owner,amount,target_account} => {
-                // 装配调用另一个程序的参数
-                let transfer_call = fungible::ApplicationCallOpt::Transfer {
-                    owner,
-                    amount,
-                    target_account
-                };
-                // 调用另一个程序
-                self.call_application(true,Self::fungible_id()?,&transfer_call,vec![]).await?;
-            }
-        }
-        Ok(ExecutionResult::default())
+        // 注意：前端操作无法拿到返回值，余额查询请使用查询服务的balance
+        let res = self.execute(operation,context.authenticated_signer,Some(context.height)).await?;
+        Ok(res.execution_result)
     }
 
+    // 跨链相互调用
     async fn execute_message(
         &mut self,
-        _context: &MessageContext,
-        _message: Self::Message,
+        context: &MessageContext,
+        message: Self::Message,
     ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
-        Ok(ExecutionResult::default())
+        // 消息来源链
+        let origin_chain_id = context.message_id.chain_id;
+        match message {
+            MessagePP::TransferRequest {request_id,token,owner,amount,target_account} => {
+                let reply = self.handle_transfer_request(context,request_id,token,owner,amount,target_account).await?;
+                // 把执行结果回复给来源链
+                Ok(ExecutionResult::default().with_message(origin_chain_id,reply))
+            }
+            MessagePP::TransferCompleted {request_id} => {
+                self.finish_remote_transfer(origin_chain_id,request_id,RemoteTransferStatus::Completed,None).await;
+                Ok(ExecutionResult::default())
+            }
+            MessagePP::TransferFailed {request_id,reason} => {
+                self.finish_remote_transfer(origin_chain_id,request_id,RemoteTransferStatus::Failed,Some(reason)).await;
+                Ok(ExecutionResult::default())
+            }
+            MessagePP::Subscribe => {
+                // 记录订阅的链，并回复完整的注册表
+                if Self::check_creator_chain().is_err() {
+                    log::warn!("忽略发到非部署链的订阅 {}",origin_chain_id);
+                    return Ok(ExecutionResult::default());
+                }
+                self.subscribers.insert(&origin_chain_id,true).expect("记录订阅的链失败!");
+                let tokens = self.registered_tokens().await;
+                Ok(ExecutionResult::default().with_message(origin_chain_id,MessagePP::RegistrySync {tokens}))
+            }
+            MessagePP::RegistrySync {..} | MessagePP::TokenRegistered {..} | MessagePP::TokenUnregistered {..} => {
+                // 只接受部署链发来的注册表(管理员在那条链上已经验证过)
+                if origin_chain_id != Self::creator_chain_id() {
+                    log::warn!("忽略不是部署链发来的代币注册表 {}",origin_chain_id);
+                    return Ok(ExecutionResult::default());
+                }
+                match message {
+                    MessagePP::RegistrySync {tokens} => self.replace_tokens(tokens).await,
+                    MessagePP::TokenRegistered {token,name} => {
+                        self.tokens.insert(&token,name).expect("登记代币失败!");
+                    }
+                    MessagePP::TokenUnregistered {token} => {
+                        self.tokens.remove(&token).expect("移除代币失败!");
+                    }
+                    _ => unreachable!(),
+                }
+                Ok(ExecutionResult::default())
+            }
+        }
     }
 
+    // 相同链其它程序调用，把每个fungible调用的结果通过Response返回
     async fn handle_application_call(
         &mut self,
-        _context: &CalleeContext,
-        _call: Self::ApplicationCall,
+        context: &CalleeContext,
+        call: Self::ApplicationCall,
         _forwarded_sessions: Vec<SessionId>,
     ) -> Result<ApplicationCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error>
     {
-        Ok(ApplicationCallResult::default())
+        let res = self.execute(call,context.authenticated_signer,None).await?;
+        Ok(ApplicationCallResult {
+            value: res.value,
+            execution_result: res.execution_result,
+            create_sessions: vec![],
+        })
     }
 
     async fn handle_session_call(
@@ -99,5 +398,31 @@ pub enum ContractError {
     #[error("Failed to deserialize JSON string")]
     JsonError(#[from] serde_json::Error),
 
-    // Add more error variants here.
+    /// Batch operation without any call
+    #[error("Batch must contain at least one call")]
+    EmptyBatch,
+
+    /// The token is not in the registry
+    #[error("Token {0} is not registered")]
+    UnknownToken(ApplicationId),
+
+    /// Only the admin can change the token registry
+    #[error("Only the admin can change the token registry")]
+    NotAdmin,
+
+    /// The token registry can only be changed on the chain that created the application
+    #[error("The token registry can only be changed on the creator chain")]
+    NotCreatorChain,
+
+    /// Deposit and withdraw need an authenticated signer
+    #[error("Operation requires an authenticated signer")]
+    MissingSigner,
+
+    /// Withdraw more shares than owned
+    #[error("Insufficient vault shares")]
+    InsufficientShares(#[from] InsufficientSharesError),
+
+    /// The vault account can only be moved by Withdraw
+    #[error("The vault account can only be debited by Withdraw")]
+    VaultAccountNotAllowed,
 }
@@ -1,5 +1,5 @@
-use async_graphql::{Request, Response};
-use linera_sdk::base::{Amount, ApplicationId, ContractAbi, ServiceAbi};
+use async_graphql::{Enum, Request, Response, scalar, SimpleObject};
+use linera_sdk::base::{Amount, ApplicationId, BlockHeight, ChainId, ContractAbi, Owner, ServiceAbi};
 use linera_sdk::graphql::GraphQLMutationRoot;
 use serde::{Deserialize, Serialize};
 use fungible::{Account, AccountOwner};
@@ -7,28 +7,216 @@ use fungible::{Account, AccountOwner};
 pub struct MetaFungibleAbi;
 
 impl ContractAbi for MetaFungibleAbi {
-    // 参数（注意：这个）
+    // 参数（注意：这个是默认的fungible程序ID，部署时会自动登记到代币注册表中）
     type Parameters = ApplicationId<fungible::FungibleTokenAbi>;
     type InitializationArgument = ();
     type Operation = OperationPP;
-    type Message = ();
-    type ApplicationCall = ();
+    // 消息类型
+    type Message = MessagePP;
+    // 其它程序调用MetaFungible时和前端使用相同的操作枚举
+    type ApplicationCall = OperationPP;
     type SessionCall = ();
     type SessionState = ();
-    type Response = ();
+    // 按顺序返回每个fungible调用的结果(Balance返回余额，Transfer和Claim返回ResponseOpt::Ok)
+    type Response = Vec<fungible::ResponseOpt>;
 }
 
 impl ServiceAbi for MetaFungibleAbi {
-    type Parameters = ();
+    // 查询服务也需要fungible程序ID，用于透传查询余额
+    type Parameters = ApplicationId<fungible::FungibleTokenAbi>;
     type Query = Request;
     type QueryResponse = Response;
 }
 // GraphQLMutationRoot是让GraphQL可以识别到该枚举
-#[derive(Debug,Serialize,Deserialize,GraphQLMutationRoot)]
+#[derive(Clone,Debug,Serialize,Deserialize,GraphQLMutationRoot)]
 pub enum OperationPP {
+    // 注意：token是要路由到的fungible程序ID，必须已经登记在代币注册表中
     Transfer {
+        token: ApplicationId,
+        owner: AccountOwner,
+        amount: Amount,
+        target_account: Account
+    },
+    // 从source_account获取代币
+    Claim {
+        token: ApplicationId,
+        source_account: Account,
+        amount: Amount,
+        target_account: Account
+    },
+    // 按顺序执行一批fungible调用(注意：其中任何一个调用失败整个操作都会失败，所以是原子的)
+    Batch {
+        token: ApplicationId,
+        calls: Vec<FungibleCall>
+    },
+    // 登记一个fungible程序到代币注册表(仅管理员，只能在部署MetaFungible的链上执行，会同步给订阅的链)
+    RegisterToken {
+        token: ApplicationId,
+        name: String
+    },
+    // 从代币注册表中移除一个fungible程序(仅管理员，只能在部署MetaFungible的链上执行，会同步给订阅的链)
+    UnregisterToken {
+        token: ApplicationId
+    },
+    // 存入金库：把签名者的代币转到MetaFungible的程序账户，并记上相同数量的份额
+    Deposit {
+        token: ApplicationId,
+        amount: Amount
+    },
+    // 从金库取出：扣除签名者的份额，由MetaFungible从自己的程序账户转给target_account
+    Withdraw {
+        token: ApplicationId,
+        amount: Amount,
+        target_account: Account
+    },
+    // 请求chain_id链上的MetaFungible代签名者执行一笔fungible转账(转出账户是签名者在chain_id链上的账户)
+    RemoteTransfer {
+        chain_id: ChainId,
+        token: ApplicationId,
+        amount: Amount,
+        target_account: Account
+    },
+    // 订阅部署MetaFungible的链上的代币注册表(注意：其它链上的注册表一开始是空的，要先订阅才能使用)
+    SubscribeRegistry
+}
+
+// 消息枚举(注意：消息只会在不同链上的MetaFungible之间发送)
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum MessagePP {
+    // 请求目标链代为转账(注意：这个消息是签名发送的，目标链用签名者验证owner)
+    TransferRequest {
+        request_id: u64,
+        token: ApplicationId,
         owner: AccountOwner,
         amount: Amount,
         target_account: Account
+    },
+    // 目标链转账成功，回复给来源链
+    TransferCompleted {
+        request_id: u64
+    },
+    // 目标链转账失败，回复给来源链
+    TransferFailed {
+        request_id: u64,
+        reason: String
+    },
+    // 其它链订阅代币注册表，发给部署MetaFungible的链
+    Subscribe,
+    // 部署MetaFungible的链回复订阅者完整的代币注册表
+    RegistrySync {
+        tokens: Vec<(ApplicationId, String)>
+    },
+    // 部署MetaFungible的链登记了代币，通知订阅者
+    TokenRegistered {
+        token: ApplicationId,
+        name: String
+    },
+    // 部署MetaFungible的链移除了代币，通知订阅者
+    TokenUnregistered {
+        token: ApplicationId
     }
 }
+
+// 跨链转账请求的状态
+#[derive(Clone, Copy, Debug, Deserialize, Enum, Eq, PartialEq, Serialize)]
+pub enum RemoteTransferStatus {
+    // 已经发出请求，还没有收到目标链的回复
+    Pending,
+    Completed,
+    Failed,
+}
+
+// 发给其它链的转账请求，记录在来源链上
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct RemoteTransfer {
+    // 执行转账的链
+    pub chain_id: ChainId,
+    pub token: ApplicationId,
+    pub owner: AccountOwner,
+    pub amount: Amount,
+    pub target_account: Account,
+    pub status: RemoteTransferStatus,
+    // 失败原因
+    pub reason: Option<String>,
+}
+
+// 这个是graphql解析使用
+scalar!(FungibleCall);
+
+// 可以转发给fungible程序的调用(和fungible::ApplicationCallOpt一一对应)
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum FungibleCall {
+    Balance {
+        owner: AccountOwner
+    },
+    Transfer {
+        owner: AccountOwner,
+        amount: Amount,
+        target_account: Account
+    },
+    Claim {
+        source_account: Account,
+        amount: Amount,
+        target_account: Account
+    }
+}
+
+impl FungibleCall {
+    // 这个调用会从哪个账户扣款(查询余额不扣款)
+    pub fn source_owner(&self) -> Option<AccountOwner> {
+        match self {
+            FungibleCall::Balance { .. } => None,
+            FungibleCall::Transfer { owner, .. } => Some(*owner),
+            FungibleCall::Claim { source_account, .. } => Some(source_account.owner),
+        }
+    }
+}
+
+impl From<FungibleCall> for fungible::ApplicationCallOpt {
+    fn from(call: FungibleCall) -> Self {
+        match call {
+            FungibleCall::Balance { owner } => fungible::ApplicationCallOpt::Balance { owner },
+            FungibleCall::Transfer { owner, amount, target_account } => {
+                fungible::ApplicationCallOpt::Transfer { owner, amount, target_account, request_id: None }
+            }
+            FungibleCall::Claim { source_account, amount, target_account } => {
+                fungible::ApplicationCallOpt::Claim { source_account, amount, target_account, request_id: None }
+            }
+        }
+    }
+}
+
+// 转发转账的结果状态
+#[derive(Clone, Copy, Debug, Deserialize, Enum, Eq, PartialEq, Serialize)]
+pub enum TransferStatus {
+    // 目标账户在当前链上，fungible程序已经直接完成入账
+    Completed,
+    // 目标账户在其它链上，fungible程序已经发出Credit消息，等待目标链处理
+    Pending,
+}
+
+// 这个是graphql解析使用
+scalar!(TokenOwner);
+
+// 某个fungible程序中的某个账户，用作按代币区分的MapView的键
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct TokenOwner {
+    pub token: ApplicationId,
+    pub owner: AccountOwner,
+}
+
+// MetaFungible通过call_application转发的一笔转账记录(Claim也会记录，owner是source_account的账户)
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct TransferRecord {
+    // 转账所属的fungible程序ID
+    pub token: ApplicationId,
+    // 发起操作的签名者
+    pub caller: Option<Owner>,
+    // 转出账户
+    pub owner: AccountOwner,
+    pub amount: Amount,
+    pub target_account: Account,
+    pub status: TransferStatus,
+    // 转发时所在的区块高度(注意：其它程序调用MetaFungible时上下文中没有区块高度)
+    pub height: Option<BlockHeight>,
+}
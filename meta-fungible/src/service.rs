@@ -6,9 +6,11 @@ use self::state::MetaFungible;
 use async_trait::async_trait;
 use linera_sdk::{base::WithServiceAbi, QueryContext, Service, ViewStateStorage};
 use std::sync::Arc;
-use async_graphql::{EmptySubscription, Schema};
+use async_graphql::{EmptySubscription, MergedObject, Object, Request, Schema, Variables};
+use linera_sdk::base::{Amount, ApplicationId};
 use linera_sdk::graphql::GraphQLMutationRoot;
 use thiserror::Error;
+use fungible::AccountOwner;
 use meta_fungible::OperationPP;
 
 linera_sdk::service!(MetaFungible);
@@ -27,12 +29,38 @@ impl Service for MetaFungible {
         _context: &QueryContext,
         query: Self::Query,
     ) -> Result<Self::QueryResponse, Self::Error> {
-        let schema = Schema::build(self.clone(),OperationPP::mutation_root(),EmptySubscription).finish();
+        // 状态视图和透传查询合并成一个查询根
+        let query_root = QueryRoot(self.clone(), FungibleQuery);
+        let schema = Schema::build(query_root,OperationPP::mutation_root(),EmptySubscription).finish();
         let response = schema.execute(query).await;
         Ok(response)
     }
 }
 
+#[derive(MergedObject)]
+struct QueryRoot(Arc<MetaFungible>, FungibleQuery);
+
+struct FungibleQuery;
+
+#[Object]
+impl FungibleQuery {
+    // 透传查询token对应的fungible程序中某个账户的余额
+    async fn balance(&self, token: ApplicationId, owner: AccountOwner) -> async_graphql::Result<Amount> {
+        // 使用变量传递owner，避免手动拼接GraphQL字符串
+        let request = Request::new("query($owner: AccountOwner!) { balanceOf(owner: $owner) { available } }")
+            .variables(Variables::from_json(serde_json::json!({ "owner": owner })));
+        let fungible_id = token.with_abi::<fungible::FungibleTokenAbi>();
+        let response = MetaFungible::query_application(fungible_id, &request).await?;
+        if let Some(error) = response.errors.into_iter().next() {
+            return Err(ServiceError::FungibleQueryFailed(error.message).into());
+        }
+        let data = response.data.into_json()?;
+        // balanceOf包括还没有迁移的旧账户，已经过期的锁定算作可用(注意：只返回可以转出的部分，不包括锁定的代币)
+        let balance = serde_json::from_value::<Amount>(data["balanceOf"]["available"].clone())?;
+        Ok(balance)
+    }
+}
+
 /// An error that can occur while querying the service.
 #[derive(Debug, Error)]
 pub enum ServiceError {
@@ -44,5 +72,7 @@ pub enum ServiceError {
     #[error("Invalid query argument; could not deserialize request")]
     InvalidQuery(#[from] serde_json::Error),
 
-    // Add error variants here.
+    /// The fungible application rejected the forwarded query.
+    #[error("Fungible query failed: {0}")]
+    FungibleQueryFailed(String),
 }
@@ -1,9 +1,173 @@
-use linera_sdk::views::{RegisterView, ViewStorageContext};
+use linera_sdk::base::{Amount, ApplicationId, ChainId, Owner};
+use linera_sdk::views::{LogView, MapView, RegisterView, ViewStorageContext};
 use linera_views::views::{GraphQLView, RootView};
+use thiserror::Error;
+use fungible::AccountOwner;
+use meta_fungible::{RemoteTransfer, RemoteTransferStatus, TokenOwner, TransferRecord};
 
 #[derive(RootView, GraphQLView)]
 #[view(context = "ViewStorageContext")]
 pub struct MetaFungible {
-    pub value: RegisterView<u64>,
-    // Add fields here.
+    /* 通过call_application转发过的转账记录 */
+    pub transfers: LogView<TransferRecord>,
+    /* 每个转出账户的转发次数 */
+    pub transfer_counts: MapView<AccountOwner, u64>,
+    /* 每个代币中每个转出账户的累计转发金额(注意：不同代币的金额不能相加，所以按代币区分) */
+    pub transferred_amounts: MapView<TokenOwner, Amount>,
+    /* 管理员，部署时的签名者 */
+    pub admin: RegisterView<Option<Owner>>,
+    /* 代币注册表，记录可以路由到的fungible程序ID和名称 */
+    pub tokens: MapView<ApplicationId, String>,
+    /* 金库中每个代币每个用户的份额(和存入的代币数量一比一) */
+    pub shares: MapView<TokenOwner, Amount>,
+    /* 金库中每个代币的总份额 */
+    pub total_shares: MapView<ApplicationId, Amount>,
+    /* 发给其它链的转账请求，键是请求ID */
+    pub remote_transfers: MapView<u64, RemoteTransfer>,
+    /* 下一个跨链转账请求ID */
+    pub next_request_id: RegisterView<u64>,
+    /* 订阅了代币注册表的链(注意：只在部署MetaFungible的链上记录) */
+    pub subscribers: MapView<ChainId, bool>,
+}
+
+// 定义份额不足异常
+#[derive(Clone, Copy, Debug, Error)]
+#[error("Insufficient vault shares")]
+pub struct InsufficientSharesError;
+
+#[allow(dead_code)]//该注解用于去除未使用警告
+impl MetaFungible {
+    // 记录一笔转发的转账并更新转出账户的计数
+    pub async fn record_transfer(&mut self, record: TransferRecord) {
+        let owner = record.owner;
+        let count = self.transfer_counts
+            .get(&owner)
+            .await
+            .expect("获取转发次数失败!")
+            .unwrap_or_default();
+        self.transfer_counts
+            .insert(&owner, count + 1)
+            .expect("更新转发次数失败!");
+        let key = TokenOwner { token: record.token, owner };
+        let mut total = self.transferred_amounts
+            .get(&key)
+            .await
+            .expect("获取累计转发金额失败!")
+            .unwrap_or_default();
+        total.saturating_add_assign(record.amount);
+        self.transferred_amounts
+            .insert(&key, total)
+            .expect("更新累计转发金额失败!");
+        self.transfers.push(record);
+    }
+
+    // 获取用户在金库中的份额
+    pub async fn shares_of(&self, key: &TokenOwner) -> Amount {
+        self.shares
+            .get(key)
+            .await
+            .expect("获取份额失败!")
+            .unwrap_or_default()
+    }
+
+    // 增加用户份额
+    pub async fn add_shares(&mut self, key: TokenOwner, amount: Amount) {
+        let mut shares = self.shares_of(&key).await;
+        shares.saturating_add_assign(amount);
+        self.shares.insert(&key, shares).expect("增加份额失败!");
+        let mut total = self.total_shares
+            .get(&key.token)
+            .await
+            .expect("获取总份额失败!")
+            .unwrap_or_default();
+        total.saturating_add_assign(amount);
+        self.total_shares.insert(&key.token, total).expect("更新总份额失败!");
+    }
+
+    // 扣除用户份额，份额不足时返回InsufficientSharesError
+    pub async fn remove_shares(&mut self, key: TokenOwner, amount: Amount) -> Result<(), InsufficientSharesError> {
+        let mut shares = self.shares_of(&key).await;
+        shares.try_sub_assign(amount).map_err(|_| InsufficientSharesError)?;
+        self.shares.insert(&key, shares).expect("扣除份额失败!");
+        let mut total = self.total_shares
+            .get(&key.token)
+            .await
+            .expect("获取总份额失败!")
+            .unwrap_or_default();
+        total = total.saturating_sub(amount);
+        self.total_shares.insert(&key.token, total).expect("更新总份额失败!");
+        Ok(())
+    }
+
+    // 记录一个发往其它链的转账请求，返回请求ID
+    pub async fn add_remote_transfer(&mut self, transfer: RemoteTransfer) -> u64 {
+        let request_id = *self.next_request_id.get();
+        self.next_request_id.set(request_id + 1);
+        self.remote_transfers
+            .insert(&request_id, transfer)
+            .expect("记录跨链转账请求失败!");
+        request_id
+    }
+
+    // 收到目标链的回复后更新请求状态(注意：只接受请求发往的那条链的回复)
+    pub async fn finish_remote_transfer(
+        &mut self,
+        origin_chain_id: ChainId,
+        request_id: u64,
+        status: RemoteTransferStatus,
+        reason: Option<String>,
+    ) {
+        let transfer = self.remote_transfers
+            .get(&request_id)
+            .await
+            .expect("获取跨链转账请求失败!");
+        match transfer {
+            Some(mut transfer)
+                if transfer.chain_id == origin_chain_id && transfer.status == RemoteTransferStatus::Pending =>
+            {
+                transfer.status = status;
+                transfer.reason = reason;
+                self.remote_transfers
+                    .insert(&request_id, transfer)
+                    .expect("更新跨链转账请求失败!");
+            }
+            _ => log::warn!("忽略未知的跨链转账回复 {} 来自 {}", request_id, origin_chain_id),
+        }
+    }
+
+    // 用部署链发来的完整注册表替换当前链上的注册表
+    pub async fn replace_tokens(&mut self, tokens: Vec<(ApplicationId, String)>) {
+        let registered = self.tokens.indices().await.expect("获取代币注册表失败!");
+        for token in registered {
+            self.tokens.remove(&token).expect("移除代币失败!");
+        }
+        for (token, name) in tokens {
+            self.tokens.insert(&token, name).expect("登记代币失败!");
+        }
+    }
+
+    // 当前的完整注册表
+    pub async fn registered_tokens(&self) -> Vec<(ApplicationId, String)> {
+        let mut tokens = Vec::new();
+        for token in self.tokens.indices().await.expect("获取代币注册表失败!") {
+            if let Some(name) = self.tokens.get(&token).await.expect("获取代币注册表失败!") {
+                tokens.push((token, name));
+            }
+        }
+        tokens
+    }
+
+    // 订阅了代币注册表的链
+    pub async fn subscriber_chains(&self) -> Vec<ChainId> {
+        self.subscribers.indices().await.expect("获取订阅的链失败!")
+    }
+
+    // 代币是否已经登记在注册表中
+    pub async fn is_registered(&self, token: &ApplicationId) -> bool {
+        self.tokens
+            .get(token)
+            .await
+            .expect("获取代币注册表失败!")
+            .is_some()
+    }
 }
@@ -0,0 +1,129 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::panic::AssertUnwindSafe;
+
+use futures::FutureExt;
+use linera_sdk::base::{Amount, Owner};
+use linera_sdk::test::TestValidator;
+use fungible::{Account, AccountOwner, FungibleTokenAbi, TransferLimits};
+use meta_fungible::{MetaFungibleAbi, OperationPP};
+use fungible_client::{ActiveChainTransport, FungibleClient};
+
+#[tokio::test]
+async fn test_remote_transfer_from_subscriber_chain() {
+    let initial_amount = Amount::from(1_000_000u128);
+    let transfer_amount = Amount::from(300u128);
+    // 模拟一个验证节点，当前项目就是MetaFungible
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    // 部署fungible和MetaFungible的链，代币注册表在这条链上
+    let mut home_chain = validator.new_chain().await;
+    let home_owner = AccountOwner::User(Owner::from(home_chain.public_key()));
+    // 在另一条链上发起跨链代转账
+    let remote_chain = validator.new_chain().await;
+    let remote_owner = AccountOwner::User(Owner::from(remote_chain.public_key()));
+    let receiver = AccountOwner::User(Owner::from(validator.new_chain().await.public_key()));
+
+    let fungible_bytecode_id = home_chain.publish_bytecodes_in("../fungible").await;
+    let token_id = home_chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let meta_id = home_chain
+        .create_application::<MetaFungibleAbi>(
+            bytecode_id,
+            token_id,
+            (),
+            vec![token_id.forget_abi()],
+        ).await;
+
+    // 先给远程链的用户在部署链上转一些代币
+    home_chain.add_block(|block| {
+        block.with_operation(
+            token_id,
+            fungible::OperationOpt::Transfer {
+                owner: home_owner,
+                amount: transfer_amount,
+                target_account: Account { chain_id: home_chain.id(), owner: remote_owner },
+                request_id: None,
+            },
+        );
+    }).await;
+
+    let remote_transfer = OperationPP::RemoteTransfer {
+        chain_id: home_chain.id(),
+        token: token_id.forget_abi(),
+        amount: transfer_amount,
+        target_account: Account { chain_id: home_chain.id(), owner: receiver },
+    };
+
+    // 没有订阅代币注册表之前，远程链上的注册表是空的
+    remote_chain.register_application(meta_id).await;
+    let unregistered = AssertUnwindSafe(remote_chain.add_block(|block| {
+        block.with_operation(meta_id, remote_transfer.clone());
+    })).catch_unwind().await;
+    assert!(unregistered.is_err());
+
+    // 远程链上不能修改代币注册表
+    let not_creator = AssertUnwindSafe(remote_chain.add_block(|block| {
+        block.with_operation(meta_id, OperationPP::UnregisterToken { token: token_id.forget_abi() });
+    })).catch_unwind().await;
+    assert!(not_creator.is_err());
+
+    // 订阅之后部署链把完整的注册表发过来
+    remote_chain.add_block(|block| {
+        block.with_operation(meta_id, OperationPP::SubscribeRegistry);
+    }).await;
+    home_chain.handle_received_messages().await;
+    remote_chain.handle_received_messages().await;
+
+    // 从远程链发起，由部署链上的MetaFungible代为转账
+    remote_chain.add_block(|block| {
+        block.with_operation(meta_id, remote_transfer);
+    }).await;
+    home_chain.handle_received_messages().await;
+    remote_chain.handle_received_messages().await;
+
+    let home_token = FungibleClient::new(ActiveChainTransport::new(&home_chain, token_id));
+    assert_eq!(home_token.execute(fungible_client::balance(remote_owner)).await.unwrap(), Amount::ZERO);
+    assert_eq!(home_token.execute(fungible_client::balance(receiver)).await.unwrap(), transfer_amount);
+    let value = remote_chain
+        .graphql_query(meta_id, String::from("query { remoteTransfers(u64: 0) { status } }"))
+        .await;
+    assert_eq!(value["remoteTransfers"]["status"], "COMPLETED");
+
+    // 超过部署链上的转账限额时回复失败，不会堵住部署链的收件箱
+    home_chain.add_block(|block| {
+        block
+            .with_operation(
+                token_id,
+                fungible::OperationOpt::Transfer {
+                    owner: home_owner,
+                    amount: transfer_amount,
+                    target_account: Account { chain_id: home_chain.id(), owner: remote_owner },
+                    request_id: None,
+                },
+            )
+            .with_operation(
+                token_id,
+                fungible::OperationOpt::SetLimits {
+                    chain_id: home_chain.id(),
+                    limits: TransferLimits { max_transfer: Some(Amount::from(100u128)), ..TransferLimits::default() },
+                },
+            );
+    }).await;
+    remote_chain.add_block(|block| {
+        block.with_operation(meta_id, OperationPP::RemoteTransfer {
+            chain_id: home_chain.id(),
+            token: token_id.forget_abi(),
+            amount: transfer_amount,
+            target_account: Account { chain_id: home_chain.id(), owner: receiver },
+        });
+    }).await;
+    home_chain.handle_received_messages().await;
+    remote_chain.handle_received_messages().await;
+
+    assert_eq!(home_token.execute(fungible_client::balance(remote_owner)).await.unwrap(), transfer_amount);
+    let value = remote_chain
+        .graphql_query(meta_id, String::from("query { remoteTransfers(u64: 1) { status } }"))
+        .await;
+    assert_eq!(value["remoteTransfers"]["status"], "FAILED");
+}
@@ -0,0 +1,97 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::panic::AssertUnwindSafe;
+
+use async_graphql::InputType;
+use futures::FutureExt;
+use linera_sdk::base::{Amount, ApplicationId, Owner};
+use linera_sdk::test::{ActiveChain, TestValidator};
+use fungible::{Account, AccountOwner, FungibleTokenAbi};
+use meta_fungible::{MetaFungibleAbi, OperationPP, TokenOwner};
+use fungible_client::{ActiveChainTransport, FungibleClient};
+
+#[tokio::test]
+async fn test_vault_deposit_and_withdraw() {
+    // 合约部署时账户初始余额
+    let initial_amount = Amount::from(1_000_000u128);
+    let deposit_amount = Amount::from(300_000u128);
+    let withdraw_amount = Amount::from(100_000u128);
+    // 模拟一个验证节点，当前项目就是MetaFungible
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    let mut chain = validator.new_chain().await;
+    let user = AccountOwner::User(Owner::from(chain.public_key()));
+    // 在同一条链上部署fungible程序和MetaFungible程序
+    let fungible_bytecode_id = chain.publish_bytecodes_in("../fungible").await;
+    let token_id = chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let meta_id = chain
+        .create_application::<MetaFungibleAbi>(
+            bytecode_id,
+            token_id,
+            (),
+            vec![token_id.forget_abi()],
+        ).await;
+    let vault = AccountOwner::Application(meta_id.forget_abi());
+    let shares_key = TokenOwner { token: token_id.forget_abi(), owner: user };
+
+    // 存入金库
+    chain.add_block(|block| {
+        block.with_operation(
+            meta_id,
+            OperationPP::Deposit { token: token_id.forget_abi(), amount: deposit_amount },
+        );
+    }).await;
+    let token_client = FungibleClient::new(ActiveChainTransport::new(&chain, token_id));
+    assert_eq!(token_client.execute(fungible_client::balance(user)).await.unwrap(), initial_amount.saturating_sub(deposit_amount));
+    assert_eq!(token_client.execute(fungible_client::balance(vault)).await.unwrap(), deposit_amount);
+    assert_eq!(query_shares(meta_id, &chain, shares_key).await, deposit_amount);
+
+    // 从金库取出一部分
+    chain.add_block(|block| {
+        block.with_operation(
+            meta_id,
+            OperationPP::Withdraw {
+                token: token_id.forget_abi(),
+                amount: withdraw_amount,
+                target_account: Account { chain_id: chain.id(), owner: user },
+            },
+        );
+    }).await;
+    let remaining = deposit_amount.saturating_sub(withdraw_amount);
+    assert_eq!(
+        token_client.execute(fungible_client::balance(user)).await.unwrap(),
+        initial_amount.saturating_sub(remaining)
+    );
+    assert_eq!(token_client.execute(fungible_client::balance(vault)).await.unwrap(), remaining);
+    assert_eq!(query_shares(meta_id, &chain, shares_key).await, remaining);
+
+    // 取出超过份额的数量，区块应该被拒绝
+    let over_withdraw = AssertUnwindSafe(chain.add_block(|block| {
+        block.with_operation(
+            meta_id,
+            OperationPP::Withdraw {
+                token: token_id.forget_abi(),
+                amount: deposit_amount,
+                target_account: Account { chain_id: chain.id(), owner: user },
+            },
+        );
+    })).catch_unwind().await;
+    assert!(over_withdraw.is_err());
+    // 余额和份额都没有变化
+    assert_eq!(token_client.execute(fungible_client::balance(vault)).await.unwrap(), remaining);
+    assert_eq!(query_shares(meta_id, &chain, shares_key).await, remaining);
+}
+
+async fn query_shares(
+    application_id: ApplicationId<MetaFungibleAbi>,
+    chain: &ActiveChain,
+    key: TokenOwner,
+) -> Amount {
+    let query = format!(
+        "query {{ shares(tokenOwner: {}) }}",
+        InputType::to_value(&key)
+    );
+    let value = chain.graphql_query(application_id, query).await;
+    value["shares"].as_str().map(|shares| shares.parse().unwrap()).unwrap_or_default()
+}
@@ -0,0 +1,185 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+use async_trait::async_trait;
+use linera_sdk::{
+    ApplicationCallResult,
+    base::{SessionId, WithContractAbi}, CalleeContext, Contract, ExecutionResult, MessageContext,
+    OperationContext, SessionCallResult, ViewStateStorage,
+};
+use linera_sdk::base::{Amount, ChainId, Owner};
+use linera_sdk::contract::system_api;
+use thiserror::Error;
+
+use fungible::{Account, AccountOwner};
+use multisig::{MessageOpt, OperationOpt, Proposal, ProposalStatus};
+
+use self::state::{Multisig, MultisigError};
+
+mod state;
+/*
+多签合约入口文件
+*/
+
+linera_sdk::contract!(Multisig);
+
+impl WithContractAbi for Multisig {
+    type Abi = multisig::MultisigAbi;
+}
+
+#[async_trait]
+impl Contract for Multisig {
+    type Error = ContractError;
+    type Storage = ViewStateStorage<Self>;
+
+    // 合约部署时调用，保存多签规则
+    async fn initialize(
+        &mut self,
+        _context: &OperationContext,
+        mut argument: Self::InitializationArgument,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        argument.owners.sort();
+        argument.owners.dedup();
+        if argument.threshold == 0 || argument.threshold as usize > argument.owners.len() {
+            return Err(ContractError::InvalidThreshold);
+        }
+        self.config.set(argument);
+        Ok(ExecutionResult::default())
+    }
+
+    // 前端调用拦截转发
+    async fn execute_operation(
+        &mut self,
+        context: &OperationContext,
+        operation: Self::Operation,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        let signer = context.authenticated_signer.ok_or(ContractError::MissingSigner)?;
+        let home_chain_id = Self::home_chain_id();
+        let on_home_chain = system_api::current_chain_id() == home_chain_id;
+        match operation {
+            OperationOpt::Propose {amount,target_account} => {
+                if amount == Amount::default() {
+                    return Err(ContractError::ZeroAmount);
+                }
+                if !on_home_chain {
+                    let message = MessageOpt::Propose {amount,target_account};
+                    return Ok(ExecutionResult::default().with_authenticated_message(home_chain_id,message));
+                }
+                let proposal_id = self.propose(Self::new_proposal(signer,amount,target_account))?;
+                log::debug!("{} 提议 {}",signer,proposal_id);
+            }
+            OperationOpt::Approve {proposal_id} => {
+                if !on_home_chain {
+                    let message = MessageOpt::Approve {proposal_id};
+                    return Ok(ExecutionResult::default().with_authenticated_message(home_chain_id,message));
+                }
+                self.approve(proposal_id,signer).await?;
+            }
+            OperationOpt::Execute {proposal_id} => {
+                if !on_home_chain {
+                    return Err(ContractError::NotHomeChain);
+                }
+                self.check_owner(&signer)?;
+                let proposal = self.execute(proposal_id).await?;
+                // 从金库转账(注意：目标账户在其它链上时fungible程序会发消息入账)
+                let call = fungible::ApplicationCallOpt::Transfer {
+                    owner: AccountOwner::Application(system_api::current_application_id()),
+                    amount: proposal.amount,
+                    target_account: proposal.target_account,
+                    request_id: None,
+                };
+                self.call_application(true,Self::parameters()?,&call,vec![]).await?;
+            }
+        }
+        Ok(ExecutionResult::default())
+    }
+
+    // 跨链相互调用
+    async fn execute_message(
+        &mut self,
+        context: &MessageContext,
+        message: Self::Message,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        let signer = context.authenticated_signer.ok_or(ContractError::MissingSigner)?;
+        // 消息执行失败会阻塞整个收件箱，所以无效的提议和批准只记录日志然后忽略
+        let result = match message {
+            MessageOpt::Propose {amount,target_account} => {
+                self.propose(Self::new_proposal(signer,amount,target_account)).map(|_| ())
+            }
+            MessageOpt::Approve {proposal_id} => self.approve(proposal_id,signer).await,
+        };
+        if let Err(error) = result {
+            log::warn!("忽略 {} 的多签消息: {}",signer,error);
+        }
+        Ok(ExecutionResult::default())
+    }
+
+    async fn handle_application_call(
+        &mut self,
+        _context: &CalleeContext,
+        _call: Self::ApplicationCall,
+        _forwarded_sessions: Vec<SessionId>,
+    ) -> Result<ApplicationCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        Err(ContractError::ApplicationCallNotSupported)
+    }
+
+    async fn handle_session_call(
+        &mut self,
+        _context: &CalleeContext,
+        _session: Self::SessionState,
+        _call: Self::SessionCall,
+        _forwarded_sessions: Vec<SessionId>,
+    ) -> Result<SessionCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        Err(ContractError::SessionNotSupported)
+    }
+}
+
+impl Multisig {
+    // 部署合约的链，金库和提议都在这条链上
+    fn home_chain_id() -> ChainId {
+        system_api::current_application_id().creation.chain_id
+    }
+
+    fn new_proposal(proposer: Owner, amount: Amount, target_account: Account) -> Proposal {
+        Proposal {
+            proposer,
+            amount,
+            target_account,
+            approvals: Vec::new(),
+            status: ProposalStatus::Pending,
+        }
+    }
+}
+
+// 自定义一些合约错误
+#[derive(Debug, Error)]
+pub enum ContractError {
+    #[error("Failed to deserialize BCS bytes")]
+    BcsError(#[from] bcs::Error),
+
+    #[error("Failed to deserialize JSON string")]
+    JsonError(#[from] serde_json::Error),
+
+    // 阈值必须在1到成员数量之间
+    #[error("Threshold must be between 1 and the number of owners")]
+    InvalidThreshold,
+
+    #[error("Operation requires an authenticated signer")]
+    MissingSigner,
+
+    #[error("Amount must be greater than zero")]
+    ZeroAmount,
+
+    // 执行只能在部署合约的链上进行
+    #[error("Operation must be executed on the multisig home chain")]
+    NotHomeChain,
+
+    // 如果碰到MultisigError错误会自动转成Multisig错误
+    #[error(transparent)]
+    Multisig(#[from] MultisigError),
+
+    #[error("Application calls not supported")]
+    ApplicationCallNotSupported,
+
+    #[error("Session not supported")]
+    SessionNotSupported,
+}
@@ -0,0 +1,92 @@
+use async_graphql::{Enum, Request, Response, SimpleObject};
+use linera_sdk::base::{Amount, ApplicationId, ContractAbi, Owner, ServiceAbi};
+use linera_sdk::graphql::GraphQLMutationRoot;
+use serde::{Deserialize, Serialize};
+use fungible::{Account, FungibleTokenAbi};
+
+/*
+多签金库上下文相关定义文件
+*/
+
+pub struct MultisigAbi;
+
+// 合约写入相关Abi
+impl ContractAbi for MultisigAbi {
+    // 参数是金库管理的fungible程序ID
+    type Parameters = ApplicationId<FungibleTokenAbi>;
+    // 合约部署时的参数
+    type InitializationArgument = MultisigConfig;
+    type Operation = OperationOpt;
+    type Message = MessageOpt;
+    type ApplicationCall = ();
+    type SessionCall = ();
+    type SessionState = ();
+    type Response = ();
+}
+
+// 合约查询相关Abi
+impl ServiceAbi for MultisigAbi {
+    type Parameters = ApplicationId<FungibleTokenAbi>;
+    type Query = Request;
+    type QueryResponse = Response;
+}
+
+// 多签规则：owners中至少threshold个人批准的转账才能执行
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SimpleObject)]
+pub struct MultisigConfig {
+    pub owners: Vec<Owner>,
+    pub threshold: u32,
+}
+
+/*
+操作枚举(注意：金库是多签程序在部署合约的链上的程序账户，提议和批准可以在任何链上进行，
+在其它链上会把签名的消息发到部署合约的链；执行只能在部署合约的链上进行)
+*/
+#[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
+pub enum OperationOpt {
+    // 提议从金库转账，提议人自动批准
+    Propose {
+        amount: Amount,
+        target_account: Account
+    },
+    // 批准提议
+    Approve {
+        proposal_id: u64
+    },
+    // 批准数量达到阈值后执行转账
+    Execute {
+        proposal_id: u64
+    }
+}
+
+// 消息枚举(注意：这些消息都是签名发送的，签名者就是提议人或批准人)
+#[derive(Debug, Deserialize, Serialize)]
+pub enum MessageOpt {
+    Propose {
+        amount: Amount,
+        target_account: Account
+    },
+    Approve {
+        proposal_id: u64
+    }
+}
+
+// 提议状态
+#[derive(Clone, Copy, Debug, Deserialize, Enum, Eq, PartialEq, Serialize)]
+pub enum ProposalStatus {
+    // 等待批准或者等待执行
+    Pending,
+    // 已经执行
+    Executed,
+}
+
+// 转账提议
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct Proposal {
+    pub proposer: Owner,
+    pub amount: Amount,
+    pub target_account: Account,
+    // 已经批准的成员
+    pub approvals: Vec<Owner>,
+    pub status: ProposalStatus,
+}
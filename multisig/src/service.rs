@@ -0,0 +1,75 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+mod state;
+
+use self::state::Multisig;
+use async_trait::async_trait;
+use linera_sdk::{base::WithServiceAbi, QueryContext, Service, ViewStateStorage};
+use std::sync::Arc;
+use async_graphql::{EmptySubscription, MergedObject, Object, Response, Schema, SimpleObject};
+use linera_sdk::graphql::GraphQLMutationRoot;
+use thiserror::Error;
+use multisig::{OperationOpt, Proposal};
+
+/*
+查询服务相关实现(就是定义一些只读的数据给前端调用)
+*/
+
+linera_sdk::service!(Multisig);
+
+impl WithServiceAbi for Multisig {
+    type Abi = multisig::MultisigAbi;
+}
+
+#[async_trait]
+impl Service for Multisig {
+    type Error = ServiceError;
+    type Storage = ViewStateStorage<Self>;
+
+    async fn handle_query(
+        self: Arc<Self>,
+        _context: &QueryContext,
+        request: Self::Query,
+    ) -> Result<Response, Self::Error> {
+        let query_root = QueryRoot(self.clone(), ProposalQuery { state: self.clone() });
+        let schema = Schema::build(query_root,OperationOpt::mutation_root(),EmptySubscription).finish();
+        let response = schema.execute(request).await;
+        Ok(response)
+    }
+}
+
+#[derive(MergedObject)]
+struct QueryRoot(Arc<Multisig>, ProposalQuery);
+
+struct ProposalQuery {
+    state: Arc<Multisig>,
+}
+
+// 带ID的提议
+#[derive(SimpleObject)]
+struct PendingProposal {
+    proposal_id: u64,
+    proposal: Proposal,
+}
+
+#[Object]
+impl ProposalQuery {
+    // 所有等待批准或执行的提议
+    async fn pending_proposals(&self) -> Vec<PendingProposal> {
+        self.state
+            .pending_proposals()
+            .await
+            .into_iter()
+            .map(|(proposal_id, proposal)| PendingProposal { proposal_id, proposal })
+            .collect()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ServiceError {
+    #[error("Queries not supported by application")]
+    QueriesNotSupported,
+
+    #[error("Invalid query argument; could not deserialize request")]
+    InvalidQuery(#[from] serde_json::Error),
+}
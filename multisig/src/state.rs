@@ -0,0 +1,118 @@
+use linera_sdk::base::Owner;
+use linera_sdk::views::{MapView, RegisterView, ViewStorageContext};
+use linera_views::views::{GraphQLView, RootView};
+use thiserror::Error;
+use multisig::{MultisigConfig, Proposal, ProposalStatus};
+
+/*
+状态文件定义数据结构以及应用核心逻辑实现(注意：状态只在部署合约的链上有意义)
+*/
+
+#[derive(RootView, GraphQLView)]
+#[view(context = "ViewStorageContext")]
+pub struct Multisig {
+    /* 多签规则 */
+    pub config: RegisterView<MultisigConfig>,
+    /* 所有提议，键是提议ID */
+    pub proposals: MapView<u64, Proposal>,
+    /* 下一个提议ID */
+    pub next_proposal_id: RegisterView<u64>,
+}
+
+// 定义多签异常
+#[derive(Clone, Copy, Debug, Error)]
+pub enum MultisigError {
+    #[error("Signer is not a multisig owner")]
+    NotOwner,
+
+    #[error("Proposal not found")]
+    ProposalNotFound,
+
+    #[error("Proposal has already been executed")]
+    AlreadyExecuted,
+
+    #[error("Owner has already approved the proposal")]
+    AlreadyApproved,
+
+    #[error("Proposal does not have enough approvals")]
+    NotEnoughApprovals,
+}
+
+#[allow(dead_code)]//该注解用于去除未使用警告
+impl Multisig {
+    pub fn check_owner(&self, owner: &Owner) -> Result<(), MultisigError> {
+        if self.config.get().owners.contains(owner) {
+            Ok(())
+        } else {
+            Err(MultisigError::NotOwner)
+        }
+    }
+
+    // 获取提议
+    pub async fn proposal(&self, proposal_id: u64) -> Result<Proposal, MultisigError> {
+        self.proposals
+            .get(&proposal_id)
+            .await
+            .expect("获取提议失败!")
+            .ok_or(MultisigError::ProposalNotFound)
+    }
+
+    // 添加提议，提议人自动批准，返回提议ID
+    pub fn propose(&mut self, mut proposal: Proposal) -> Result<u64, MultisigError> {
+        self.check_owner(&proposal.proposer)?;
+        proposal.approvals = vec![proposal.proposer];
+        let proposal_id = *self.next_proposal_id.get();
+        self.next_proposal_id.set(proposal_id + 1);
+        self.proposals
+            .insert(&proposal_id, proposal)
+            .expect("记录提议失败!");
+        Ok(proposal_id)
+    }
+
+    // 批准提议
+    pub async fn approve(&mut self, proposal_id: u64, owner: Owner) -> Result<(), MultisigError> {
+        self.check_owner(&owner)?;
+        let mut proposal = self.pending_proposal(proposal_id).await?;
+        if proposal.approvals.contains(&owner) {
+            return Err(MultisigError::AlreadyApproved);
+        }
+        proposal.approvals.push(owner);
+        self.proposals
+            .insert(&proposal_id, proposal)
+            .expect("记录提议失败!");
+        Ok(())
+    }
+
+    // 标记提议已执行，返回要执行的提议(注意：批准数量要达到阈值)
+    pub async fn execute(&mut self, proposal_id: u64) -> Result<Proposal, MultisigError> {
+        let mut proposal = self.pending_proposal(proposal_id).await?;
+        if proposal.approvals.len() < self.config.get().threshold as usize {
+            return Err(MultisigError::NotEnoughApprovals);
+        }
+        proposal.status = ProposalStatus::Executed;
+        self.proposals
+            .insert(&proposal_id, proposal.clone())
+            .expect("记录提议失败!");
+        Ok(proposal)
+    }
+
+    // 所有等待批准或执行的提议
+    pub async fn pending_proposals(&self) -> Vec<(u64, Proposal)> {
+        let mut pending = Vec::new();
+        for proposal_id in self.proposals.indices().await.expect("获取提议失败!") {
+            let proposal = self.proposal(proposal_id).await.expect("获取提议失败!");
+            if proposal.status == ProposalStatus::Pending {
+                pending.push((proposal_id, proposal));
+            }
+        }
+        pending
+    }
+
+    async fn pending_proposal(&self, proposal_id: u64) -> Result<Proposal, MultisigError> {
+        let proposal = self.proposal(proposal_id).await?;
+        if proposal.status != ProposalStatus::Pending {
+            return Err(MultisigError::AlreadyExecuted);
+        }
+        Ok(proposal)
+    }
+}
@@ -0,0 +1,88 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::panic::AssertUnwindSafe;
+
+use futures::FutureExt;
+use linera_sdk::base::{Amount, Owner};
+use linera_sdk::test::TestValidator;
+use fungible::{Account, AccountOwner, FungibleTokenAbi};
+use multisig::{MultisigAbi, MultisigConfig, OperationOpt};
+use fungible_client::{ActiveChainTransport, FungibleClient};
+
+#[tokio::test]
+async fn test_remote_approval_unlocks_execution() {
+    let initial_amount = Amount::from(1_000_000u128);
+    let treasury_amount = Amount::from(1_000u128);
+    let transfer_amount = Amount::from(300u128);
+    // 模拟一个验证节点，当前项目就是多签程序
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    // 部署合约的链，金库在这条链上
+    let mut home_chain = validator.new_chain().await;
+    let home_owner = Owner::from(home_chain.public_key());
+    // 另一个成员在另一条链上
+    let member_chain = validator.new_chain().await;
+    let member = Owner::from(member_chain.public_key());
+
+    let fungible_bytecode_id = home_chain.publish_bytecodes_in("../fungible").await;
+    let token_id = home_chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let multisig_id = home_chain
+        .create_application::<MultisigAbi>(
+            bytecode_id,
+            token_id,
+            MultisigConfig { owners: vec![home_owner, member], threshold: 2 },
+            vec![token_id.forget_abi()],
+        ).await;
+    let treasury = AccountOwner::Application(multisig_id.forget_abi());
+
+    // 给金库充值，然后提议给另一个成员转账
+    home_chain.add_block(|block| {
+        block
+            .with_operation(
+                token_id,
+                fungible::OperationOpt::Transfer {
+                    owner: AccountOwner::User(home_owner),
+                    amount: treasury_amount,
+                    target_account: Account { chain_id: home_chain.id(), owner: treasury },
+                    request_id: None,
+                },
+            )
+            .with_operation(
+                multisig_id,
+                OperationOpt::Propose {
+                    amount: transfer_amount,
+                    target_account: Account { chain_id: member_chain.id(), owner: AccountOwner::User(member) },
+                },
+            );
+    }).await;
+
+    // 只有一个批准时不能执行
+    let result = AssertUnwindSafe(home_chain.add_block(|block| {
+        block.with_operation(multisig_id, OperationOpt::Execute { proposal_id: 0 });
+    })).catch_unwind().await;
+    assert!(result.is_err());
+
+    // 另一个成员在自己的链上批准
+    member_chain.register_application(multisig_id).await;
+    member_chain.add_block(|block| {
+        block.with_operation(multisig_id, OperationOpt::Approve { proposal_id: 0 });
+    }).await;
+    home_chain.handle_received_messages().await;
+    let value = home_chain
+        .graphql_query(multisig_id, String::from("query { pendingProposals { proposalId proposal { approvals } } }"))
+        .await;
+    assert_eq!(value["pendingProposals"][0]["proposal"]["approvals"].as_array().unwrap().len(), 2);
+
+    home_chain.add_block(|block| {
+        block.with_operation(multisig_id, OperationOpt::Execute { proposal_id: 0 });
+    }).await;
+    member_chain.handle_received_messages().await;
+
+    let home_token = FungibleClient::new(ActiveChainTransport::new(&home_chain, token_id));
+    assert_eq!(home_token.execute(fungible_client::balance(treasury)).await.unwrap(), treasury_amount.saturating_sub(transfer_amount));
+    let member_token = FungibleClient::new(ActiveChainTransport::new(&member_chain, token_id));
+    assert_eq!(member_token.execute(fungible_client::balance(AccountOwner::User(member))).await.unwrap(), transfer_amount);
+    let value = home_chain.graphql_query(multisig_id, String::from("query { pendingProposals { proposalId } }")).await;
+    assert!(value["pendingProposals"].as_array().unwrap().is_empty());
+}
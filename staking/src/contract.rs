@@ -0,0 +1,185 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+use async_trait::async_trait;
+use linera_sdk::{
+    ApplicationCallResult,
+    base::{SessionId, WithContractAbi}, CalleeContext, Contract, ExecutionResult, MessageContext,
+    OperationContext, SessionCallResult, ViewStateStorage,
+};
+use linera_sdk::base::{Amount, ApplicationId, ChainId, Owner};
+use linera_sdk::contract::system_api;
+use thiserror::Error;
+
+use fungible::{Account, AccountOwner, FungibleTokenAbi};
+use staking::OperationOpt;
+
+use self::state::{Staking, StakingError};
+
+mod state;
+/*
+质押合约入口文件
+*/
+
+linera_sdk::contract!(Staking);
+
+impl WithContractAbi for Staking {
+    type Abi = staking::StakingAbi;
+}
+
+#[async_trait]
+impl Contract for Staking {
+    type Error = ContractError;
+    type Storage = ViewStateStorage<Self>;
+
+    // 合约部署时调用，保存质押规则
+    async fn initialize(
+        &mut self,
+        _context: &OperationContext,
+        argument: Self::InitializationArgument,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        self.config.set(argument);
+        self.last_update.set(system_api::current_system_time());
+        Ok(ExecutionResult::default())
+    }
+
+    // 前端调用拦截转发
+    async fn execute_operation(
+        &mut self,
+        context: &OperationContext,
+        operation: Self::Operation,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        let signer = context.authenticated_signer.ok_or(ContractError::MissingSigner)?;
+        // 质押的代币账户在部署合约的链上
+        if system_api::current_chain_id() != Self::home_chain_id() {
+            return Err(ContractError::NotHomeChain);
+        }
+        let parameters = Self::parameters()?;
+        let now = system_api::current_system_time();
+        match operation {
+            OperationOpt::Stake {amount} => {
+                self.stake(signer,amount,now).await?;
+                self.pull(parameters.staking_token,signer,amount).await?;
+            }
+            OperationOpt::Unstake {amount} => {
+                let released = self.unstake(signer,amount,now).await?;
+                self.pay(parameters.staking_token,signer,released).await?;
+            }
+            OperationOpt::Withdraw => {
+                let released = self.withdraw(signer,now).await;
+                self.pay(parameters.staking_token,signer,released).await?;
+            }
+            OperationOpt::ClaimRewards => {
+                let rewards = self.claim_rewards(signer,now).await;
+                log::debug!("{} 领取奖励 {}",signer,rewards);
+                self.pay(parameters.reward_token,signer,rewards).await?;
+            }
+            OperationOpt::FundRewards {amount} => {
+                if amount == Amount::default() {
+                    return Err(StakingError::ZeroAmount.into());
+                }
+                self.fund_rewards(amount,now);
+                self.pull(parameters.reward_token,signer,amount).await?;
+            }
+        }
+        Ok(ExecutionResult::default())
+    }
+
+    async fn execute_message(
+        &mut self,
+        _context: &MessageContext,
+        _message: Self::Message,
+    ) -> Result<ExecutionResult<Self::Message>, Self::Error> {
+        Err(ContractError::MessagesNotSupported)
+    }
+
+    async fn handle_application_call(
+        &mut self,
+        _context: &CalleeContext,
+        _call: Self::ApplicationCall,
+        _forwarded_sessions: Vec<SessionId>,
+    ) -> Result<ApplicationCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        Err(ContractError::ApplicationCallNotSupported)
+    }
+
+    async fn handle_session_call(
+        &mut self,
+        _context: &CalleeContext,
+        _session: Self::SessionState,
+        _call: Self::SessionCall,
+        _forwarded_sessions: Vec<SessionId>,
+    ) -> Result<SessionCallResult<Self::Message, Self::Response, Self::SessionState>, Self::Error> {
+        Err(ContractError::SessionNotSupported)
+    }
+}
+
+impl Staking {
+    // 部署合约的链，质押的代币都在这条链上
+    fn home_chain_id() -> ChainId {
+        system_api::current_application_id().creation.chain_id
+    }
+
+    // 质押程序在fungible程序中的账户
+    fn staking_account() -> Account {
+        Account {
+            chain_id: system_api::current_chain_id(),
+            owner: AccountOwner::Application(system_api::current_application_id()),
+        }
+    }
+
+    // 从签名者的账户转入质押程序账户
+    async fn pull(&mut self, token: ApplicationId<FungibleTokenAbi>, owner: Owner, amount: Amount) -> Result<(), ContractError> {
+        let call = fungible::ApplicationCallOpt::Transfer {
+            owner: AccountOwner::User(owner),
+            amount,
+            target_account: Self::staking_account(),
+            request_id: None,
+        };
+        self.call_application(true,token,&call,vec![]).await?;
+        Ok(())
+    }
+
+    // 从质押程序账户转给签名者
+    async fn pay(&mut self, token: ApplicationId<FungibleTokenAbi>, owner: Owner, amount: Amount) -> Result<(), ContractError> {
+        if amount == Amount::default() {
+            return Ok(());
+        }
+        let call = fungible::ApplicationCallOpt::Transfer {
+            owner: Self::staking_account().owner,
+            amount,
+            target_account: Account { chain_id: system_api::current_chain_id(), owner: AccountOwner::User(owner) },
+            request_id: None,
+        };
+        self.call_application(true,token,&call,vec![]).await?;
+        Ok(())
+    }
+}
+
+// 自定义一些合约错误
+#[derive(Debug, Error)]
+pub enum ContractError {
+    #[error("Failed to deserialize BCS bytes")]
+    BcsError(#[from] bcs::Error),
+
+    #[error("Failed to deserialize JSON string")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Operation requires an authenticated signer")]
+    MissingSigner,
+
+    // 所有操作只能在部署合约的链上进行
+    #[error("Operation must be executed on the staking home chain")]
+    NotHomeChain,
+
+    // 如果碰到StakingError错误会自动转成Staking错误
+    #[error(transparent)]
+    Staking(#[from] StakingError),
+
+    #[error("Messages not supported")]
+    MessagesNotSupported,
+
+    #[error("Application calls not supported")]
+    ApplicationCallNotSupported,
+
+    #[error("Session not supported")]
+    SessionNotSupported,
+}
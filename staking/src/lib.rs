@@ -0,0 +1,91 @@
+use async_graphql::{Request, Response, SimpleObject};
+use linera_sdk::base::{Amount, ApplicationId, ContractAbi, ServiceAbi, Timestamp};
+use linera_sdk::graphql::GraphQLMutationRoot;
+use serde::{Deserialize, Serialize};
+use fungible::FungibleTokenAbi;
+
+/*
+质押奖励程序上下文相关定义文件
+*/
+
+pub struct StakingAbi;
+
+// 合约写入相关Abi
+impl ContractAbi for StakingAbi {
+    // 参数是质押的fungible程序ID和奖励的fungible程序ID(可以是同一个)
+    type Parameters = StakingParameters;
+    // 合约部署时的参数
+    type InitializationArgument = StakingConfig;
+    type Operation = OperationOpt;
+    type Message = ();
+    type ApplicationCall = ();
+    type SessionCall = ();
+    type SessionState = ();
+    type Response = ();
+}
+
+// 合约查询相关Abi
+impl ServiceAbi for StakingAbi {
+    type Parameters = StakingParameters;
+    type Query = Request;
+    type QueryResponse = Response;
+}
+
+// 奖励累加器的放大倍数
+pub const REWARD_SCALE: u128 = 1_000_000_000_000_000_000;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct StakingParameters {
+    pub staking_token: ApplicationId<FungibleTokenAbi>,
+    pub reward_token: ApplicationId<FungibleTokenAbi>,
+}
+
+// 质押规则
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, SimpleObject)]
+pub struct StakingConfig {
+    // 每秒发放给所有质押者的奖励总量(按质押数量比例分配)
+    pub reward_rate: Amount,
+    // 解除质押后要等多久才能取回(微秒)，0表示立即取回
+    pub unbonding_period_micros: u64,
+}
+
+// 操作枚举(注意：质押的代币都在部署合约的链上，所有操作只能在这条链上进行)
+#[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
+pub enum OperationOpt {
+    // 质押：把签名者的代币转到质押程序账户
+    Stake {
+        amount: Amount
+    },
+    // 解除质押：没有解绑期时立即取回，否则要等解绑期结束后调用Withdraw
+    Unstake {
+        amount: Amount
+    },
+    // 取回解绑期已经结束的代币
+    Withdraw,
+    // 领取奖励
+    ClaimRewards,
+    // 往奖励池里充值奖励代币(任何人都可以充值)
+    FundRewards {
+        amount: Amount
+    }
+}
+
+// 解绑中的代币
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct Unbonding {
+    pub amount: Amount,
+    // 可以取回的时间
+    pub release_time: Timestamp,
+}
+
+// 每个用户的质押信息
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SimpleObject)]
+pub struct StakeInfo {
+    pub staked: Amount,
+    // 上次结算奖励时的每份额累计奖励(放大了REWARD_SCALE倍)
+    pub reward_per_token_paid: Amount,
+    // 已经结算还没领取的奖励
+    pub rewards: Amount,
+    // 解绑中的代币
+    pub unbonding: Vec<Unbonding>,
+}
@@ -0,0 +1,73 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+mod state;
+
+use self::state::Staking;
+use async_trait::async_trait;
+use linera_sdk::{base::WithServiceAbi, QueryContext, Service, ViewStateStorage};
+use std::sync::Arc;
+use async_graphql::{EmptySubscription, MergedObject, Object, Response, Schema};
+use linera_sdk::base::{Amount, Owner, Timestamp};
+use linera_sdk::graphql::GraphQLMutationRoot;
+use linera_sdk::service::system_api;
+use thiserror::Error;
+use staking::OperationOpt;
+
+/*
+查询服务相关实现(就是定义一些只读的数据给前端调用)
+*/
+
+linera_sdk::service!(Staking);
+
+impl WithServiceAbi for Staking {
+    type Abi = staking::StakingAbi;
+}
+
+#[async_trait]
+impl Service for Staking {
+    type Error = ServiceError;
+    type Storage = ViewStateStorage<Self>;
+
+    async fn handle_query(
+        self: Arc<Self>,
+        _context: &QueryContext,
+        request: Self::Query,
+    ) -> Result<Response, Self::Error> {
+        let now = system_api::current_system_time();
+        let query_root = QueryRoot(self.clone(), RewardQuery { state: self.clone(), now });
+        let schema = Schema::build(query_root,OperationOpt::mutation_root(),EmptySubscription).finish();
+        let response = schema.execute(request).await;
+        Ok(response)
+    }
+}
+
+#[derive(MergedObject)]
+struct QueryRoot(Arc<Staking>, RewardQuery);
+
+// 按当前时间计算的查询(注意：状态中的累加器只在质押数量变化时更新，这里会先算到当前时间)
+struct RewardQuery {
+    state: Arc<Staking>,
+    now: Timestamp,
+}
+
+#[Object]
+impl RewardQuery {
+    // 用户待领取的奖励
+    async fn pending_rewards(&self, owner: Owner) -> Amount {
+        self.state.pending_rewards(&owner, self.now).await
+    }
+
+    // 奖励池中还没发放的奖励
+    async fn remaining_rewards(&self) -> Amount {
+        self.state.accrued(self.now).1
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ServiceError {
+    #[error("Queries not supported by application")]
+    QueriesNotSupported,
+
+    #[error("Invalid query argument; could not deserialize request")]
+    InvalidQuery(#[from] serde_json::Error),
+}
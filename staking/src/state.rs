@@ -0,0 +1,176 @@
+use linera_sdk::base::{Amount, Owner, Timestamp};
+use linera_sdk::views::{MapView, RegisterView, ViewStorageContext};
+use linera_views::views::{GraphQLView, RootView};
+use thiserror::Error;
+use fixed_math::mul_div;
+use staking::{REWARD_SCALE, StakeInfo, StakingConfig, Unbonding};
+
+/*
+状态文件定义数据结构以及应用核心逻辑实现(注意：状态只在部署合约的链上有意义)
+奖励按每份额累计奖励(reward per token)计算：每次质押数量变化前先把累加器更新到当前时间，
+用户的奖励 = 质押数量 * (当前累加器 - 上次结算时的累加器)
+*/
+
+#[derive(RootView, GraphQLView)]
+#[view(context = "ViewStorageContext")]
+pub struct Staking {
+    /* 质押规则 */
+    pub config: RegisterView<StakingConfig>,
+    /* 质押总量 */
+    pub total_staked: RegisterView<Amount>,
+    /* 奖励池中还没发放的奖励 */
+    pub reward_pool: RegisterView<Amount>,
+    /* 每份额累计奖励(放大了REWARD_SCALE倍) */
+    pub reward_per_token: RegisterView<Amount>,
+    /* 累加器上次更新的时间 */
+    pub last_update: RegisterView<Timestamp>,
+    /* 每个用户的质押信息 */
+    pub stakes: MapView<Owner, StakeInfo>,
+}
+
+// 定义质押异常
+#[derive(Clone, Copy, Debug, Error)]
+pub enum StakingError {
+    #[error("Amount must be greater than zero")]
+    ZeroAmount,
+
+    #[error("Insufficient staked amount")]
+    InsufficientStake,
+}
+
+#[allow(dead_code)]//该注解用于去除未使用警告
+impl Staking {
+    // 获取用户的质押信息
+    pub async fn stake_info(&self, owner: &Owner) -> StakeInfo {
+        self.stakes
+            .get(owner)
+            .await
+            .expect("获取质押信息失败!")
+            .unwrap_or_default()
+    }
+
+    // 计算到now为止的累加器和奖励池余额(不写入状态，查询服务也用这个计算待领取奖励)
+    pub fn accrued(&self, now: Timestamp) -> (Amount, Amount) {
+        let reward_per_token = *self.reward_per_token.get();
+        let reward_pool = *self.reward_pool.get();
+        let total_staked = u128::from(*self.total_staked.get());
+        let elapsed = now.micros().saturating_sub(self.last_update.get().micros());
+        if total_staked == 0 || elapsed == 0 || reward_pool == Amount::default() {
+            return (reward_per_token, reward_pool);
+        }
+        // 按微秒计算，奖励池不够时只发放剩下的部分
+        let emitted = mul_div(u128::from(self.config.get().reward_rate), u128::from(elapsed), 1_000_000)
+            .unwrap_or(u128::MAX)
+            .min(u128::from(reward_pool));
+        let increase = mul_div(emitted, REWARD_SCALE, total_staked).unwrap_or(u128::MAX);
+        (
+            reward_per_token.saturating_add(Amount::from(increase)),
+            reward_pool.saturating_sub(Amount::from(emitted)),
+        )
+    }
+
+    // 把累加器更新到now
+    pub fn update_rewards(&mut self, now: Timestamp) {
+        let (reward_per_token, reward_pool) = self.accrued(now);
+        self.reward_per_token.set(reward_per_token);
+        self.reward_pool.set(reward_pool);
+        self.last_update.set(now);
+    }
+
+    // 用户到now为止待领取的奖励
+    pub async fn pending_rewards(&self, owner: &Owner, now: Timestamp) -> Amount {
+        let (reward_per_token, _) = self.accrued(now);
+        let info = self.stake_info(owner).await;
+        Self::earned(&info, reward_per_token)
+    }
+
+    // 充值奖励池
+    pub fn fund_rewards(&mut self, amount: Amount, now: Timestamp) {
+        self.update_rewards(now);
+        self.reward_pool.get_mut().saturating_add_assign(amount);
+    }
+
+    // 质押
+    pub async fn stake(&mut self, owner: Owner, amount: Amount, now: Timestamp) -> Result<(), StakingError> {
+        if amount == Amount::default() {
+            return Err(StakingError::ZeroAmount);
+        }
+        let mut info = self.settle(owner, now).await;
+        info.staked.saturating_add_assign(amount);
+        self.total_staked.get_mut().saturating_add_assign(amount);
+        self.save(owner, info);
+        Ok(())
+    }
+
+    // 解除质押，没有解绑期时返回可以立即取回的数量
+    pub async fn unstake(&mut self, owner: Owner, amount: Amount, now: Timestamp) -> Result<Amount, StakingError> {
+        if amount == Amount::default() {
+            return Err(StakingError::ZeroAmount);
+        }
+        let mut info = self.settle(owner, now).await;
+        info.staked.try_sub_assign(amount).map_err(|_| StakingError::InsufficientStake)?;
+        self.total_staked.get_mut().try_sub_assign(amount).map_err(|_| StakingError::InsufficientStake)?;
+        let unbonding_period_micros = self.config.get().unbonding_period_micros;
+        let released = if unbonding_period_micros == 0 {
+            amount
+        } else {
+            info.unbonding.push(Unbonding {
+                amount,
+                release_time: Timestamp::from(now.micros().saturating_add(unbonding_period_micros)),
+            });
+            Amount::default()
+        };
+        self.save(owner, info);
+        Ok(released)
+    }
+
+    // 取出所有解绑期已经结束的代币
+    pub async fn withdraw(&mut self, owner: Owner, now: Timestamp) -> Amount {
+        let mut info = self.stake_info(&owner).await;
+        let mut released = Amount::default();
+        info.unbonding.retain(|unbonding| {
+            if unbonding.release_time <= now {
+                released.saturating_add_assign(unbonding.amount);
+                false
+            } else {
+                true
+            }
+        });
+        self.save(owner, info);
+        released
+    }
+
+    // 领取奖励，返回领取的数量
+    pub async fn claim_rewards(&mut self, owner: Owner, now: Timestamp) -> Amount {
+        let mut info = self.settle(owner, now).await;
+        let rewards = info.rewards;
+        info.rewards = Amount::default();
+        self.save(owner, info);
+        rewards
+    }
+
+    // 更新累加器并把用户的奖励结算到rewards中
+    async fn settle(&mut self, owner: Owner, now: Timestamp) -> StakeInfo {
+        self.update_rewards(now);
+        let reward_per_token = *self.reward_per_token.get();
+        let mut info = self.stake_info(&owner).await;
+        info.rewards = Self::earned(&info, reward_per_token);
+        info.reward_per_token_paid = reward_per_token;
+        info
+    }
+
+    fn earned(info: &StakeInfo, reward_per_token: Amount) -> Amount {
+        let delta = u128::from(reward_per_token.saturating_sub(info.reward_per_token_paid));
+        let earned = mul_div(u128::from(info.staked), delta, REWARD_SCALE).unwrap_or(u128::MAX);
+        info.rewards.saturating_add(Amount::from(earned))
+    }
+
+    // 保存用户的质押信息，什么都没有了就删除
+    fn save(&mut self, owner: Owner, info: StakeInfo) {
+        if info.staked == Amount::default() && info.rewards == Amount::default() && info.unbonding.is_empty() {
+            self.stakes.remove(&owner).expect("删除质押信息失败!");
+        } else {
+            self.stakes.insert(&owner, info).expect("记录质押信息失败!");
+        }
+    }
+}
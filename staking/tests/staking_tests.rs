@@ -0,0 +1,71 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use async_graphql::InputType;
+use linera_sdk::base::{Amount, Owner, Timestamp};
+use linera_sdk::test::TestValidator;
+use fungible::{AccountOwner, FungibleTokenAbi};
+use staking::{OperationOpt, StakingAbi, StakingConfig, StakingParameters};
+use fungible_client::{ActiveChainTransport, FungibleClient};
+
+#[tokio::test]
+async fn test_rewards_accrue_per_second_and_unbond() {
+    let initial_amount = Amount::from(1_000_000u128);
+    let seconds = 1_000_000;
+    // 模拟一个验证节点，当前项目就是质押程序
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode().await;
+    let mut chain = validator.new_chain().await;
+    let owner = Owner::from(chain.public_key());
+    let user = AccountOwner::User(owner);
+    // 质押和奖励用同一个代币
+    let fungible_bytecode_id = chain.publish_bytecodes_in("../fungible").await;
+    let token = chain
+        .create_application::<FungibleTokenAbi>(fungible_bytecode_id, (), initial_amount.into(), vec![])
+        .await;
+    let staking_id = chain
+        .create_application::<StakingAbi>(
+            bytecode_id,
+            StakingParameters { staking_token: token, reward_token: token },
+            StakingConfig { reward_rate: Amount::from(10u128), unbonding_period_micros: 100 * seconds },
+            vec![token.forget_abi()],
+        ).await;
+
+    // 充值1000个奖励，质押100个
+    let start = Timestamp::now();
+    chain.add_block(|block| {
+        block
+            .with_timestamp(start)
+            .with_operation(staking_id, OperationOpt::FundRewards { amount: Amount::from(1_000u128) })
+            .with_operation(staking_id, OperationOpt::Stake { amount: Amount::from(100u128) });
+    }).await;
+    let token_client = FungibleClient::new(ActiveChainTransport::new(&chain, token));
+    assert_eq!(token_client.execute(fungible_client::balance(user)).await.unwrap(), initial_amount.saturating_sub(Amount::from(1_100u128)));
+
+    // 50秒后领取奖励(每秒10个)，并解除全部质押
+    chain.add_block(|block| {
+        block
+            .with_timestamp(Timestamp::from(start.micros() + 50 * seconds))
+            .with_operation(staking_id, OperationOpt::ClaimRewards)
+            .with_operation(staking_id, OperationOpt::Unstake { amount: Amount::from(100u128) });
+    }).await;
+    assert_eq!(token_client.execute(fungible_client::balance(user)).await.unwrap(), initial_amount.saturating_sub(Amount::from(600u128)));
+
+    // 解绑期没结束时取不回来
+    chain.add_block(|block| {
+        block
+            .with_timestamp(Timestamp::from(start.micros() + 100 * seconds))
+            .with_operation(staking_id, OperationOpt::Withdraw);
+    }).await;
+    assert_eq!(token_client.execute(fungible_client::balance(user)).await.unwrap(), initial_amount.saturating_sub(Amount::from(600u128)));
+
+    // 解绑期结束后取回，没有质押之后不再产生奖励
+    chain.add_block(|block| {
+        block
+            .with_timestamp(Timestamp::from(start.micros() + 150 * seconds))
+            .with_operation(staking_id, OperationOpt::Withdraw);
+    }).await;
+    assert_eq!(token_client.execute(fungible_client::balance(user)).await.unwrap(), initial_amount.saturating_sub(Amount::from(500u128)));
+    let query = format!("query {{ pendingRewards(owner: {}) remainingRewards }}", InputType::to_value(&owner));
+    let value = chain.graphql_query(staking_id, query).await;
+    assert_eq!(value["pendingRewards"].as_str().unwrap().parse::<Amount>().unwrap(), Amount::default());
+    assert_eq!(value["remainingRewards"].as_str().unwrap().parse::<Amount>().unwrap(), Amount::from(500u128));
+}